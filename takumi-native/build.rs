@@ -4,6 +4,15 @@ use syn::{Attribute, Fields, GenericArgument, Item, PathArguments, Type};
 fn main() {
     csbindgen::Builder::default()
         .input_extern_file("src/lib.rs")
+        .input_extern_file("src/font.rs")
+        .input_extern_file("src/font_cache.rs")
+        .input_extern_file("src/measure.rs")
+        .input_extern_file("src/node/fit.rs")
+        .input_extern_file("src/node/paint.rs")
+        .input_extern_file("src/template.rs")
+        .input_extern_file("src/handle.rs")
+        .input_extern_file("src/builder.rs")
+        .input_extern_file("src/input.rs")
         .csharp_dll_name("takumi")
         .csharp_namespace("TakumiSharp.Bindings")
         .csharp_class_name("NativeBindings")
@@ -11,6 +20,29 @@ fn main() {
         .unwrap();
 
     generate_csharp_models();
+    emit_takumi_version();
+}
+
+/// Expose the locked takumi version as `TAKUMI_VERSION`, used to invalidate persisted caches.
+fn emit_takumi_version() {
+    let lockfile = ["../Cargo.lock", "Cargo.lock"]
+        .iter()
+        .find_map(|path| fs::read_to_string(path).ok())
+        .unwrap_or_default();
+
+    let version = lockfile
+        .split("[[package]]")
+        .find(|package| package.lines().any(|line| line.trim() == "name = \"takumi\""))
+        .and_then(|package| {
+            package
+                .lines()
+                .find_map(|line| line.trim().strip_prefix("version = "))
+                .map(|version| version.trim_matches('"').to_string())
+        })
+        .unwrap_or_else(|| "unknown".to_string());
+
+    println!("cargo:rustc-env=TAKUMI_VERSION={}", version);
+    println!("cargo:rerun-if-changed=../Cargo.lock");
 }
 
 fn generate_csharp_models() {
@@ -24,13 +56,33 @@ fn generate_csharp_models() {
         for entry in entries.flatten() {
             let path = entry.path();
             if path.extension().map(|e| e == "rs").unwrap_or(false) {
-                generator.parse_file(&path);
+                generator.parse_file(&path, false);
             }
         }
     }
 
-    // Parse style properties from the define_style! macro
-    let style_props = parse_style_properties(&takumi_path.join("src/layout/style/stylesheets.rs"));
+    // Parse the native node layer last, so its nodes replace the upstream ones they extend.
+    // The style module only extends the Style class and is parsed below.
+    let native_style_path = PathBuf::from("src/node/style.rs");
+    if let Ok(entries) = fs::read_dir("src/node") {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().map(|e| e == "rs").unwrap_or(false) {
+                if path != native_style_path {
+                    generator.parse_file(&path, true);
+                }
+                println!("cargo:rerun-if-changed={}", path.display());
+            }
+        }
+    }
+
+    // Parse style properties from the define_style! macro, then the natively resolved ones
+    let base_props = parse_style_properties(&takumi_path.join("src/layout/style/stylesheets.rs"));
+    let native_props = parse_native_style_properties(&native_style_path);
+    generate_style_properties(&base_props, &native_props);
+
+    let mut style_props = base_props;
+    style_props.extend(native_props);
 
     // Generate and write C# output
     let csharp_code = generator.generate(&style_props);
@@ -75,12 +127,21 @@ fn parse_style_properties(path: &PathBuf) -> Vec<StyleProperty> {
         
         let macro_body = &macro_content[14..end_pos]; // Skip "define_style!("
         
-        // Parse each line for property definitions
+        // Parse each line for property definitions, along with the names of their serde attribute
+        let mut serde_names = Vec::new();
         for line in macro_body.lines() {
             let line = line.trim();
             
             // Skip comments and empty lines
-            if line.is_empty() || line.starts_with("//") || line.starts_with('#') {
+            if line.is_empty() || line.starts_with("//") {
+                continue;
+            }
+
+            if line.starts_with("#[serde(") {
+                serde_names = line.split('"').skip(1).step_by(2).map(str::to_string).collect();
+                continue;
+            }
+            if line.starts_with('#') {
                 continue;
             }
             
@@ -105,8 +166,13 @@ fn parse_style_properties(path: &PathBuf) -> Vec<StyleProperty> {
                 let rust_type = rest[..type_end].trim().trim_end_matches(',');
                 
                 if !prop_name.is_empty() && !rust_type.is_empty() {
+                    let json_names = match serde_names.is_empty() {
+                        true => vec![to_camel_case(prop_name)],
+                        false => std::mem::take(&mut serde_names),
+                    };
                     properties.push(StyleProperty {
                         name: prop_name.to_string(),
+                        json_names,
                         rust_type: rust_type.to_string(),
                         csharp_type: style_rust_type_to_csharp(rust_type),
                     });
@@ -118,6 +184,90 @@ fn parse_style_properties(path: &PathBuf) -> Vec<StyleProperty> {
     properties
 }
 
+/// Parse the natively resolved style properties from the fields of the NativeStyle struct
+fn parse_native_style_properties(path: &PathBuf) -> Vec<StyleProperty> {
+    let Ok(content) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    let Ok(file) = syn::parse_file(&content) else {
+        return Vec::new();
+    };
+
+    let mut properties = Vec::new();
+
+    for item in file.items {
+        let Item::Struct(s) = item else {
+            continue;
+        };
+        if s.ident != "NativeStyle" {
+            continue;
+        }
+
+        if let Fields::Named(named) = s.fields {
+            for field in named.named {
+                let rust_type = type_to_string(&field.ty);
+                let name = field.ident.map(|i| i.to_string()).unwrap_or_default();
+                properties.push(StyleProperty {
+                    json_names: vec![to_camel_case(&name)],
+                    name,
+                    csharp_type: style_rust_type_to_csharp(&rust_type),
+                    rust_type,
+                });
+            }
+        }
+    }
+
+    properties
+}
+
+/// Generate the Rust functions that tell the style properties apart by name, included by
+/// src/node/style.rs.
+fn generate_style_properties(base_props: &[StyleProperty], native_props: &[StyleProperty]) {
+    let props = base_props
+        .iter()
+        .map(|prop| (prop, "base"))
+        .chain(native_props.iter().map(|prop| (prop, "native")));
+
+    let mut names = Vec::new();
+    let mut arms = String::new();
+    for (prop, field) in props {
+        let pattern = prop
+            .json_names
+            .iter()
+            .map(|name| format!("{name:?}"))
+            .collect::<Vec<_>>()
+            .join(" | ");
+        arms.push_str(&format!(
+            "            {pattern} => self.{field}.{name} = declared.{field}.{name},\n",
+            name = prop.name
+        ));
+        names.push(pattern);
+    }
+
+    let code = format!(
+        r#"// Generated by build.rs from takumi's define_style! and NativeStyle.
+
+impl Style {{
+    /// Returns whether a style property is known by this name.
+    pub(crate) fn is_property(property: &str) -> bool {{
+        matches!(property, {names})
+    }}
+
+    /// Sets a style property to its value in `declared`.
+    pub(crate) fn set_property(&mut self, property: &str, declared: Style) {{
+        match property {{
+{arms}            _ => {{}}
+        }}
+    }}
+}}
+"#,
+        names = names.join(" | "),
+    );
+
+    let out_dir = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR is not set"));
+    fs::write(out_dir.join("style_properties.rs"), code).expect("Failed to write style_properties.rs");
+}
+
 /// Dynamically maps Rust types to C# types.
 /// Uses pattern matching on type structure, not hardcoded type names.
 fn style_rust_type_to_csharp(rust_ty: &str) -> String {
@@ -155,6 +305,8 @@ fn style_rust_type_to_csharp(rust_ty: &str) -> String {
 #[derive(Debug, Clone)]
 struct StyleProperty {
     name: String,
+    /// The names the property is deserialized from
+    json_names: Vec<String>,
     #[allow(dead_code)]
     rust_type: String,
     csharp_type: String,
@@ -222,6 +374,8 @@ struct SerdeAttrs {
 struct CSharpGenerator {
     structs: HashMap<String, StructDef>,
     enums: HashMap<String, EnumDef>,
+    /// Enums of unit variants, deserialized from their variant names as strings
+    string_enums: Vec<String>,
 }
 
 impl CSharpGenerator {
@@ -229,10 +383,13 @@ impl CSharpGenerator {
         Self {
             structs: HashMap::new(),
             enums: HashMap::new(),
+            string_enums: Vec::new(),
         }
     }
 
-    fn parse_file(&mut self, path: &PathBuf) {
+    /// Parses the nodes of a file. With `include_models`, every other deserializable
+    /// struct is generated too, for types only used as node fields.
+    fn parse_file(&mut self, path: &PathBuf, include_models: bool) {
         let content = match fs::read_to_string(path) {
             Ok(c) => c,
             Err(e) => {
@@ -251,18 +408,24 @@ impl CSharpGenerator {
 
         for item in file.items {
             match item {
-                Item::Struct(s) => self.process_struct(s),
+                Item::Struct(s) => self.process_struct(s, include_models),
                 Item::Enum(e) => self.process_enum(e),
                 _ => {}
             }
         }
     }
 
-    fn process_struct(&mut self, s: syn::ItemStruct) {
+    fn process_struct(&mut self, s: syn::ItemStruct, include_models: bool) {
         let name = s.ident.to_string();
 
         // Process structs that end with "Node" - this is a pattern, not a hardcoded list
-        if !name.ends_with("Node") {
+        let is_model = include_models && derives_deserialize(&s.attrs);
+        if !name.ends_with("Node") && !is_model {
+            return;
+        }
+
+        // Newtypes are declared as the type they wrap.
+        if !matches!(s.fields, Fields::Named(_)) {
             return;
         }
 
@@ -298,6 +461,9 @@ impl CSharpGenerator {
 
         // Process enums that end with "Kind" - this is a pattern for tagged unions
         if !name.ends_with("Kind") {
+            if e.variants.iter().all(|variant| matches!(variant.fields, Fields::Unit)) {
+                self.string_enums.push(name);
+            }
             return;
         }
 
@@ -342,7 +508,10 @@ impl CSharpGenerator {
         output.push_str("namespace TakumiSharp.Models;\n\n");
 
         // Generate all "Kind" enums as polymorphic base classes
-        for (enum_name, enum_def) in &self.enums {
+        let mut enums: Vec<_> = self.enums.iter().collect();
+        enums.sort_by_key(|(name, _)| *name); // Deterministic output order
+
+        for (enum_name, enum_def) in enums {
             // Generate base class name by removing "Kind" suffix
             let base_class_name = enum_name.trim_end_matches("Kind");
             if base_class_name.is_empty() {
@@ -509,11 +678,30 @@ impl CSharpGenerator {
                 return format!("List<{}>?", enum_name);
             }
         }
-        
+
+        // Unit enums are written as the string of their variant name
+        let name = default_csharp.trim_end_matches('?');
+        if self.string_enums.iter().any(|string_enum| string_enum == name) {
+            return default_csharp.replacen(name, "string", 1);
+        }
+
         default_csharp.to_string()
     }
 }
 
+fn derives_deserialize(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        let mut found = false;
+        if attr.path().is_ident("derive") {
+            let _ = attr.parse_nested_meta(|meta| {
+                found |= meta.path.is_ident("Deserialize");
+                Ok(())
+            });
+        }
+        found
+    })
+}
+
 fn parse_serde_attrs(attrs: &[Attribute]) -> SerdeAttrs {
     let mut result = SerdeAttrs::default();
 
@@ -564,7 +752,8 @@ fn type_to_string(ty: &Type) -> String {
                     }
                 })
                 .collect();
-            segments.join("::")
+            // Paths like `takumi::layout::style::Style` map to their last segment
+            segments.last().cloned().unwrap_or_default()
         }
         // Handle slice types like [Nodes] -> represent as [Nodes]
         Type::Slice(slice) => {
@@ -588,12 +777,32 @@ fn rust_type_to_csharp(rust_ty: &str) -> String {
         return format!("List<{}>", inner_csharp.trim_end_matches('?'));
     }
 
+    // Handle Box<T> -> T
+    if let Some(inner) = rust_ty.strip_prefix("Box<").and_then(|s| s.strip_suffix(">")) {
+        return rust_type_to_csharp(inner);
+    }
+
     // Handle Vec<T> -> List<T>
     if let Some(inner) = rust_ty.strip_prefix("Vec<").and_then(|s| s.strip_suffix(">")) {
         let inner_csharp = rust_type_to_csharp(inner);
         return format!("List<{}>", inner_csharp.trim_end_matches('?'));
     }
 
+    // Handle HashMap<K, V> and serde_json's Map<K, V> -> Dictionary<K, V>
+    if let Some(inner) = rust_ty
+        .strip_prefix("HashMap<")
+        .or_else(|| rust_ty.strip_prefix("Map<"))
+        .and_then(|s| s.strip_suffix(">"))
+    {
+        if let Some((key, value)) = inner.split_once(", ") {
+            return format!(
+                "Dictionary<{}, {}>",
+                rust_type_to_csharp(key).trim_end_matches('?'),
+                rust_type_to_csharp(value).trim_end_matches('?')
+            );
+        }
+    }
+
     // Handle Arc<str> -> string
     if rust_ty == "Arc<str>" {
         return "string".to_string();
@@ -610,9 +819,12 @@ fn rust_type_to_csharp(rust_ty: &str) -> String {
         "u32" => "uint".to_string(),
         "u64" => "ulong".to_string(),
         "bool" => "bool".to_string(),
-        // Known model types we generate
-        "Style" => "Style".to_string(),
+        // Known model types we generate. Markdown element styles are declared as styles too.
+        "Style" | "ElementStyle" => "Style".to_string(),
         "TailwindValues" => "TailwindValues".to_string(),
+        // Fragments are declared as nodes, and arbitrary JSON values as objects.
+        "Fragment" => "NodeKind".to_string(),
+        "Value" => "object".to_string(),
         // Everything else - use the Rust type name as-is (will be object for unknown)
         _ => rust_ty.to_string(),
     }
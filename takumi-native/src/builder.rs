@@ -0,0 +1,374 @@
+//! Node trees built call by call, without going through JSON.
+//!
+//! A builder node is created for a container, text or image, styled and given its
+//! children, and finally built into a [`NodeHandle`] that renders like a parsed tree.
+//! Every style declaration and Tailwind class is checked when it is set, so a mistake
+//! fails the call that made it.
+
+use std::ffi::{c_char, CStr};
+
+use serde_json::{Number, Value};
+use takumi::layout::{
+    node::ImageNode,
+    style::tw::{TailwindValue, TailwindValues},
+};
+
+use crate::{
+    handle::NodeHandle,
+    node::{ContainerNode, NativeStyle, NodeKind, Style, TextNode},
+    set_last_error,
+};
+
+/// A node being built, along with its style and children.
+pub struct NodeBuilder {
+    node: NodeKind,
+    style: Option<Style>,
+    children: Vec<NodeBuilder>,
+}
+
+impl NodeBuilder {
+    fn new(node: NodeKind) -> *mut NodeBuilder {
+        Box::into_raw(Box::new(NodeBuilder {
+            node,
+            style: None,
+            children: Vec::new(),
+        }))
+    }
+
+    fn set_style(&mut self, property: &str, value: Value) -> Result<(), String> {
+        match Style::parse_declaration(property, value) {
+            Ok(Some(declared)) => {
+                // Images are laid out by takumi alone, which has no use for native properties.
+                if matches!(self.node, NodeKind::Image(_)) && declared.native != NativeStyle::default() {
+                    return Err(format!("style property \"{property}\" is not supported on image nodes"));
+                }
+
+                self.style.get_or_insert_default().set_property(property, declared);
+                Ok(())
+            }
+            Ok(None) => Err(format!("unsupported style property \"{property}\"")),
+            Err(e) => Err(format!("invalid value for style property \"{property}\": {e}")),
+        }
+    }
+
+    fn tw_mut(&mut self) -> &mut Option<TailwindValues> {
+        match &mut self.node {
+            NodeKind::Container(container) => &mut container.tw,
+            NodeKind::Text(text) => &mut text.tw,
+            NodeKind::Image(image) => &mut image.tw,
+            _ => unreachable!("builders only create containers, text and images"),
+        }
+    }
+
+    /// Builds the node and its children, giving each one the style declared on it.
+    fn build(self) -> Result<NodeKind, String> {
+        let style = self.style;
+        let mut node = self.node;
+        match &mut node {
+            NodeKind::Container(container) => {
+                let children = self
+                    .children
+                    .into_iter()
+                    .map(NodeBuilder::build)
+                    .collect::<Result<Vec<_>, String>>()?;
+
+                container.style = style;
+                container.children = Some(children.into_boxed_slice());
+            }
+            NodeKind::Text(text) => text.style = style,
+            NodeKind::Image(image) => image.style = style.map(|style| style.base),
+            _ => unreachable!("builders only create containers, text and images"),
+        }
+
+        Ok(node)
+    }
+}
+
+unsafe fn c_str<'a>(ptr: *const c_char, name: &str) -> Result<&'a str, String> {
+    if ptr.is_null() {
+        return Err(format!("{name} is null"));
+    }
+
+    unsafe { CStr::from_ptr(ptr) }.to_str().map_err(|e| e.to_string())
+}
+
+/// Creates a builder for an empty container node.
+///
+/// The builder must be appended to a container, built with [`takumi_builder_build`] or
+/// released with [`takumi_builder_free`].
+#[no_mangle]
+pub extern "C" fn takumi_builder_new_container() -> *mut NodeBuilder {
+    NodeBuilder::new(NodeKind::Container(ContainerNode {
+        preset: None,
+        style: None,
+        children: None,
+        tw: None,
+        lang: None,
+        fragments: None,
+    }))
+}
+
+/// Creates a builder for a text node, or returns null on failure.
+///
+/// # Safety
+///
+/// - `text` must be a valid null-terminated C string pointer.
+#[no_mangle]
+pub unsafe extern "C" fn takumi_builder_new_text(text: *const c_char) -> *mut NodeBuilder {
+    let text = match unsafe { c_str(text, "text") } {
+        Ok(text) => text,
+        Err(e) => {
+            set_last_error(e);
+            return std::ptr::null_mut();
+        }
+    };
+
+    NodeBuilder::new(NodeKind::Text(TextNode {
+        preset: None,
+        style: None,
+        text: text.to_owned(),
+        spans: None,
+        tw: None,
+        fit: None,
+        lang: None,
+    }))
+}
+
+/// Creates a builder for an image node, or returns null on failure.
+///
+/// `width` and `height` are in pixels, or negative to use the size of the image.
+///
+/// # Safety
+///
+/// - `src` must be a valid null-terminated C string pointer.
+#[no_mangle]
+pub unsafe extern "C" fn takumi_builder_new_image(src: *const c_char, width: f32, height: f32) -> *mut NodeBuilder {
+    let src = match unsafe { c_str(src, "src") } {
+        Ok(src) => src,
+        Err(e) => {
+            set_last_error(e);
+            return std::ptr::null_mut();
+        }
+    };
+
+    NodeBuilder::new(NodeKind::Image(ImageNode {
+        preset: None,
+        style: None,
+        src: src.into(),
+        width: (width >= 0.0).then_some(width),
+        height: (height >= 0.0).then_some(height),
+        tw: None,
+    }))
+}
+
+/// Sets a style property of a node to a CSS value, like `"fontSize"` to `"2rem"`.
+///
+/// The property is named like in a node's JSON style. Fails when takumi does not know the
+/// property or cannot parse the value, or for a property resolved natively, like
+/// `"writingMode"`, on an image.
+///
+/// # Safety
+///
+/// - `builder` must be a builder that was not built, appended or released yet.
+/// - `property` and `value` must be valid null-terminated C string pointers.
+#[no_mangle]
+pub unsafe extern "C" fn takumi_builder_set_style(
+    builder: *mut NodeBuilder,
+    property: *const c_char,
+    value: *const c_char,
+) -> bool {
+    let Some(builder) = (unsafe { builder.as_mut() }) else {
+        set_last_error("builder is null");
+        return false;
+    };
+
+    let result = unsafe { c_str(property, "property") }.and_then(|property| {
+        let value = unsafe { c_str(value, "value") }?;
+        builder.set_style(property, Value::String(value.to_owned()))
+    });
+
+    match result {
+        Ok(()) => true,
+        Err(e) => {
+            set_last_error(e);
+            false
+        }
+    }
+}
+
+/// Sets a style property of a node to a number, like `"flexGrow"` to `1`. Lengths given
+/// as a number are in pixels.
+///
+/// # Safety
+///
+/// - `builder` must be a builder that was not built, appended or released yet.
+/// - `property` must be a valid null-terminated C string pointer.
+#[no_mangle]
+pub unsafe extern "C" fn takumi_builder_set_style_number(
+    builder: *mut NodeBuilder,
+    property: *const c_char,
+    value: f32,
+) -> bool {
+    let Some(builder) = (unsafe { builder.as_mut() }) else {
+        set_last_error("builder is null");
+        return false;
+    };
+
+    let result = unsafe { c_str(property, "property") }.and_then(|property| {
+        let Some(value) = Number::from_f64(value as f64) else {
+            return Err(format!("invalid value for style property \"{property}\": {value}"));
+        };
+        builder.set_style(property, Value::Number(value))
+    });
+
+    match result {
+        Ok(()) => true,
+        Err(e) => {
+            set_last_error(e);
+            false
+        }
+    }
+}
+
+/// Sets the Tailwind classes of a node, replacing the ones set before. Fails for a class
+/// takumi does not know.
+///
+/// # Safety
+///
+/// - `builder` must be a builder that was not built, appended or released yet.
+/// - `tw` must be a valid null-terminated C string pointer.
+#[no_mangle]
+pub unsafe extern "C" fn takumi_builder_set_tw(builder: *mut NodeBuilder, tw: *const c_char) -> bool {
+    let Some(builder) = (unsafe { builder.as_mut() }) else {
+        set_last_error("builder is null");
+        return false;
+    };
+
+    let tw = unsafe { c_str(tw, "tw") }.and_then(|tw| {
+        // Parsing skips unknown classes, so each one is checked on its own first.
+        match tw
+            .split_whitespace()
+            .find(|class| TailwindValue::parse(class).is_none())
+        {
+            Some(class) => Err(format!("unknown tailwind class \"{class}\"")),
+            None => tw.parse(),
+        }
+    });
+
+    match tw {
+        Ok(tw) => {
+            *builder.tw_mut() = Some(tw);
+            true
+        }
+        Err(e) => {
+            set_last_error(e);
+            false
+        }
+    }
+}
+
+/// Appends a node as the last child of a container.
+///
+/// On success the parent owns the child, and the child's builder must not be used again.
+/// On failure, when the parent is not a container, the child stays with the caller.
+///
+/// # Safety
+///
+/// - `parent` and `child` must be distinct builders that were not built, appended or released yet.
+#[no_mangle]
+pub unsafe extern "C" fn takumi_builder_append_child(parent: *mut NodeBuilder, child: *mut NodeBuilder) -> bool {
+    let Some(parent) = (unsafe { parent.as_mut() }) else {
+        set_last_error("parent is null");
+        return false;
+    };
+
+    if child.is_null() {
+        set_last_error("child is null");
+        return false;
+    }
+
+    if !matches!(parent.node, NodeKind::Container(_)) {
+        set_last_error("only container nodes can have children");
+        return false;
+    }
+
+    parent.children.push(*unsafe { Box::from_raw(child) });
+    true
+}
+
+/// Builds a node and its children into a handle rendered like a parsed tree, or returns
+/// null on failure.
+///
+/// The builder is consumed either way. The handle must be released with
+/// [`crate::handle::takumi_node_free`].
+///
+/// # Safety
+///
+/// - `builder` must be a builder that was not built, appended or released yet.
+#[no_mangle]
+pub unsafe extern "C" fn takumi_builder_build(builder: *mut NodeBuilder) -> *mut NodeHandle {
+    if builder.is_null() {
+        set_last_error("builder is null");
+        return std::ptr::null_mut();
+    }
+
+    match unsafe { Box::from_raw(builder) }.build().and_then(NodeHandle::new) {
+        Ok(handle) => handle,
+        Err(e) => {
+            set_last_error(e);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Releases a builder that was not built or appended, along with its children. Does
+/// nothing when `builder` is null.
+///
+/// # Safety
+///
+/// - `builder` must be null or a builder that was not built, appended or released yet.
+#[no_mangle]
+pub unsafe extern "C" fn takumi_builder_free(builder: *mut NodeBuilder) {
+    if !builder.is_null() {
+        drop(unsafe { Box::from_raw(builder) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn builder(node: *mut NodeBuilder) -> Box<NodeBuilder> {
+        unsafe { Box::from_raw(node) }
+    }
+
+    #[test]
+    fn native_properties_are_rejected_on_images() {
+        let mut image = builder(unsafe { takumi_builder_new_image(c"logo.png".as_ptr(), 32.0, 32.0) });
+
+        assert!(image.set_style("width", json!(64)).is_ok());
+        assert_eq!(
+            image.set_style("writingMode", json!("vertical-rl")).unwrap_err(),
+            "style property \"writingMode\" is not supported on image nodes"
+        );
+
+        let mut text = builder(unsafe { takumi_builder_new_text(c"Hello".as_ptr()) });
+        assert!(text.set_style("writingMode", json!("vertical-rl")).is_ok());
+    }
+
+    #[test]
+    fn unknown_properties_and_invalid_values_fail() {
+        let mut container = builder(takumi_builder_new_container());
+
+        assert_eq!(
+            container.set_style("fontSzie", json!(12)).unwrap_err(),
+            "unsupported style property \"fontSzie\""
+        );
+        assert!(container
+            .set_style("display", json!("sideways"))
+            .unwrap_err()
+            .starts_with("invalid value for style property \"display\""));
+    }
+}
@@ -0,0 +1,466 @@
+use std::{
+    borrow::Cow,
+    ffi::CStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use takumi::{
+    parley::fontique::{Blob, FallbackKey, FamilyId, FontInfo, Script},
+    resources::font::{load_font, FontContext, FontError},
+    GlobalContext,
+};
+
+use crate::{set_last_error, GLOBAL_CONTEXT};
+
+/// A font source loaded through the FFI, tracked so it can be unloaded or persisted later.
+pub(crate) struct LoadedFont {
+    /// The face ID handed back to the caller.
+    pub(crate) id: u64,
+    /// The decompressed font data.
+    pub(crate) data: Blob<u8>,
+    /// Every face registered from this source, keyed by its family.
+    pub(crate) faces: Vec<(FamilyId, FontInfo)>,
+}
+
+static mut LOADED_FONTS: Vec<LoadedFont> = Vec::new();
+static mut NEXT_FACE_ID: u64 = 1;
+
+/// The names of the families registered from loaded fonts.
+static mut FAMILY_NAMES: Vec<(FamilyId, String)> = Vec::new();
+
+/// Bumped whenever fonts are loaded into or unloaded from the global context, or the fonts
+/// of a language change, so copies of its font context and trees lowered with it know when
+/// they are out of date.
+static FONT_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Font families text in a language falls back to, keyed by lowercase BCP 47 tag.
+static mut LANGUAGE_FALLBACKS: Vec<(String, String)> = Vec::new();
+
+/// Fira Sans Regular, licensed under the SIL Open Font License 1.1.
+#[cfg(feature = "fallback-font")]
+const FALLBACK_FONT: &[u8] = include_bytes!("../assets/fonts/FiraSans-Regular.woff2");
+
+/// The family name of the built-in fallback font.
+#[cfg(feature = "fallback-font")]
+const FALLBACK_FONT_FAMILY: &str = "Fira Sans";
+
+/// The family of the built-in fallback font in the current font context.
+#[cfg(feature = "fallback-font")]
+static mut FALLBACK_FAMILY: Option<FamilyId> = None;
+
+/// Creates an empty global context, with the built-in fallback font registered when enabled.
+pub(crate) fn create_global_context() -> GlobalContext {
+    GlobalContext {
+        font_context: create_font_context(),
+        ..Default::default()
+    }
+}
+
+/// Creates an empty font context, with the built-in fallback font registered when enabled.
+pub(crate) fn create_font_context() -> FontContext {
+    #[allow(unused_mut)]
+    let mut font_context = FontContext::default();
+
+    #[cfg(feature = "fallback-font")]
+    if let Ok(font_data) = load_font(FALLBACK_FONT, None) {
+        let collection = &mut font_context.collection;
+        let families = collection.register_fonts(Blob::new(Arc::new(font_data.into_owned())), None);
+        let family = families.first().map(|(family, _)| *family);
+
+        if let Some(family) = family {
+            for (script, _) in Script::all_samples() {
+                collection.append_fallbacks(FallbackKey::new(*script, None), std::iter::once(family));
+            }
+        }
+
+        unsafe { *std::ptr::addr_of_mut!(FALLBACK_FAMILY) = family };
+    }
+
+    FONT_GENERATION.fetch_add(1, Ordering::Release);
+    font_context
+}
+
+/// Returns the generation of the global font context, which changes whenever its fonts do.
+pub(crate) fn font_generation() -> u64 {
+    FONT_GENERATION.load(Ordering::Acquire)
+}
+
+/// Rebuilds the fallback families of every script from the fonts currently loaded: every
+/// loaded family in load order, then the built-in fallback font.
+///
+/// The lists are replaced rather than appended to, so loading the same family again or
+/// unloading it never leaves stale or duplicate entries behind.
+unsafe fn rebuild_fallbacks(collection: &mut takumi::parley::fontique::Collection) {
+    let mut families: Vec<FamilyId> = Vec::new();
+    for (family, _) in unsafe { loaded_fonts() }.iter().flat_map(|loaded| &loaded.faces) {
+        if !families.contains(family) {
+            families.push(*family);
+        }
+    }
+
+    #[cfg(feature = "fallback-font")]
+    families.extend(unsafe { *std::ptr::addr_of!(FALLBACK_FAMILY) });
+
+    for (script, _) in Script::all_samples() {
+        collection.set_fallbacks(FallbackKey::new(*script, None), families.iter().copied());
+    }
+}
+
+/// Loads font data into the global context and returns the face ID assigned to it.
+///
+/// Mirrors [`takumi::resources::font::FontContext::load_and_store`], but keeps
+/// the registered faces around so they can be unloaded again.
+pub(crate) unsafe fn load_and_store(global: &mut GlobalContext, source: &[u8]) -> Result<u64, FontError> {
+    let font_data = Blob::new(Arc::new(match load_font(source, None)? {
+        Cow::Owned(vec) => vec,
+        Cow::Borrowed(slice) => slice.to_vec(),
+    }));
+
+    Ok(unsafe { store(global, font_data) })
+}
+
+/// Registers already decompressed font data into the global context and returns its face ID.
+pub(crate) unsafe fn store(global: &mut GlobalContext, font_data: Blob<u8>) -> u64 {
+    FONT_GENERATION.fetch_add(1, Ordering::Release);
+    let collection = &mut global.font_context.collection;
+    let family_names = unsafe { &mut *std::ptr::addr_of_mut!(FAMILY_NAMES) };
+    let mut faces = Vec::new();
+
+    for (family, fonts) in collection.register_fonts(font_data.clone(), None) {
+        if let Some(name) = collection.family_name(family) {
+            if !family_names.iter().any(|(id, _)| *id == family) {
+                family_names.push((family, name.to_string()));
+            }
+        }
+
+        faces.extend(fonts.into_iter().map(|font| (family, font)));
+    }
+
+    let loaded_fonts = unsafe { &mut *std::ptr::addr_of_mut!(LOADED_FONTS) };
+    let next_face_id = unsafe { &mut *std::ptr::addr_of_mut!(NEXT_FACE_ID) };
+
+    let id = *next_face_id;
+    *next_face_id += 1;
+    loaded_fonts.push(LoadedFont {
+        id,
+        data: font_data,
+        faces,
+    });
+
+    unsafe { rebuild_fallbacks(collection) };
+    id
+}
+
+/// Returns every font source currently tracked in the global context.
+pub(crate) unsafe fn loaded_fonts() -> &'static [LoadedFont] {
+    unsafe { &*std::ptr::addr_of!(LOADED_FONTS) }
+}
+
+/// Rebuilds the font context from the faces still tracked, once faces are no longer.
+///
+/// fontique only unregisters faces by family, width, style and weight, which would also
+/// drop a face with the same attributes loaded from another source, e.g. the new version
+/// of a font replacing an old one. So every tracked source is registered again instead,
+/// dropping the families it no longer tracks.
+unsafe fn rebuild_font_context(global: &mut GlobalContext) {
+    let family_names = unsafe { &mut *std::ptr::addr_of_mut!(FAMILY_NAMES) };
+    let loaded_fonts = unsafe { &mut *std::ptr::addr_of_mut!(LOADED_FONTS) };
+
+    global.font_context = create_font_context();
+    let collection = &mut global.font_context.collection;
+    let mut new_family_names: Vec<(FamilyId, String)> = Vec::new();
+
+    for loaded in loaded_fonts.iter_mut() {
+        let tracked: Vec<&str> = loaded
+            .faces
+            .iter()
+            .filter_map(|(family, _)| family_names.iter().find(|(id, _)| id == family))
+            .map(|(_, name)| name.as_str())
+            .collect();
+        let mut faces = Vec::new();
+
+        for (family, fonts) in collection.register_fonts(loaded.data.clone(), None) {
+            let name = collection.family_name(family).map(str::to_string);
+            match name.filter(|name| tracked.contains(&name.as_str())) {
+                Some(name) => {
+                    if !new_family_names.iter().any(|(id, _)| *id == family) {
+                        new_family_names.push((family, name));
+                    }
+                    faces.extend(fonts.into_iter().map(|font| (family, font)));
+                }
+                // Sources loaded earlier stopped tracking this family at the same time, so
+                // only their faces are dropped along with these.
+                None => {
+                    for font in fonts {
+                        collection.unregister_font(family, font.width(), font.style(), font.weight());
+                    }
+                }
+            }
+        }
+
+        loaded.faces = faces;
+    }
+
+    *family_names = new_family_names;
+    unsafe { rebuild_fallbacks(collection) };
+}
+
+/// Returns the names of the families text falls back to, in fallback order: every loaded
+/// family in load order, then the built-in fallback font.
+pub(crate) unsafe fn fallback_family_names() -> Vec<&'static str> {
+    let family_names = unsafe { &*std::ptr::addr_of!(FAMILY_NAMES) };
+    let mut names: Vec<&'static str> = Vec::new();
+
+    for (family, _) in unsafe { loaded_fonts() }.iter().flat_map(|loaded| &loaded.faces) {
+        if let Some((_, name)) = family_names.iter().find(|(id, _)| id == family) {
+            if !names.contains(&name.as_str()) {
+                names.push(name);
+            }
+        }
+    }
+
+    #[cfg(feature = "fallback-font")]
+    names.push(FALLBACK_FONT_FAMILY);
+
+    names
+}
+
+/// Drops every tracked font source, without touching the font collection itself.
+pub(crate) unsafe fn forget_loaded_fonts() {
+    unsafe { &mut *std::ptr::addr_of_mut!(LOADED_FONTS) }.clear();
+    unsafe { &mut *std::ptr::addr_of_mut!(FAMILY_NAMES) }.clear();
+}
+
+/// Returns the fallback font families registered for a BCP 47 language tag.
+///
+/// Falls back to less specific tags, e.g. from `zh-Hant-TW` to `zh-Hant` and `zh`.
+pub(crate) unsafe fn language_fallback(lang: &str) -> Option<&'static str> {
+    let fallbacks = unsafe { &*std::ptr::addr_of!(LANGUAGE_FALLBACKS) };
+    let mut tag = normalize_language_tag(lang);
+
+    loop {
+        if let Some((_, families)) = fallbacks.iter().find(|(fallback_tag, _)| *fallback_tag == tag) {
+            return Some(families);
+        }
+
+        tag.truncate(tag.rfind('-')?);
+    }
+}
+
+/// Drops every registered language fallback.
+pub(crate) unsafe fn forget_language_fallbacks() {
+    unsafe { &mut *std::ptr::addr_of_mut!(LANGUAGE_FALLBACKS) }.clear();
+}
+
+fn normalize_language_tag(lang: &str) -> String {
+    lang.trim().to_ascii_lowercase().replace('_', "-")
+}
+
+/// Loads font data into the global font context and returns its face ID.
+///
+/// Every face contained in the data (e.g. all fonts of a collection) shares the
+/// returned ID, which can later be passed to [`global_font_context_unload_face`].
+/// Returns 0 on failure.
+///
+/// # Safety
+///
+/// - `data` must be a valid pointer to a byte array of at least `len` bytes.
+/// - The data must remain valid for the duration of this call.
+#[no_mangle]
+pub unsafe extern "C" fn global_font_context_load_and_store_face(data: *const u8, len: usize) -> u64 {
+    if data.is_null() {
+        set_last_error("data is null");
+        return 0;
+    }
+
+    let data_arr = unsafe { std::slice::from_raw_parts(data, len) };
+    match unsafe { load_and_store(&mut *std::ptr::addr_of_mut!(GLOBAL_CONTEXT), data_arr) } {
+        Ok(id) => id,
+        Err(e) => {
+            set_last_error(e);
+            0
+        }
+    }
+}
+
+/// Unloads every face registered under the given face ID.
+///
+/// # Safety
+///
+/// This function must not be called concurrently with rendering or other font context calls.
+#[no_mangle]
+pub unsafe extern "C" fn global_font_context_unload_face(face_id: u64) -> bool {
+    let loaded_fonts = unsafe { &mut *std::ptr::addr_of_mut!(LOADED_FONTS) };
+
+    let Some(index) = loaded_fonts.iter().position(|font| font.id == face_id) else {
+        set_last_error(format!("No font loaded with face ID {face_id}"));
+        return false;
+    };
+
+    loaded_fonts.remove(index);
+    unsafe { rebuild_font_context(&mut *std::ptr::addr_of_mut!(GLOBAL_CONTEXT)) };
+    true
+}
+
+/// Unloads every face of the given font family that was loaded through
+/// [`global_font_context_load_and_store_face`]. Returns false when there is none, e.g. for
+/// the built-in fallback font.
+///
+/// # Safety
+///
+/// - `family` must be a valid null-terminated C string pointer.
+/// - This function must not be called concurrently with rendering or other font context calls.
+#[no_mangle]
+pub unsafe extern "C" fn global_font_context_unload_family(family: *const std::ffi::c_char) -> bool {
+    if family.is_null() {
+        set_last_error("family is null");
+        return false;
+    }
+
+    let family = match CStr::from_ptr(family).to_str() {
+        Ok(s) => s,
+        Err(e) => {
+            set_last_error(e);
+            return false;
+        }
+    };
+
+    let family_names = unsafe { &*std::ptr::addr_of!(FAMILY_NAMES) };
+    let Some(&(family_id, _)) = family_names.iter().find(|(_, name)| name.eq_ignore_ascii_case(family)) else {
+        set_last_error(format!("No loaded font family named {family}"));
+        return false;
+    };
+
+    let loaded_fonts = unsafe { &mut *std::ptr::addr_of_mut!(LOADED_FONTS) };
+    for loaded in loaded_fonts.iter_mut() {
+        loaded.faces.retain(|(family, _)| *family != family_id);
+    }
+    loaded_fonts.retain(|loaded| !loaded.faces.is_empty());
+
+    unsafe { rebuild_font_context(&mut *std::ptr::addr_of_mut!(GLOBAL_CONTEXT)) };
+    true
+}
+
+/// Unloads every font from the global font context.
+///
+/// The built-in fallback font, if enabled, is registered again afterwards.
+///
+/// # Safety
+///
+/// This function must not be called concurrently with rendering or other font context calls.
+#[no_mangle]
+pub unsafe extern "C" fn global_font_context_clear() {
+    let global = unsafe { &mut *std::ptr::addr_of_mut!(GLOBAL_CONTEXT) };
+    global.font_context = create_font_context();
+    unsafe { forget_loaded_fonts() };
+}
+
+/// Sets the font families text in a language falls back to when the font families in
+/// its style lack a glyph, e.g. `"Noto Sans JP"` for `ja`.
+///
+/// `families` is a comma-separated list like the `fontFamily` style property. Passing
+/// null or an empty string removes the fallback for the language.
+///
+/// # Safety
+///
+/// - `lang` must be a valid null-terminated C string pointer.
+/// - `families` must be null or a valid null-terminated C string pointer.
+/// - This function must not be called concurrently with rendering or other font context calls.
+#[no_mangle]
+pub unsafe extern "C" fn global_font_context_set_language_fallback(
+    lang: *const std::ffi::c_char,
+    families: *const std::ffi::c_char,
+) -> bool {
+    if lang.is_null() {
+        set_last_error("lang is null");
+        return false;
+    }
+
+    let lang = match CStr::from_ptr(lang).to_str() {
+        Ok(s) => normalize_language_tag(s),
+        Err(e) => {
+            set_last_error(e);
+            return false;
+        }
+    };
+
+    let families = if families.is_null() {
+        ""
+    } else {
+        match CStr::from_ptr(families).to_str() {
+            Ok(s) => s.trim(),
+            Err(e) => {
+                set_last_error(e);
+                return false;
+            }
+        }
+    };
+
+    let fallbacks = unsafe { &mut *std::ptr::addr_of_mut!(LANGUAGE_FALLBACKS) };
+    fallbacks.retain(|(fallback_lang, _)| *fallback_lang != lang);
+
+    if !families.is_empty() {
+        fallbacks.push((lang, families.to_string()));
+    }
+
+    FONT_GENERATION.fetch_add(1, Ordering::Release);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FONT: &[u8] = include_bytes!("../../font.ttf");
+
+    /// Returns whether a family has faces in the global context.
+    unsafe fn resolves(family: &str) -> bool {
+        let global = unsafe { &mut *std::ptr::addr_of_mut!(GLOBAL_CONTEXT) };
+        global
+            .font_context
+            .collection
+            .family_by_name(family)
+            .is_some_and(|family| !family.fonts().is_empty())
+    }
+
+    unsafe fn family_of(face_id: u64) -> String {
+        let family_names = unsafe { &*std::ptr::addr_of!(FAMILY_NAMES) };
+        let loaded = unsafe { loaded_fonts() }
+            .iter()
+            .find(|loaded| loaded.id == face_id)
+            .unwrap();
+        let (family, _) = loaded.faces[0];
+        family_names.iter().find(|(id, _)| *id == family).unwrap().1.clone()
+    }
+
+    // The global font context is shared, so the steps run in one test rather than in parallel.
+    #[test]
+    fn unloading_tracks_faces_by_source() {
+        unsafe {
+            // Unloading an old version of a font keeps the new one with the same family and weight.
+            let v1 = global_font_context_load_and_store_face(FONT.as_ptr(), FONT.len());
+            let v2 = global_font_context_load_and_store_face(FONT.as_ptr(), FONT.len());
+            let family = family_of(v2);
+
+            assert!(global_font_context_unload_face(v1));
+            assert!(resolves(&family));
+            assert_eq!(family_of(v2), family);
+
+            assert!(global_font_context_unload_face(v2));
+            assert!(!resolves(&family));
+            assert!(!global_font_context_unload_face(v2));
+
+            // Only families of loaded fonts can be unloaded.
+            let v3 = global_font_context_load_and_store_face(FONT.as_ptr(), FONT.len());
+            let name = std::ffi::CString::new(family_of(v3)).unwrap();
+            assert!(global_font_context_unload_family(name.as_ptr()));
+            assert!(!resolves(&family));
+            assert!(loaded_fonts().is_empty());
+            assert!(!global_font_context_unload_family(name.as_ptr()));
+            assert!(!global_font_context_unload_family(c"Fira Sans".as_ptr()));
+        }
+    }
+}
@@ -0,0 +1,258 @@
+//! Persisting the font context to a single cache file.
+//!
+//! The cache file starts with the `TKFC` magic, followed by the length of a JSON
+//! header (little-endian `u32`), the header itself and finally the decompressed
+//! font data of every loaded font, back to back. Restoring memory-maps the file
+//! and registers the font data straight from the mapping.
+//!
+//! Only the decompression of the font data (e.g. WOFF2) is cached, not the index of
+//! families and faces built from it. fontique can only register fonts by scanning their
+//! data and offers no way to register faces from metadata indexed earlier, so restoring
+//! still reads the tables of every face to index it, which is cheap next to decompressing
+//! it. Caching the index would take a fork of fontique and is left out on purpose.
+
+use std::{
+    ffi::CStr,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    ops::Range,
+    sync::Arc,
+};
+
+use memmap2::Mmap;
+use serde::{Deserialize, Serialize};
+use takumi::parley::fontique::Blob;
+
+use crate::{font, set_last_error, GLOBAL_CONTEXT};
+
+const CACHE_MAGIC: &[u8; 4] = b"TKFC";
+
+/// Bumped whenever the layout of the cache file changes.
+const CACHE_FORMAT_VERSION: u32 = 2;
+
+/// The version of `takumi` the native library was built against.
+const TAKUMI_VERSION: &str = env!("TAKUMI_VERSION");
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CacheHeader {
+    format_version: u32,
+    takumi_version: String,
+    fonts: Vec<CachedFont>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CachedFont {
+    /// Offset of the font data, relative to the end of the header.
+    offset: u64,
+    /// Length of the font data in bytes.
+    len: u64,
+}
+
+/// A font stored inside a memory-mapped cache file.
+struct MappedFont {
+    map: Arc<Mmap>,
+    range: Range<usize>,
+}
+
+impl AsRef<[u8]> for MappedFont {
+    fn as_ref(&self) -> &[u8] {
+        &self.map[self.range.clone()]
+    }
+}
+
+unsafe fn save(path: &str) -> Result<(), String> {
+    let loaded_fonts = unsafe { font::loaded_fonts() };
+
+    let mut offset = 0;
+    let mut fonts = Vec::with_capacity(loaded_fonts.len());
+
+    for loaded in loaded_fonts {
+        let len = loaded.data.len() as u64;
+        fonts.push(CachedFont { offset, len });
+        offset += len;
+    }
+
+    let header = serde_json::to_vec(&CacheHeader {
+        format_version: CACHE_FORMAT_VERSION,
+        takumi_version: TAKUMI_VERSION.to_string(),
+        fonts,
+    })
+    .map_err(|e| e.to_string())?;
+
+    // Fonts restored from a cache file are still mapped from it, so the file is written
+    // next to it and then renamed over it instead of being truncated.
+    let temp_path = format!("{path}.{}.tmp", std::process::id());
+    let written = write_cache(&temp_path, &header, loaded_fonts).and_then(|()| fs::rename(&temp_path, path));
+
+    written.map_err(|e| {
+        let _ = fs::remove_file(&temp_path);
+        e.to_string()
+    })
+}
+
+fn write_cache(path: &str, header: &[u8], loaded_fonts: &[font::LoadedFont]) -> std::io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+
+    writer.write_all(CACHE_MAGIC)?;
+    writer.write_all(&(header.len() as u32).to_le_bytes())?;
+    writer.write_all(header)?;
+
+    for loaded in loaded_fonts {
+        writer.write_all(loaded.data.data())?;
+    }
+
+    writer.into_inner().map_err(|e| e.into_error())?.sync_all()
+}
+
+/// Restores the fonts of a cache file and returns their face IDs, in the order they were saved.
+///
+/// Nothing is restored if the file holds more fonts than `max_fonts`; the error then
+/// comes with the number of fonts in the file.
+unsafe fn restore(path: &str, max_fonts: usize) -> Result<Vec<u64>, (String, Option<usize>)> {
+    let file = File::open(path).map_err(|e| (e.to_string(), None))?;
+    // `save` replaces the cache file by renaming a new one over it, never modifying it in
+    // place, so the mapping stays valid.
+    let map = Arc::new(unsafe { Mmap::map(&file) }.map_err(|e| (e.to_string(), None))?);
+    let (header, data_start) = read_header(&map).map_err(|e| (e, None))?;
+
+    if header.fonts.len() > max_fonts {
+        return Err(("Buffer too small".to_string(), Some(header.fonts.len())));
+    }
+
+    let mut ranges = Vec::with_capacity(header.fonts.len());
+    for cached in &header.fonts {
+        let range = usize::try_from(cached.offset)
+            .ok()
+            .and_then(|offset| data_start.checked_add(offset))
+            .and_then(|start| Some(start..start.checked_add(usize::try_from(cached.len).ok()?)?))
+            .filter(|range| range.end <= map.len())
+            .ok_or(("Font cache data is truncated".to_string(), None))?;
+        ranges.push(range);
+    }
+
+    let global = unsafe { &mut *std::ptr::addr_of_mut!(GLOBAL_CONTEXT) };
+    let face_ids = ranges
+        .into_iter()
+        .map(|range| {
+            let data = Blob::new(Arc::new(MappedFont {
+                map: map.clone(),
+                range,
+            }));
+            unsafe { font::store(global, data) }
+        })
+        .collect();
+
+    Ok(face_ids)
+}
+
+/// Reads the header of a cache file, returning it with the offset the font data starts at.
+fn read_header(map: &[u8]) -> Result<(CacheHeader, usize), String> {
+    if map.len() < 8 || &map[..4] != CACHE_MAGIC {
+        return Err("Not a font cache file".to_string());
+    }
+
+    let header_len = u32::from_le_bytes([map[4], map[5], map[6], map[7]]) as usize;
+    let header_bytes = 8usize
+        .checked_add(header_len)
+        .and_then(|data_start| map.get(8..data_start))
+        .ok_or("Font cache header is truncated")?;
+    let header: CacheHeader = serde_json::from_slice(header_bytes).map_err(|e| e.to_string())?;
+
+    if header.format_version != CACHE_FORMAT_VERSION || header.takumi_version != TAKUMI_VERSION {
+        return Err(format!(
+            "Font cache is stale: written by takumi {} (format {}), expected takumi {} (format {})",
+            header.takumi_version, header.format_version, TAKUMI_VERSION, CACHE_FORMAT_VERSION
+        ));
+    }
+
+    Ok((header, 8 + header_bytes.len()))
+}
+
+/// Writes every font loaded into the global font context to a cache file.
+///
+/// # Safety
+///
+/// - `path` must be a valid null-terminated C string pointer.
+/// - This function must not be called concurrently with other font context calls.
+#[no_mangle]
+pub unsafe extern "C" fn global_font_context_save_cache(path: *const std::ffi::c_char) -> bool {
+    if path.is_null() {
+        set_last_error("path is null");
+        return false;
+    }
+
+    let path = match CStr::from_ptr(path).to_str() {
+        Ok(s) => s,
+        Err(e) => {
+            set_last_error(e);
+            return false;
+        }
+    };
+
+    if let Err(e) = unsafe { save(path) } {
+        set_last_error(e);
+        return false;
+    }
+    true
+}
+
+/// Restores fonts from a cache file written by [`global_font_context_save_cache`].
+///
+/// The file is memory-mapped, so it must not be modified while fonts from it are loaded.
+/// Returns false if the file is missing, invalid, or was written by a different version
+/// of takumi, in which case the fonts should be loaded again and the cache rebuilt.
+///
+/// Writes the face ID of every restored font to `out_face_ids`, in the order the fonts
+/// were loaded before saving, and their number to `out_count`. If the file holds more
+/// than `len` fonts, nothing is restored, false is returned and `out_count` is set to the
+/// number of fonts in the file, so the call can be repeated with a buffer of that size.
+///
+/// # Safety
+///
+/// - `path` must be a valid null-terminated C string pointer.
+/// - `out_face_ids` must be null or a valid pointer to at least `len` `u64`s.
+/// - `out_count` must be a valid pointer to a `u64`.
+/// - This function must not be called concurrently with rendering or other font context calls.
+#[no_mangle]
+pub unsafe extern "C" fn global_font_context_restore_cache(
+    path: *const std::ffi::c_char,
+    out_face_ids: *mut u64,
+    len: u64,
+    out_count: *mut u64,
+) -> bool {
+    if path.is_null() {
+        set_last_error("path is null");
+        return false;
+    }
+
+    if out_count.is_null() {
+        set_last_error("out_count is null");
+        return false;
+    }
+
+    let path = match CStr::from_ptr(path).to_str() {
+        Ok(s) => s,
+        Err(e) => {
+            set_last_error(e);
+            return false;
+        }
+    };
+
+    let max_fonts = if out_face_ids.is_null() { 0 } else { len as usize };
+    match unsafe { restore(path, max_fonts) } {
+        Ok(face_ids) => {
+            if !face_ids.is_empty() {
+                std::ptr::copy_nonoverlapping(face_ids.as_ptr(), out_face_ids, face_ids.len());
+            }
+            unsafe { *out_count = face_ids.len() as u64 };
+            true
+        }
+        Err((e, count)) => {
+            set_last_error(e);
+            unsafe { *out_count = count.unwrap_or(0) as u64 };
+            false
+        }
+    }
+}
@@ -0,0 +1,185 @@
+//! Node trees parsed once and rendered many times through an opaque handle.
+
+use std::{
+    ffi::{c_char, CStr},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use crate::{
+    font,
+    node::{Lowered, NodeKind},
+    render_prepared, set_last_error, ImageFormat, Viewport, GLOBAL_CONTEXT,
+};
+
+/// A parsed and checked node tree. It is never changed once parsed, so a handle can be
+/// rendered from several threads at once, as long as fonts and other global state are not
+/// changed meanwhile. Each thread reads the errors of its own renders.
+///
+/// The tree is lowered into takumi's nodes for the viewport it was last rendered with,
+/// and lowered again only once the viewport or the fonts change.
+pub struct NodeHandle {
+    node: NodeKind,
+    /// The viewport and font generation the tree was last lowered for, and the lowered tree.
+    lowered: Mutex<Option<(Viewport, u64, Arc<Lowered>)>>,
+}
+
+impl NodeHandle {
+    /// Checks a tree once and moves it behind a handle.
+    pub(crate) fn new(mut node: NodeKind) -> Result<*mut NodeHandle, String> {
+        node.resolve()?;
+        Ok(Box::into_raw(Box::new(NodeHandle {
+            node,
+            lowered: Mutex::new(None),
+        })))
+    }
+
+    /// Lowers the tree for a viewport, reusing the last lowered tree when neither the
+    /// viewport nor the fonts changed since.
+    ///
+    /// # Safety
+    ///
+    /// - This function must not be called concurrently with global context calls.
+    pub(crate) unsafe fn lower(&self, viewport: Viewport) -> Result<Arc<Lowered>, String> {
+        let generation = font::font_generation();
+        if let Some((lowered_viewport, lowered_generation, lowered)) = &*self.cache() {
+            if *lowered_viewport == viewport && *lowered_generation == generation {
+                return Ok(lowered.clone());
+            }
+        }
+
+        // The tree is lowered without holding the lock, so renders with other viewports
+        // do not wait for each other.
+        let global = unsafe { &*std::ptr::addr_of!(GLOBAL_CONTEXT) };
+        let lowered = Arc::new(self.node.clone().lower(viewport.into(), global)?);
+        *self.cache() = Some((viewport, generation, lowered.clone()));
+
+        Ok(lowered)
+    }
+
+    /// Renders the tree with the global context and encodes the image.
+    ///
+    /// # Safety
+    ///
+    /// - This function must not be called concurrently with global context calls.
+    unsafe fn render(&self, viewport: Viewport, format: ImageFormat) -> Result<Vec<u8>, String> {
+        let global = unsafe { &*std::ptr::addr_of!(GLOBAL_CONTEXT) };
+        let (tree, resources) = unsafe { self.lower(viewport) }?.draw(viewport.into(), global)?;
+
+        unsafe { render_prepared(tree, resources, viewport, format) }
+    }
+
+    fn cache(&self) -> MutexGuard<'_, Option<(Viewport, u64, Arc<Lowered>)>> {
+        self.lowered.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Parses a node tree once, returning a handle to render it with or null on failure.
+///
+/// The tree is parsed like the one passed to [`crate::render_to_buffer_with_format`],
+/// with its Tailwind classes resolved and its composite nodes expanded. The handle must
+/// be released with [`takumi_node_free`].
+///
+/// # Safety
+///
+/// - `node_str` must be a valid null-terminated C string pointer.
+#[no_mangle]
+pub unsafe extern "C" fn takumi_node_parse(node_str: *const c_char) -> *mut NodeHandle {
+    if node_str.is_null() {
+        set_last_error("node_str is null");
+        return std::ptr::null_mut();
+    }
+
+    let handle = CStr::from_ptr(node_str)
+        .to_str()
+        .map_err(|e| e.to_string())
+        .and_then(NodeKind::from_json)
+        .and_then(NodeHandle::new);
+
+    match handle {
+        Ok(handle) => handle,
+        Err(e) => {
+            set_last_error(e);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Releases a handle returned by [`takumi_node_parse`]. Does nothing when `handle` is null.
+///
+/// # Safety
+///
+/// - `handle` must be null or a handle returned by [`takumi_node_parse`] that was not released yet.
+/// - The handle must not be rendered concurrently or used after this call.
+#[no_mangle]
+pub unsafe extern "C" fn takumi_node_free(handle: *mut NodeHandle) {
+    if !handle.is_null() {
+        drop(unsafe { Box::from_raw(handle) });
+    }
+}
+
+/// Calculates the required buffer size for rendering a parsed node tree to an image.
+///
+/// # Safety
+///
+/// - `handle` must be a handle returned by [`takumi_node_parse`] that was not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn takumi_node_calculate_buffer_size_with_format(
+    handle: *const NodeHandle,
+    viewport: Viewport,
+    format: ImageFormat,
+) -> u64 {
+    let Some(handle) = (unsafe { handle.as_ref() }) else {
+        set_last_error("handle is null");
+        return 0;
+    };
+
+    match unsafe { handle.render(viewport, format) } {
+        Ok(bytes) => bytes.len() as u64,
+        Err(e) => {
+            set_last_error(e);
+            0
+        }
+    }
+}
+
+/// Renders a parsed node tree to an image and writes it to a buffer.
+///
+/// # Safety
+///
+/// - `handle` must be a handle returned by [`takumi_node_parse`] that was not released yet.
+/// - `out_buffer` must be a valid pointer to a buffer of at least `buffer_len` bytes.
+/// - The buffer must remain valid for the duration of this call.
+#[no_mangle]
+pub unsafe extern "C" fn takumi_node_render_to_buffer_with_format(
+    handle: *const NodeHandle,
+    viewport: Viewport,
+    format: ImageFormat,
+    out_buffer: *mut u8,
+    buffer_len: u64,
+) -> bool {
+    let Some(handle) = (unsafe { handle.as_ref() }) else {
+        set_last_error("handle is null");
+        return false;
+    };
+
+    if out_buffer.is_null() || buffer_len == 0 {
+        set_last_error("Invalid arguments: null pointer or zero buffer length");
+        return false;
+    }
+
+    let bytes = match unsafe { handle.render(viewport, format) } {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            set_last_error(e);
+            return false;
+        }
+    };
+
+    if bytes.len() > buffer_len as usize {
+        set_last_error("Buffer too small");
+        return false;
+    }
+
+    std::ptr::copy_nonoverlapping(bytes.as_ptr(), out_buffer, bytes.len());
+    true
+}
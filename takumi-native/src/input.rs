@@ -0,0 +1,387 @@
+//! Node trees read from bytes in one of several encodings, optionally compressed, or
+//! from UTF-16 JSON.
+//!
+//! Compression is detected from the magic bytes gzip and zstd streams start with, neither
+//! of which can start a node tree in any of the encodings. The bytes and UTF-16 code units
+//! are passed with their length, so that hosts can pass their buffers and strings as-is
+//! rather than copy them to add a null terminator.
+
+use std::io::Read;
+
+use flate2::read::GzDecoder;
+use ruzstd::decoding::StreamingDecoder;
+use serde_json::Value;
+
+use crate::{handle::NodeHandle, node::NodeKind, render, set_last_error, ImageFormat, Viewport};
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// The most bytes a compressed node tree may decompress to, so that a small stream cannot
+/// expand without bounds.
+const MAX_DECOMPRESSED_LEN: u64 = 256 * 1024 * 1024;
+
+/// How the bytes of a node tree are encoded, after decompressing them. It is passed to
+/// the render functions as its `u8` value, which is checked.
+///
+/// MessagePack and CBOR trees are decoded through JSON values, so they may only hold what
+/// JSON can: maps need string keys, and binary (bin or byte string) values are rejected.
+/// Images are passed as strings, like URLs or data URIs, as in the JSON.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy)]
+pub enum NodeFormat {
+    /// UTF-8 JSON, like the node strings the other render functions take
+    Json = 0,
+
+    /// MessagePack, with the same fields as the JSON
+    MessagePack = 1,
+
+    /// CBOR, with the same fields as the JSON
+    Cbor = 2,
+}
+
+impl TryFrom<u8> for NodeFormat {
+    type Error = String;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(NodeFormat::Json),
+            1 => Ok(NodeFormat::MessagePack),
+            2 => Ok(NodeFormat::Cbor),
+            _ => Err(format!("unknown node format {value}")),
+        }
+    }
+}
+
+/// Decompresses and decodes a node tree in the format with the given value.
+pub(crate) fn decode(bytes: &[u8], format: u8) -> Result<NodeKind, String> {
+    let format = NodeFormat::try_from(format)?;
+
+    let decompressed;
+    let bytes = if bytes.starts_with(GZIP_MAGIC) {
+        decompressed = decompress(GzDecoder::new(bytes), "gzip")?;
+        decompressed.as_slice()
+    } else if bytes.starts_with(ZSTD_MAGIC) {
+        let decoder = StreamingDecoder::new(bytes).map_err(|e| format!("invalid zstd stream: {e}"))?;
+        decompressed = decompress(decoder, "zstd")?;
+        decompressed.as_slice()
+    } else {
+        bytes
+    };
+
+    // Binary trees are decoded into JSON values first, so that they accept the same React
+    // elements JSON does.
+    let value: Value = match format {
+        NodeFormat::Json => {
+            let json = std::str::from_utf8(bytes).map_err(|e| e.to_string())?;
+            return NodeKind::from_json(json);
+        }
+        NodeFormat::MessagePack => rmp_serde::from_slice(bytes).map_err(|e| format!("invalid MessagePack: {e}"))?,
+        NodeFormat::Cbor => ciborium::from_reader(bytes).map_err(|e| format!("invalid CBOR: {e}"))?,
+    };
+
+    NodeKind::from_value(&value)
+}
+
+/// Reads a whole compressed stream, failing once it exceeds [`MAX_DECOMPRESSED_LEN`].
+fn decompress(decoder: impl Read, name: &str) -> Result<Vec<u8>, String> {
+    let mut decompressed = Vec::new();
+    decoder
+        .take(MAX_DECOMPRESSED_LEN + 1)
+        .read_to_end(&mut decompressed)
+        .map_err(|e| format!("invalid {name} stream: {e}"))?;
+
+    if decompressed.len() as u64 > MAX_DECOMPRESSED_LEN {
+        return Err(format!(
+            "{name} stream decompresses to more than {} MiB",
+            MAX_DECOMPRESSED_LEN / 1024 / 1024
+        ));
+    }
+
+    Ok(decompressed)
+}
+
+/// Decodes a node tree from UTF-16 JSON.
+fn decode_utf16(units: &[u16]) -> Result<NodeKind, String> {
+    let json = String::from_utf16(units).map_err(|e| e.to_string())?;
+    NodeKind::from_json(&json)
+}
+
+/// Calculates the required buffer size for rendering a node tree read from bytes to an image.
+///
+/// This is also how to render UTF-8 JSON given by its length, with [`NodeFormat::Json`].
+/// `node_format` is the value of a [`NodeFormat`].
+///
+/// # Safety
+///
+/// - `data` must be a valid pointer to a byte array of at least `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn render_bytes_calculate_buffer_size_with_format(
+    data: *const u8,
+    len: usize,
+    node_format: u8,
+    viewport: Viewport,
+    format: ImageFormat,
+) -> u64 {
+    if data.is_null() {
+        set_last_error("data is null");
+        return 0;
+    }
+
+    let bytes = unsafe { std::slice::from_raw_parts(data, len) };
+    match decode(bytes, node_format).and_then(|node| unsafe { render(node, viewport, format) }) {
+        Ok(bytes) => bytes.len() as u64,
+        Err(e) => {
+            set_last_error(e);
+            0
+        }
+    }
+}
+
+/// Renders a node tree read from bytes to an image and writes it to a buffer.
+///
+/// # Safety
+///
+/// - `data` must be a valid pointer to a byte array of at least `len` bytes.
+/// - `out_buffer` must be a valid pointer to a buffer of at least `buffer_len` bytes.
+/// - The buffers must remain valid for the duration of this call.
+#[no_mangle]
+pub unsafe extern "C" fn render_bytes_to_buffer_with_format(
+    data: *const u8,
+    len: usize,
+    node_format: u8,
+    viewport: Viewport,
+    format: ImageFormat,
+    out_buffer: *mut u8,
+    buffer_len: u64,
+) -> bool {
+    if data.is_null() || out_buffer.is_null() || buffer_len == 0 {
+        set_last_error("Invalid arguments: null pointer or zero buffer length");
+        return false;
+    }
+
+    let bytes = unsafe { std::slice::from_raw_parts(data, len) };
+    let bytes = match decode(bytes, node_format).and_then(|node| unsafe { render(node, viewport, format) }) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            set_last_error(e);
+            return false;
+        }
+    };
+
+    if bytes.len() > buffer_len as usize {
+        set_last_error("Buffer too small");
+        return false;
+    }
+
+    std::ptr::copy_nonoverlapping(bytes.as_ptr(), out_buffer, bytes.len());
+    true
+}
+
+/// Parses a node tree read from bytes once, returning a handle to render it with or null
+/// on failure. The handle must be released with [`crate::handle::takumi_node_free`].
+///
+/// # Safety
+///
+/// - `data` must be a valid pointer to a byte array of at least `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn takumi_node_parse_bytes(
+    data: *const u8,
+    len: usize,
+    node_format: u8,
+) -> *mut NodeHandle {
+    if data.is_null() {
+        set_last_error("data is null");
+        return std::ptr::null_mut();
+    }
+
+    let bytes = unsafe { std::slice::from_raw_parts(data, len) };
+    match decode(bytes, node_format).and_then(NodeHandle::new) {
+        Ok(handle) => handle,
+        Err(e) => {
+            set_last_error(e);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Calculates the required buffer size for rendering a node tree in UTF-16 JSON to an image.
+///
+/// # Safety
+///
+/// - `data` must be a valid pointer to an array of at least `len` UTF-16 code units.
+#[no_mangle]
+pub unsafe extern "C" fn render_utf16_calculate_buffer_size_with_format(
+    data: *const u16,
+    len: usize,
+    viewport: Viewport,
+    format: ImageFormat,
+) -> u64 {
+    if data.is_null() {
+        set_last_error("data is null");
+        return 0;
+    }
+
+    let units = unsafe { std::slice::from_raw_parts(data, len) };
+    match decode_utf16(units).and_then(|node| unsafe { render(node, viewport, format) }) {
+        Ok(bytes) => bytes.len() as u64,
+        Err(e) => {
+            set_last_error(e);
+            0
+        }
+    }
+}
+
+/// Renders a node tree in UTF-16 JSON to an image and writes it to a buffer.
+///
+/// # Safety
+///
+/// - `data` must be a valid pointer to an array of at least `len` UTF-16 code units.
+/// - `out_buffer` must be a valid pointer to a buffer of at least `buffer_len` bytes.
+/// - The buffers must remain valid for the duration of this call.
+#[no_mangle]
+pub unsafe extern "C" fn render_utf16_to_buffer_with_format(
+    data: *const u16,
+    len: usize,
+    viewport: Viewport,
+    format: ImageFormat,
+    out_buffer: *mut u8,
+    buffer_len: u64,
+) -> bool {
+    if data.is_null() || out_buffer.is_null() || buffer_len == 0 {
+        set_last_error("Invalid arguments: null pointer or zero buffer length");
+        return false;
+    }
+
+    let units = unsafe { std::slice::from_raw_parts(data, len) };
+    let bytes = match decode_utf16(units).and_then(|node| unsafe { render(node, viewport, format) }) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            set_last_error(e);
+            return false;
+        }
+    };
+
+    if bytes.len() > buffer_len as usize {
+        set_last_error("Buffer too small");
+        return false;
+    }
+
+    std::ptr::copy_nonoverlapping(bytes.as_ptr(), out_buffer, bytes.len());
+    true
+}
+
+/// Parses a node tree in UTF-16 JSON once, returning a handle to render it with or null on
+/// failure. The handle must be released with [`crate::handle::takumi_node_free`].
+///
+/// # Safety
+///
+/// - `data` must be a valid pointer to an array of at least `len` UTF-16 code units.
+#[no_mangle]
+pub unsafe extern "C" fn takumi_node_parse_utf16(data: *const u16, len: usize) -> *mut NodeHandle {
+    if data.is_null() {
+        set_last_error("data is null");
+        return std::ptr::null_mut();
+    }
+
+    let units = unsafe { std::slice::from_raw_parts(data, len) };
+    match decode_utf16(units).and_then(NodeHandle::new) {
+        Ok(handle) => handle,
+        Err(e) => {
+            set_last_error(e);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::GzEncoder, Compression};
+    use ruzstd::encoding::{compress_to_vec, CompressionLevel};
+    use serde_json::json;
+
+    use super::*;
+
+    fn text(node: NodeKind) -> String {
+        match node {
+            NodeKind::Text(text) => text.text,
+            node => panic!("{node:?} is not a text node"),
+        }
+    }
+
+    fn tree() -> Value {
+        json!({ "type": "text", "text": "Hello" })
+    }
+
+    #[test]
+    fn binary_formats_decode_like_json() {
+        let json = serde_json::to_vec(&tree()).unwrap();
+        assert_eq!(text(decode(&json, NodeFormat::Json as u8).unwrap()), "Hello");
+
+        let message_pack = rmp_serde::to_vec_named(&tree()).unwrap();
+        assert_eq!(text(decode(&message_pack, NodeFormat::MessagePack as u8).unwrap()), "Hello");
+
+        let mut cbor = Vec::new();
+        ciborium::into_writer(&tree(), &mut cbor).unwrap();
+        assert_eq!(text(decode(&cbor, NodeFormat::Cbor as u8).unwrap()), "Hello");
+
+        assert_eq!(decode(&json, 3).unwrap_err(), "unknown node format 3");
+    }
+
+    #[test]
+    fn binary_values_are_rejected() {
+        // {"type": "text", "text": <bin 8 of one byte>}
+        let message_pack = [&[0x82, 0xa4][..], b"type", &[0xa4], b"text", &[0xa4], b"text", &[0xc4, 0x01, 0x00]]
+            .concat();
+        let error = decode(&message_pack, NodeFormat::MessagePack as u8).unwrap_err();
+        assert!(error.starts_with("invalid MessagePack: "), "{error}");
+
+        let tree = ciborium::Value::Map(vec![
+            ("type".into(), "text".into()),
+            ("text".into(), ciborium::Value::Bytes(vec![0])),
+        ]);
+        let mut cbor = Vec::new();
+        ciborium::into_writer(&tree, &mut cbor).unwrap();
+        let error = decode(&cbor, NodeFormat::Cbor as u8).unwrap_err();
+        assert!(error.starts_with("invalid CBOR: "), "{error}");
+    }
+
+    #[test]
+    fn compressed_trees_are_detected_by_their_magic_bytes() {
+        let message_pack = rmp_serde::to_vec_named(&tree()).unwrap();
+
+        let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+        gzip.write_all(&message_pack).unwrap();
+        let gzip = gzip.finish().unwrap();
+        assert_eq!(text(decode(&gzip, NodeFormat::MessagePack as u8).unwrap()), "Hello");
+
+        let zstd = compress_to_vec(message_pack.as_slice(), CompressionLevel::Fastest);
+        assert_eq!(text(decode(&zstd, NodeFormat::MessagePack as u8).unwrap()), "Hello");
+
+        let error = decode(&gzip[..gzip.len() / 2], NodeFormat::MessagePack as u8).unwrap_err();
+        assert!(error.starts_with("invalid gzip stream: "), "{error}");
+    }
+
+    #[test]
+    fn decompression_stops_past_the_limit() {
+        let within = decompress(std::io::repeat(0).take(1024), "gzip").unwrap();
+        assert_eq!(within.len(), 1024);
+
+        let error = decompress(std::io::repeat(0), "zstd").unwrap_err();
+        assert_eq!(error, "zstd stream decompresses to more than 256 MiB");
+    }
+
+    #[test]
+    fn utf16_json_is_decoded() {
+        let units: Vec<u16> = r#"{ "type": "text", "text": "Grüße 🎉" }"#.encode_utf16().collect();
+        assert_eq!(text(decode_utf16(&units).unwrap()), "Grüße 🎉");
+
+        // A high surrogate without the low one after it.
+        let mut units: Vec<u16> = r#"{ "type": "text", "text": "?" }"#.encode_utf16().collect();
+        let question_mark = units.iter().position(|&unit| unit == u16::from(b'?')).unwrap();
+        units[question_mark] = 0xd83c;
+        assert_eq!(decode_utf16(&units).unwrap_err(), "invalid utf-16: lone surrogate found");
+    }
+}
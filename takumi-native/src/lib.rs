@@ -1,18 +1,37 @@
-use std::{ffi::CStr, sync::LazyLock};
+use std::{cell::RefCell, ffi::CStr, sync::LazyLock};
 
-use takumi::{layout::node::NodeKind, rendering::RenderOptionsBuilder, GlobalContext};
+use takumi::{rendering::RenderOptionsBuilder, GlobalContext};
 
-static mut GLOBAL_CONTEXT: LazyLock<GlobalContext> = LazyLock::new(GlobalContext::default);
-static mut GLOBAL_LAST_ERROR: Option<std::ffi::CString> = None;
+use crate::node::NodeKind;
 
-unsafe fn set_last_error(err: impl std::fmt::Display) {
+mod builder;
+mod font;
+mod font_cache;
+mod handle;
+mod input;
+mod measure;
+mod node;
+mod template;
+
+pub use input::NodeFormat;
+
+static mut GLOBAL_CONTEXT: LazyLock<GlobalContext> = LazyLock::new(font::create_global_context);
+
+thread_local! {
+    /// The last error of each thread, so that failures of concurrent renders do not race.
+    static LAST_ERROR: RefCell<Option<std::ffi::CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(err: impl std::fmt::Display) {
     let s = err.to_string();
-    GLOBAL_LAST_ERROR = Some(std::ffi::CString::new(s).unwrap_or_else(|_| std::ffi::CString::new("Error creating error string").unwrap()));
+    let error =
+        std::ffi::CString::new(s).unwrap_or_else(|_| std::ffi::CString::new("Error creating error string").unwrap());
+    LAST_ERROR.with_borrow_mut(|last_error| *last_error = Some(error));
 }
 
 /// The viewport for the image renderer.
 #[repr(C)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Viewport {
     /// The width of the viewport in pixels, or -1 for none/auto.
     pub width: i32,
@@ -122,16 +141,30 @@ impl From<ImageFormat> for takumi::image::ImageFormat {
 #[no_mangle]
 pub unsafe extern "C" fn global_font_context_load_and_store(data: *const u8, len: usize) -> bool {
     let data_arr = unsafe { std::slice::from_raw_parts(data, len) };
-    if let Err(e) = unsafe { &mut *std::ptr::addr_of_mut!(GLOBAL_CONTEXT) }
-        .font_context
-        .load_and_store(data_arr, None, None)
-    {
-        unsafe { set_last_error(e) };
+    if let Err(e) = unsafe { font::load_and_store(&mut *std::ptr::addr_of_mut!(GLOBAL_CONTEXT), data_arr) } {
+        set_last_error(e);
         return false;
     }
     true
 }
 
+/// Resets the global context, dropping all fonts, language fallbacks, stored images and the
+/// last error of the calling thread.
+///
+/// # Safety
+///
+/// - This function must not be called concurrently with rendering or other global context calls.
+/// - Pointers previously returned by [`get_last_error`] on the calling thread are invalidated.
+#[no_mangle]
+pub unsafe extern "C" fn global_context_reset() {
+    unsafe {
+        **std::ptr::addr_of_mut!(GLOBAL_CONTEXT) = font::create_global_context();
+        font::forget_loaded_fonts();
+        font::forget_language_fallbacks();
+    }
+    LAST_ERROR.with_borrow_mut(|last_error| *last_error = None);
+}
+
 /// Calculates the required buffer size for rendering a node to an image.
 ///
 /// # Safety
@@ -144,69 +177,88 @@ pub unsafe extern "C" fn render_calculate_buffer_size_with_format(
     format: ImageFormat,
 ) -> u64 {
     if node_str.is_null() {
-        unsafe { set_last_error("node_str is null") };
+        set_last_error("node_str is null");
         return 0;
     }
 
     let node_str = match CStr::from_ptr(node_str).to_str() {
         Ok(s) => s,
         Err(e) => {
-            unsafe { set_last_error(e) };
+            set_last_error(e);
             return 0;
         }
     };
 
-    let node: NodeKind = match serde_json::from_str(node_str) {
+    let node = match NodeKind::from_json(node_str) {
         Ok(n) => n,
         Err(e) => {
-            unsafe { set_last_error(e) };
+            set_last_error(e);
             return 0;
         }
     };
 
-    let opt = match RenderOptionsBuilder::default()
+    match unsafe { render(node, viewport, format) } {
+        Ok(bytes) => bytes.len() as u64,
+        Err(e) => {
+            set_last_error(e);
+            0
+        }
+    }
+}
+
+/// Prepares a tree, renders it with the global context and encodes the image.
+///
+/// # Safety
+///
+/// - This function must not be called concurrently with global context calls.
+pub(crate) unsafe fn render(node: NodeKind, viewport: Viewport, format: ImageFormat) -> Result<Vec<u8>, String> {
+    let global = unsafe { &*std::ptr::addr_of!(GLOBAL_CONTEXT) };
+    let (node, resources) = node.prepare(viewport.into(), global)?;
+
+    unsafe { render_prepared(node, resources, viewport, format) }
+}
+
+/// Renders a prepared tree with the global context and encodes the image.
+///
+/// # Safety
+///
+/// - This function must not be called concurrently with global context calls.
+pub(crate) unsafe fn render_prepared(
+    node: takumi::layout::node::NodeKind,
+    resources: node::Resources,
+    viewport: Viewport,
+    format: ImageFormat,
+) -> Result<Vec<u8>, String> {
+    let global = unsafe { &*std::ptr::addr_of!(GLOBAL_CONTEXT) };
+
+    let opt = RenderOptionsBuilder::default()
         .viewport(viewport.into())
         .node(node)
-        .global(unsafe { &*std::ptr::addr_of!(GLOBAL_CONTEXT) })
+        .global(global)
+        .fetched_resources(resources)
         .build()
-    {
-        Ok(o) => o,
-        Err(e) => {
-            unsafe { set_last_error(e) };
-            return 0;
-        }
-    };
+        .map_err(|e| e.to_string())?;
 
     let img_format: takumi::image::ImageFormat = format.into();
-
-    let image = match takumi::rendering::render(opt) {
-        Ok(img) => img,
-        Err(e) => {
-            unsafe { set_last_error(e) };
-            return 0;
-        }
-    };
+    let image = takumi::rendering::render(opt).map_err(|e| e.to_string())?;
 
     let mut cursor = std::io::Cursor::new(Vec::new());
-    if let Err(e) = image.write_to(&mut cursor, img_format) {
-        unsafe { set_last_error(e) };
-        return 0;
-    }
-
-    cursor.into_inner().len() as u64
+    image.write_to(&mut cursor, img_format).map_err(|e| e.to_string())?;
+    Ok(cursor.into_inner())
 }
 
-/// Returns the last error that occurred in the global context.
+/// Returns the last error that occurred on the calling thread.
 ///
 /// # Safety
 ///
-/// - The returned pointer must not be used after the global context is dropped.
+/// - The returned pointer must only be used on the calling thread, and not after its next
+///   failing call or [`global_context_reset`].
 #[no_mangle]
 pub unsafe extern "C" fn get_last_error() -> *const std::ffi::c_char {
-    match unsafe { &*std::ptr::addr_of!(GLOBAL_LAST_ERROR) } {
+    LAST_ERROR.with_borrow(|last_error| match last_error {
         Some(s) => s.as_ptr(),
         None => std::ptr::null(),
-    }
+    })
 }
 
 /// Renders a node to an image and writes it to a buffer.
@@ -225,58 +277,36 @@ pub unsafe extern "C" fn render_to_buffer_with_format(
     buffer_len: u64,
 ) -> bool {
     if node_str.is_null() || out_buffer.is_null() || buffer_len == 0 {
-         unsafe { set_last_error("Invalid arguments: null pointer or zero buffer length") };
+         set_last_error("Invalid arguments: null pointer or zero buffer length");
         return false;
     }
 
     let node_str = match CStr::from_ptr(node_str).to_str() {
         Ok(s) => s,
         Err(e) => {
-            unsafe { set_last_error(e) };
+            set_last_error(e);
             return false;
         }
     };
 
-    let node: NodeKind = match serde_json::from_str(node_str) {
+    let node = match NodeKind::from_json(node_str) {
         Ok(n) => n,
         Err(e) => {
-            unsafe { set_last_error(e) };
+            set_last_error(e);
             return false;
         }
     };
 
-    let opt = match RenderOptionsBuilder::default()
-        .viewport(viewport.into())
-        .node(node)
-        .global(unsafe { &*std::ptr::addr_of!(GLOBAL_CONTEXT) })
-        .build()
-    {
-        Ok(o) => o,
-        Err(e) => {
-            unsafe { set_last_error(e) };
-            return false;
-        }
-    };
-
-    let img_format: takumi::image::ImageFormat = format.into();
-
-    let image = match takumi::rendering::render(opt) {
-        Ok(img) => img,
+    let bytes = match unsafe { render(node, viewport, format) } {
+        Ok(bytes) => bytes,
         Err(e) => {
-             unsafe { set_last_error(e) };
+            set_last_error(e);
             return false;
         }
     };
 
-    let mut cursor = std::io::Cursor::new(Vec::new());
-    if let Err(e) = image.write_to(&mut cursor, img_format) {
-         unsafe { set_last_error(e) };
-        return false;
-    }
-
-    let bytes = cursor.into_inner();
     if bytes.len() > buffer_len as usize {
-        unsafe { set_last_error("Buffer too small") };
+        set_last_error("Buffer too small");
         return false;
     }
 
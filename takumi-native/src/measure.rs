@@ -0,0 +1,265 @@
+use std::{borrow::Cow, cell::RefCell, ffi::CStr};
+
+use takumi::{
+    parley::{FontStack, FontWeight, LayoutContext, LineHeight, StyleProperty},
+    resources::font::FontContext,
+};
+
+use crate::{font, set_last_error, GLOBAL_CONTEXT};
+
+/// The font line height scaler used when no line height is given, matching takumi's default.
+const DEFAULT_LINE_HEIGHT: f32 = 1.2;
+
+/// The contexts text is measured with, kept between calls on the same thread.
+struct MeasureContext {
+    /// The generation of the global font context the font context was copied at.
+    generation: u64,
+    font_context: FontContext,
+    layout_context: LayoutContext<()>,
+}
+
+thread_local! {
+    static MEASURE_CONTEXT: RefCell<Option<MeasureContext>> = const { RefCell::new(None) };
+}
+
+/// The style used to measure text.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct TextMeasureStyle {
+    /// A null-terminated CSS font family list, or null for sans-serif.
+    pub font_family: *const std::ffi::c_char,
+    /// The font size in pixels.
+    pub font_size: f32,
+    /// The font weight, from 1 to 1000.
+    pub font_weight: f32,
+    /// Extra spacing between letters in pixels.
+    pub letter_spacing: f32,
+    /// The line height as a multiple of the font size, or 0 or less for the default.
+    pub line_height: f32,
+}
+
+/// The overall size of measured text.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TextMetrics {
+    /// The width of the text box in pixels (the widest line width), rounded up. It exceeds
+    /// the maximum width when a word that cannot be broken does not fit it.
+    pub width: f32,
+    /// The total height of all lines in pixels, rounded up.
+    pub height: f32,
+    /// The number of lines the text wraps to.
+    pub line_count: u32,
+}
+
+/// The metrics of a single line of measured text.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LineMetrics {
+    /// The advance of the line in pixels, excluding trailing whitespace.
+    pub width: f32,
+    /// The height of the line in pixels.
+    pub height: f32,
+    /// The offset of the baseline from the top of the text in pixels.
+    pub baseline: f32,
+    /// The typographic ascent of the line.
+    pub ascent: f32,
+    /// The typographic descent of the line.
+    pub descent: f32,
+    /// The UTF-16 index of the first character of the line.
+    pub start: u32,
+    /// The UTF-16 index after the last character of the line.
+    pub end: u32,
+}
+
+/// Converts a UTF-8 byte offset into `text` to a UTF-16 code unit offset.
+fn utf16_offset(text: &str, byte_offset: usize) -> u32 {
+    text[..byte_offset].encode_utf16().count() as u32
+}
+
+/// Measures text with the fonts loaded into the global context, without rasterizing it.
+///
+/// Writes the overall size to `out_metrics` and the metrics of up to `lines_len` lines to
+/// `out_lines`. `line_count` always holds the total number of lines, so a second call with
+/// a larger buffer can retrieve every line. The text is measured as-is, without collapsing
+/// whitespace, and the widths of the lines and of the text box exclude the whitespace lines
+/// end with, like the width of a text node sized to its content.
+///
+/// Each thread keeps its own copy of the global font context to measure with, copied again
+/// only after fonts are loaded or unloaded. Text can therefore be measured concurrently
+/// with rendering and with other measurements, which only read the global font context.
+///
+/// # Safety
+///
+/// - `text` must be a valid null-terminated C string pointer.
+/// - `style.font_family` must be null or a valid null-terminated C string pointer.
+/// - `out_lines` must be null or a valid pointer to at least `lines_len` [`LineMetrics`].
+/// - `out_metrics` must be a valid pointer to a [`TextMetrics`].
+/// - This function must not be called concurrently with loading or unloading fonts.
+#[no_mangle]
+pub unsafe extern "C" fn measure_text(
+    text: *const std::ffi::c_char,
+    style: TextMeasureStyle,
+    max_width: f32,
+    out_lines: *mut LineMetrics,
+    lines_len: u64,
+    out_metrics: *mut TextMetrics,
+) -> bool {
+    if text.is_null() || out_metrics.is_null() {
+        set_last_error("Invalid arguments: null pointer");
+        return false;
+    }
+
+    let text = match CStr::from_ptr(text).to_str() {
+        Ok(s) => s,
+        Err(e) => {
+            set_last_error(e);
+            return false;
+        }
+    };
+
+    let font_family = if style.font_family.is_null() {
+        "sans-serif"
+    } else {
+        match CStr::from_ptr(style.font_family).to_str() {
+            Ok(s) => s,
+            Err(e) => {
+                set_last_error(e);
+                return false;
+            }
+        }
+    };
+
+    let line_height = if style.line_height > 0.0 {
+        style.line_height
+    } else {
+        DEFAULT_LINE_HEIGHT
+    };
+
+    let generation = font::font_generation();
+
+    MEASURE_CONTEXT.with_borrow_mut(|context| {
+        let context = match context {
+            Some(context) if context.generation == generation => context,
+            context => context.insert(MeasureContext {
+                generation,
+                font_context: unsafe { &*std::ptr::addr_of!(GLOBAL_CONTEXT) }.font_context.clone(),
+                layout_context: LayoutContext::new(),
+            }),
+        };
+
+        let lines = match out_lines.is_null() {
+            true => &mut [][..],
+            false => unsafe { std::slice::from_raw_parts_mut(out_lines, lines_len as usize) },
+        };
+
+        unsafe { *out_metrics = context.measure(text, font_family, style, line_height, max_width, lines) };
+    });
+
+    true
+}
+
+impl MeasureContext {
+    /// Lays out text, writing the metrics of as many lines as fit into `lines`.
+    fn measure(
+        &mut self,
+        text: &str,
+        font_family: &str,
+        style: TextMeasureStyle,
+        line_height: f32,
+        max_width: f32,
+        lines: &mut [LineMetrics],
+    ) -> TextMetrics {
+        let mut builder = self
+            .layout_context
+            .ranged_builder(&mut self.font_context, text, 1.0, true);
+        builder.push_default(StyleProperty::FontStack(FontStack::Source(Cow::Borrowed(font_family))));
+        builder.push_default(StyleProperty::FontSize(style.font_size));
+        builder.push_default(StyleProperty::FontWeight(FontWeight::new(style.font_weight)));
+        builder.push_default(StyleProperty::LetterSpacing(style.letter_spacing));
+        builder.push_default(StyleProperty::LineHeight(LineHeight::FontSizeRelative(line_height)));
+
+        let mut layout = builder.build(text);
+        layout.break_all_lines((max_width >= 0.0).then_some(max_width));
+
+        let mut metrics = TextMetrics::default();
+
+        for (index, line) in layout.lines().enumerate() {
+            let line_metrics = line.metrics();
+            let text_range = line.text_range();
+            let width = line_metrics.advance - line_metrics.trailing_whitespace;
+
+            if let Some(line) = lines.get_mut(index) {
+                *line = LineMetrics {
+                    width,
+                    height: line_metrics.line_height,
+                    baseline: line_metrics.baseline,
+                    ascent: line_metrics.ascent,
+                    descent: line_metrics.descent,
+                    start: utf16_offset(text, text_range.start),
+                    end: utf16_offset(text, text_range.end),
+                };
+            }
+
+            metrics.width = metrics.width.max(width);
+            metrics.height += line_metrics.line_height;
+            metrics.line_count += 1;
+        }
+
+        metrics.width = metrics.width.ceil();
+        metrics.height = metrics.height.ceil();
+
+        metrics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use takumi::parley::fontique::Blob;
+
+    use super::*;
+
+    const FONT: &[u8] = include_bytes!("../../font.ttf");
+
+    fn measure(text: &str, max_width: f32) -> TextMetrics {
+        let mut font_context = FontContext::default();
+        let collection = &mut font_context.collection;
+        let (family, _) = collection.register_fonts(Blob::new(Arc::new(FONT)), None).remove(0);
+        let font_family = collection.family_name(family).unwrap().to_owned();
+
+        let mut context = MeasureContext {
+            generation: 0,
+            font_context,
+            layout_context: LayoutContext::new(),
+        };
+        let style = TextMeasureStyle {
+            font_family: std::ptr::null(),
+            font_size: 20.0,
+            font_weight: 400.0,
+            letter_spacing: 0.0,
+            line_height: 0.0,
+        };
+
+        context.measure(text, &font_family, style, DEFAULT_LINE_HEIGHT, max_width, &mut [])
+    }
+
+    #[test]
+    fn text_wraps_at_the_max_width() {
+        let unwrapped = measure("one two three four", -1.0);
+        let wrapped = measure("one two three four", 100.0);
+
+        assert_eq!(unwrapped.line_count, 1);
+        assert!(wrapped.line_count > 1);
+        assert!(wrapped.width <= 100.0);
+        assert!(wrapped.height > unwrapped.height);
+    }
+
+    #[test]
+    fn words_that_cannot_break_overflow_the_max_width() {
+        let metrics = measure("incomprehensibilities", 50.0);
+
+        assert_eq!(metrics.line_count, 1);
+        assert!(metrics.width > 50.0, "{}", metrics.width);
+    }
+}
@@ -0,0 +1,240 @@
+//! Line break opportunities the text layout cannot find on its own.
+//!
+//! takumi breaks lines with the plain Unicode line breaking algorithm, which neither
+//! hyphenates words nor finds word boundaries in scripts written without spaces. Both
+//! are resolved here by inserting invisible break opportunities into the text: soft
+//! hyphens (U+00AD) at hyphenation points and zero width spaces (U+200B) between words.
+
+use std::borrow::Cow;
+
+use super::{Hyphens, InheritedStyle};
+
+const SOFT_HYPHEN: char = '\u{00AD}';
+
+#[cfg(feature = "dictionary-line-breaking")]
+const ZERO_WIDTH_SPACE: char = '\u{200B}';
+
+/// Inserts the break opportunities allowed by the inherited style into the text.
+pub(crate) fn insert_break_opportunities<'t>(text: &'t str, style: &InheritedStyle) -> Cow<'t, str> {
+    let text = match style.hyphens {
+        Hyphens::None if text.contains(SOFT_HYPHEN) => Cow::Owned(text.replace(SOFT_HYPHEN, "")),
+        Hyphens::Auto => hyphenate(text, style.lang.as_deref()),
+        _ => Cow::Borrowed(text),
+    };
+
+    if let Cow::Owned(broken) = break_complex_scripts(&text) {
+        return Cow::Owned(broken);
+    }
+    text
+}
+
+#[cfg(feature = "hyphenation")]
+fn hyphenate<'t>(text: &'t str, lang: Option<&str>) -> Cow<'t, str> {
+    use hyphenation::Hyphenator;
+
+    let Some(dictionary) = lang.and_then(hyphenation_dictionary::load) else {
+        return Cow::Borrowed(text);
+    };
+
+    let mut hyphenated = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(word_start) = rest.find(char::is_alphabetic) {
+        hyphenated.push_str(&rest[..word_start]);
+        rest = &rest[word_start..];
+
+        let word_end = rest.find(|c: char| !c.is_alphabetic()).unwrap_or(rest.len());
+        let word = &rest[..word_end];
+        let mut last_break = 0;
+
+        for word_break in dictionary.hyphenate(word).breaks {
+            hyphenated.push_str(&word[last_break..word_break]);
+            hyphenated.push(SOFT_HYPHEN);
+            last_break = word_break;
+        }
+
+        hyphenated.push_str(&word[last_break..]);
+        rest = &rest[word_end..];
+    }
+
+    hyphenated.push_str(rest);
+    Cow::Owned(hyphenated)
+}
+
+#[cfg(not(feature = "hyphenation"))]
+fn hyphenate<'t>(text: &'t str, _lang: Option<&str>) -> Cow<'t, str> {
+    Cow::Borrowed(text)
+}
+
+#[cfg(feature = "hyphenation")]
+mod hyphenation_dictionary {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, LazyLock, Mutex},
+    };
+
+    use hyphenation::{Language, Load, Standard};
+
+    /// Dictionaries are deserialized from the embedded patterns once per language.
+    static DICTIONARIES: LazyLock<Mutex<HashMap<Language, Arc<Standard>>>> = LazyLock::new(Default::default);
+
+    /// Loads the hyphenation dictionary for a BCP 47 language tag.
+    pub(super) fn load(lang: &str) -> Option<Arc<Standard>> {
+        let language = language_for_tag(lang)?;
+        let mut dictionaries = DICTIONARIES.lock().unwrap_or_else(|e| e.into_inner());
+
+        if let Some(dictionary) = dictionaries.get(&language) {
+            return Some(dictionary.clone());
+        }
+
+        let dictionary = Arc::new(Standard::from_embedded(language).ok()?);
+        dictionaries.insert(language, dictionary.clone());
+        Some(dictionary)
+    }
+
+    /// Maps a BCP 47 tag to the closest language with hyphenation patterns.
+    pub(super) fn language_for_tag(lang: &str) -> Option<Language> {
+        let tag = lang.to_ascii_lowercase().replace('_', "-");
+
+        if let Some(language) = Language::try_from_code(&tag) {
+            return Some(language);
+        }
+
+        let mut subtags = tag.split('-');
+        let primary = subtags.next()?;
+        let subtags: Vec<&str> = subtags.collect();
+        let has = |subtag: &str| subtags.contains(&subtag);
+
+        let code = match primary {
+            "en" if has("gb") || has("uk") || has("au") || has("nz") || has("ie") || has("in") => "en-gb",
+            "en" => "en-us",
+            "de" if has("ch") => "de-ch-1901",
+            "de" => "de-1996",
+            "el" => "el-monoton",
+            "mn" => "mn-cyrl",
+            "sr" if has("latn") => "sh-latn",
+            "sr" => "sr-cyrl",
+            _ => primary,
+        };
+
+        Language::try_from_code(code)
+    }
+}
+
+#[cfg(feature = "dictionary-line-breaking")]
+fn break_complex_scripts(text: &str) -> Cow<'_, str> {
+    use icu_segmenter::{options::LineBreakOptions, LineSegmenter};
+
+    if !text.chars().any(is_complex_script) {
+        return Cow::Borrowed(text);
+    }
+
+    let segmenter = LineSegmenter::new_dictionary(LineBreakOptions::default());
+    let mut broken = String::with_capacity(text.len() + text.len() / 4);
+    let mut last_break = 0;
+
+    for line_break in segmenter.segment_str(text) {
+        if line_break == 0 || line_break == text.len() {
+            continue;
+        }
+
+        let before = text[..line_break].chars().next_back();
+        let after = text[line_break..].chars().next();
+
+        // Only breaks inside runs of complex scripts are missing from the text layout.
+        if before.is_some_and(is_complex_script) && after.is_some_and(is_complex_script) {
+            broken.push_str(&text[last_break..line_break]);
+            broken.push(ZERO_WIDTH_SPACE);
+            last_break = line_break;
+        }
+    }
+
+    broken.push_str(&text[last_break..]);
+    Cow::Owned(broken)
+}
+
+#[cfg(not(feature = "dictionary-line-breaking"))]
+fn break_complex_scripts(text: &str) -> Cow<'_, str> {
+    Cow::Borrowed(text)
+}
+
+/// Whether the character belongs to a script written without spaces between words
+/// (line break class SA), such as Thai, Lao, Khmer and Myanmar.
+#[cfg(feature = "dictionary-line-breaking")]
+fn is_complex_script(c: char) -> bool {
+    matches!(
+        c,
+        '\u{0E00}'..='\u{0EFF}'
+            | '\u{1000}'..='\u{109F}'
+            | '\u{1780}'..='\u{17FF}'
+            | '\u{1950}'..='\u{19DF}'
+            | '\u{19E0}'..='\u{19FF}'
+            | '\u{1A20}'..='\u{1AAF}'
+            | '\u{A9E0}'..='\u{A9FF}'
+            | '\u{AA60}'..='\u{AADF}'
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn style(hyphens: Hyphens, lang: Option<&str>) -> InheritedStyle {
+        InheritedStyle {
+            hyphens,
+            lang: lang.map(Into::into),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn soft_hyphens_are_dropped_without_hyphenation() {
+        let text = "extra\u{00AD}ordinary";
+
+        assert_eq!(insert_break_opportunities(text, &style(Hyphens::None, None)), "extraordinary");
+        assert_eq!(insert_break_opportunities(text, &style(Hyphens::Manual, None)), text);
+    }
+
+    #[test]
+    fn text_without_breaks_to_insert_is_borrowed() {
+        let broken = insert_break_opportunities("plain words", &style(Hyphens::Manual, Some("en")));
+
+        assert!(matches!(broken, Cow::Borrowed("plain words")));
+    }
+
+    #[cfg(feature = "hyphenation")]
+    #[test]
+    fn words_are_hyphenated_in_the_language_of_the_text() {
+        let hyphenated = insert_break_opportunities("hyphenation works", &style(Hyphens::Auto, Some("en-US")));
+
+        assert_eq!(hyphenated, "hy\u{00AD}phen\u{00AD}a\u{00AD}tion works");
+        assert_eq!(
+            insert_break_opportunities("hyphenation", &style(Hyphens::Auto, None)),
+            "hyphenation"
+        );
+    }
+
+    #[cfg(feature = "hyphenation")]
+    #[test]
+    fn language_tags_map_to_the_closest_patterns() {
+        use hyphenation::Language;
+
+        use super::hyphenation_dictionary::language_for_tag;
+
+        assert_eq!(language_for_tag("en"), Some(Language::EnglishUS));
+        assert_eq!(language_for_tag("en-AU"), Some(Language::EnglishGB));
+        assert_eq!(language_for_tag("de_CH"), Some(Language::GermanSwiss));
+        assert_eq!(language_for_tag("sr-Latn-RS"), Some(Language::SerbocroatianLatin));
+        assert_eq!(language_for_tag("tlh"), None);
+    }
+
+    #[cfg(feature = "dictionary-line-breaking")]
+    #[test]
+    fn words_of_complex_scripts_are_separated() {
+        let thai = "ภาษาไทยง่ายนิดเดียว";
+        let broken = insert_break_opportunities(thai, &style(Hyphens::Manual, None));
+
+        assert!(broken.contains(ZERO_WIDTH_SPACE));
+        assert_eq!(broken.replace(ZERO_WIDTH_SPACE, ""), thai);
+    }
+}
@@ -0,0 +1,121 @@
+//! Resolving the style properties declared on a node the way takumi does.
+//!
+//! takumi merges the preset, the tailwind classes and the inline style of a node right
+//! before layout and keeps the result to itself. Native features that depend on those
+//! properties resolve them from the same declarations instead.
+
+use takumi::layout::{
+    node::NodeKind,
+    style::{
+        tw::{TailwindProperty, TailwindValues},
+        CssValue, Style,
+    },
+    Viewport,
+};
+
+/// The styles declared on a node, in the order takumi merges them.
+pub(crate) struct Declarations<'n> {
+    preset: Option<&'n Style>,
+    tw: Option<&'n TailwindValues>,
+    style: Option<&'n Style>,
+    viewport: Viewport,
+}
+
+impl<'n> Declarations<'n> {
+    pub(crate) fn new(
+        preset: Option<&'n Style>,
+        tw: Option<&'n TailwindValues>,
+        style: Option<&'n Style>,
+        viewport: Viewport,
+    ) -> Self {
+        Declarations {
+            preset,
+            tw,
+            style,
+            viewport,
+        }
+    }
+
+    /// The declarations of a lowered node.
+    pub(crate) fn of(node: &'n NodeKind, viewport: Viewport) -> Self {
+        let (preset, tw, style) = match node {
+            NodeKind::Container(container) => (&container.preset, &container.tw, &container.style),
+            NodeKind::Image(image) => (&image.preset, &image.tw, &image.style),
+            NodeKind::Text(text) => (&text.preset, &text.tw, &text.style),
+        };
+
+        Declarations::new(preset.as_ref(), tw.as_ref(), style.as_ref(), viewport)
+    }
+
+    /// Returns the declared value of a property: the inline style takes precedence over
+    /// tailwind, which takes precedence over the preset.
+    ///
+    /// Returns `None` when the property is not declared or inherits its parent's value.
+    pub(crate) fn declared<T: Clone + Default, const INHERIT: bool>(
+        &self,
+        property: impl Fn(&Style) -> &CssValue<T, INHERIT>,
+        from_tw: impl Fn(&TailwindProperty) -> Option<T>,
+    ) -> Option<T> {
+        let declared = |style: Option<&Style>| match style.map(&property) {
+            Some(CssValue::Value(value)) => Some(Some(value.clone())),
+            Some(CssValue::Initial) => Some(Some(T::default())),
+            Some(CssValue::Inherit) => Some(None),
+            Some(CssValue::Unset) | None => None,
+        };
+
+        let tw = || {
+            self.tw
+                .into_iter()
+                .flat_map(TailwindValues::iter)
+                .filter(|value| value.breakpoint.is_none_or(|breakpoint| breakpoint.matches(self.viewport)))
+                .filter_map(|value| from_tw(&value.property))
+                .last()
+                .map(Some)
+        };
+
+        declared(self.style)
+            .or_else(tw)
+            .or_else(|| declared(self.preset))
+            .flatten()
+    }
+
+    /// Returns the value of a property that is not inherited.
+    pub(crate) fn resolve<T: Clone + Default>(
+        &self,
+        property: impl Fn(&Style) -> &CssValue<T>,
+        from_tw: impl Fn(&TailwindProperty) -> Option<T>,
+    ) -> T {
+        self.declared(property, from_tw).unwrap_or_default()
+    }
+
+    /// Returns the value of a property that is not inherited and that tailwind classes may
+    /// only change part of, like `col-start-2` does for `grid-column`, by applying every
+    /// class to the value in order.
+    pub(crate) fn resolve_merged<T: Clone + Default>(
+        &self,
+        property: impl Fn(&Style) -> &CssValue<T>,
+        apply_tw: impl Fn(&mut T, &TailwindProperty),
+    ) -> T {
+        let declared = |style: Option<&Style>| match style.map(&property) {
+            Some(CssValue::Value(value)) => Some(value.clone()),
+            Some(CssValue::Initial) => Some(T::default()),
+            Some(CssValue::Inherit | CssValue::Unset) | None => None,
+        };
+
+        if let Some(value) = declared(self.style) {
+            return value;
+        }
+
+        let mut value = declared(self.preset).unwrap_or_default();
+        for tw in self
+            .tw
+            .into_iter()
+            .flat_map(TailwindValues::iter)
+            .filter(|value| value.breakpoint.is_none_or(|breakpoint| breakpoint.matches(self.viewport)))
+        {
+            apply_tw(&mut value, &tw.property);
+        }
+
+        value
+    }
+}
@@ -0,0 +1,460 @@
+//! QR codes and barcodes, encoded natively and drawn as vector modules.
+//!
+//! A code is lowered into an image of SVG markup with one unit per module, which is drawn
+//! natively at the laid-out size like an SVG node. Its modules are drawn without
+//! antialiasing, so their edges stay sharp at any size.
+
+use std::fmt::Write;
+
+use qrcodegen::{QrCode, QrCodeEcc};
+use serde::Deserialize;
+use takumi::layout::{
+    node::ImageNode,
+    style::{tw::TailwindValues, Color},
+    Viewport,
+};
+
+use super::{cascade::Declarations, InheritedStyle, Style};
+
+/// The size of a QR code module in pixels, unless the node is sized by its style.
+const QR_MODULE_SIZE: u32 = 4;
+
+/// The width of a barcode module in pixels, unless the node is sized by its style.
+const BARCODE_MODULE_WIDTH: u32 = 2;
+
+/// The height of a barcode in pixels, unless the node is sized by its style.
+const BARCODE_HEIGHT: u32 = 80;
+
+/// A node that draws data as a QR code, in the node's `color`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QrCodeNode {
+    /// Default style presets from HTML element type (lowest priority)
+    pub preset: Option<takumi::layout::style::Style>,
+    /// The styling properties for this QR code node
+    pub style: Option<Style>,
+    /// The text to encode
+    pub data: String,
+    /// The lowest error correction level to encode with, raised when the code fits anyway
+    pub error_correction: Option<ErrorCorrection>,
+    /// The width of the empty margin around the code in modules, 4 by default
+    pub quiet_zone: Option<u32>,
+    /// The tailwind properties for this QR code node
+    pub tw: Option<TailwindValues>,
+}
+
+/// How much of a QR code can be damaged and still be read.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorCorrection {
+    /// About 7% of the code can be restored.
+    Low,
+    /// About 15% of the code can be restored.
+    #[default]
+    Medium,
+    /// About 25% of the code can be restored.
+    Quartile,
+    /// About 30% of the code can be restored.
+    High,
+}
+
+/// A node that draws data as a barcode, in the node's `color`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BarcodeNode {
+    /// Default style presets from HTML element type (lowest priority)
+    pub preset: Option<takumi::layout::style::Style>,
+    /// The styling properties for this barcode node
+    pub style: Option<Style>,
+    /// The text to encode
+    pub data: String,
+    /// The symbology to encode the data with
+    pub format: BarcodeFormat,
+    /// The width of the empty margins on both sides in modules, the format's minimum by default
+    pub quiet_zone: Option<u32>,
+    /// The tailwind properties for this barcode node
+    pub tw: Option<TailwindValues>,
+}
+
+/// The symbology of a barcode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BarcodeFormat {
+    /// Code 128, encoding printable ASCII text.
+    Code128,
+    /// EAN-13, encoding 12 digits and a check digit, which is added when left out.
+    Ean13,
+    /// EAN-8, encoding 7 digits and a check digit, which is added when left out.
+    Ean8,
+}
+
+impl QrCodeNode {
+    /// Resolves the native properties of this QR code node.
+    pub(crate) fn inherited_style(&self, parent: &InheritedStyle, viewport: Viewport) -> InheritedStyle {
+        let declared = Declarations::new(
+            self.preset.as_ref(),
+            self.tw.as_ref(),
+            self.style.as_ref().map(|style| &style.base),
+            viewport,
+        );
+
+        parent.inherit(None, self.style.as_ref().map(|style| &style.native), &declared)
+    }
+
+    /// Encodes the data into a QR code.
+    pub(crate) fn encode(&self) -> Result<QrCode, String> {
+        let ecc = match self.error_correction.unwrap_or_default() {
+            ErrorCorrection::Low => QrCodeEcc::Low,
+            ErrorCorrection::Medium => QrCodeEcc::Medium,
+            ErrorCorrection::Quartile => QrCodeEcc::Quartile,
+            ErrorCorrection::High => QrCodeEcc::High,
+        };
+
+        QrCode::encode_text(&self.data, ecc).map_err(|e| format!("QR code data cannot be encoded: {e}"))
+    }
+
+    /// Lowers the QR code node into a takumi image node of its modules, which is drawn
+    /// natively once it is laid out.
+    pub(crate) fn into_takumi(self, parent: &InheritedStyle, viewport: Viewport) -> takumi::layout::node::NodeKind {
+        let color = self.inherited_style(parent, viewport).color.unwrap_or(Color::black());
+        let quiet_zone = self.quiet_zone.unwrap_or(4) as i32;
+
+        // The data was checked to encode before lowering.
+        let markup = self.encode().map_or_else(
+            |_| String::new(),
+            |code| {
+                let size = code.size() + 2 * quiet_zone;
+                let rows =
+                    (0..code.size()).map(|y| (0..code.size()).map(|x| code.get_module(x, y)).collect::<Vec<_>>());
+
+                let mut path = String::new();
+                for (y, row) in rows.enumerate() {
+                    write_runs(&mut path, &row, quiet_zone, y as i32 + quiet_zone, 1);
+                }
+
+                write_markup(
+                    size as u32 * QR_MODULE_SIZE,
+                    size as u32 * QR_MODULE_SIZE,
+                    size as u32,
+                    size as u32,
+                    &path,
+                    color,
+                )
+            },
+        );
+
+        ImageNode {
+            preset: self.preset,
+            style: self.style.map(|style| style.base),
+            src: markup.into(),
+            width: None,
+            height: None,
+            tw: self.tw,
+        }
+        .into()
+    }
+}
+
+impl BarcodeNode {
+    /// Resolves the native properties of this barcode node.
+    pub(crate) fn inherited_style(&self, parent: &InheritedStyle, viewport: Viewport) -> InheritedStyle {
+        let declared = Declarations::new(
+            self.preset.as_ref(),
+            self.tw.as_ref(),
+            self.style.as_ref().map(|style| &style.base),
+            viewport,
+        );
+
+        parent.inherit(None, self.style.as_ref().map(|style| &style.native), &declared)
+    }
+
+    /// Encodes the data into modules, `true` for bars.
+    pub(crate) fn encode(&self) -> Result<Vec<bool>, String> {
+        match self.format {
+            BarcodeFormat::Code128 => encode_code128(&self.data),
+            BarcodeFormat::Ean13 => encode_ean(&self.data, 13),
+            BarcodeFormat::Ean8 => encode_ean(&self.data, 8),
+        }
+    }
+
+    /// Lowers the barcode node into a takumi image node of its bars, which is drawn
+    /// natively once it is laid out.
+    pub(crate) fn into_takumi(self, parent: &InheritedStyle, viewport: Viewport) -> takumi::layout::node::NodeKind {
+        let color = self.inherited_style(parent, viewport).color.unwrap_or(Color::black());
+        let (left, right) = match (self.quiet_zone, self.format) {
+            (Some(quiet_zone), _) => (quiet_zone, quiet_zone),
+            (None, BarcodeFormat::Code128) => (10, 10),
+            (None, BarcodeFormat::Ean13) => (11, 7),
+            (None, BarcodeFormat::Ean8) => (7, 7),
+        };
+
+        // The data was checked to encode before lowering.
+        let markup = self.encode().map_or_else(
+            |_| String::new(),
+            |modules| {
+                let width = left + modules.len() as u32 + right;
+
+                let mut path = String::new();
+                write_runs(&mut path, &modules, left as i32, 0, 1);
+
+                write_markup(width * BARCODE_MODULE_WIDTH, BARCODE_HEIGHT, width, 1, &path, color)
+            },
+        );
+
+        ImageNode {
+            preset: self.preset,
+            style: self.style.map(|style| style.base),
+            src: markup.into(),
+            width: None,
+            height: None,
+            tw: self.tw,
+        }
+        .into()
+    }
+}
+
+/// Writes path data filling each run of dark modules of a row with a `height` tall rectangle.
+fn write_runs(path: &mut String, modules: &[bool], x: i32, y: i32, height: i32) {
+    let mut start = None;
+
+    for (index, dark) in modules.iter().chain([&false]).enumerate() {
+        match (start, dark) {
+            (None, true) => start = Some(index),
+            (Some(run_start), false) => {
+                let width = (index - run_start) as i32;
+                let _ = write!(path, "M{} {y}h{width}v{height}h-{width}z", x + run_start as i32);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Writes the markup of modules drawn with `path`, `width` by `height` pixels large and
+/// stretched from a `view_width` by `view_height` module grid.
+fn write_markup(width: u32, height: u32, view_width: u32, view_height: u32, path: &str, color: Color) -> String {
+    let [red, green, blue, alpha] = color.0;
+
+    format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {view_width} {view_height}" preserveAspectRatio="none" shape-rendering="crispEdges"><path fill="rgba({red}, {green}, {blue}, {})" d="{path}"/></svg>"#,
+        f32::from(alpha) / 255.0,
+    )
+}
+
+/// The widths of the alternating bars and spaces of each Code 128 symbol, by value.
+const CODE128_PATTERNS: [&str; 107] = [
+    "212222", "222122", "222221", "121223", "121322", "131222", "122213", "122312", "132212", "221213", "221312",
+    "231212", "112232", "122132", "122231", "113222", "123122", "123221", "223211", "221132", "221231", "213212",
+    "223112", "312131", "311222", "321122", "321221", "312212", "322112", "322211", "212123", "212321", "232121",
+    "111323", "131123", "131321", "112313", "132113", "132311", "211313", "231113", "231311", "112133", "112331",
+    "132131", "113123", "113321", "133121", "313121", "211331", "231131", "213113", "213311", "213131", "311123",
+    "311321", "331121", "312113", "312311", "332111", "314111", "221411", "431111", "111224", "111422", "121124",
+    "121421", "141122", "141221", "112214", "112412", "122114", "122411", "142112", "142211", "241211", "221114",
+    "413111", "241112", "134111", "111242", "121142", "121241", "114212", "124112", "124211", "411212", "421112",
+    "421211", "212141", "214121", "412121", "111143", "111341", "131141", "114113", "114311", "411113", "411311",
+    "113141", "114131", "311141", "411131", "211412", "211214", "211232", "2331112",
+];
+
+const CODE128_CODE_B: u8 = 100;
+const CODE128_CODE_C: u8 = 99;
+const CODE128_START_B: u8 = 104;
+const CODE128_START_C: u8 = 105;
+const CODE128_STOP: u8 = 106;
+
+/// Encodes printable ASCII text as Code 128, packing runs of digits in pairs with code set C.
+fn encode_code128(data: &str) -> Result<Vec<bool>, String> {
+    if data.is_empty() || !data.bytes().all(|byte| (b' '..=b'~').contains(&byte)) {
+        return Err("Code 128 data must be non-empty printable ASCII text".to_string());
+    }
+
+    let bytes = data.as_bytes();
+    let digits_at = |index: usize| bytes[index..].iter().take_while(|byte| byte.is_ascii_digit()).count();
+
+    let leading_digits = digits_at(0);
+    let mut set_c = leading_digits >= 4 || (leading_digits == 2 && bytes.len() == 2);
+    let mut values = vec![if set_c { CODE128_START_C } else { CODE128_START_B }];
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let digits = digits_at(index);
+
+        if set_c {
+            if digits >= 2 {
+                values.push((bytes[index] - b'0') * 10 + bytes[index + 1] - b'0');
+                index += 2;
+            } else {
+                values.push(CODE128_CODE_B);
+                set_c = false;
+            }
+        } else if digits >= 6 || (digits >= 4 && index + digits == bytes.len()) {
+            // An odd digit stays in code set B, so the rest pairs up.
+            if digits % 2 == 1 {
+                values.push(bytes[index] - b' ');
+                index += 1;
+            }
+            values.push(CODE128_CODE_C);
+            set_c = true;
+        } else {
+            values.push(bytes[index] - b' ');
+            index += 1;
+        }
+    }
+
+    let checksum = values
+        .iter()
+        .enumerate()
+        .map(|(position, value)| position.max(1) as u32 * u32::from(*value))
+        .sum::<u32>()
+        % 103;
+    values.push(checksum as u8);
+    values.push(CODE128_STOP);
+
+    let mut modules = Vec::new();
+    for value in values {
+        for (element, width) in CODE128_PATTERNS[value as usize].bytes().enumerate() {
+            modules.extend(std::iter::repeat_n(element % 2 == 0, (width - b'0') as usize));
+        }
+    }
+
+    Ok(modules)
+}
+
+/// The modules of each digit in the left half of an EAN with odd parity. The right half
+/// inverts them, and even parity mirrors the right half.
+const EAN_L_CODES: [u8; 10] = [
+    0b0001101, 0b0011001, 0b0010011, 0b0111101, 0b0100011, 0b0110001, 0b0101111, 0b0111011, 0b0110111, 0b0001011,
+];
+
+/// Which digits of the left half of an EAN-13 have even parity, by its first digit, from
+/// the most significant bit.
+const EAN13_PARITIES: [u8; 10] = [
+    0b000000, 0b001011, 0b001101, 0b001110, 0b010011, 0b011001, 0b011100, 0b010101, 0b010110, 0b011010,
+];
+
+/// Encodes digits as an EAN-13 or EAN-8, adding the check digit when it is left out.
+fn encode_ean(data: &str, length: usize) -> Result<Vec<bool>, String> {
+    if !data.bytes().all(|byte| byte.is_ascii_digit()) || !(length - 1..=length).contains(&data.len()) {
+        return Err(format!("EAN-{length} data must be {} or {length} digits", length - 1));
+    }
+
+    let mut digits: Vec<u8> = data.bytes().map(|byte| byte - b'0').collect();
+
+    // Digits are weighted 3 and 1 alternately, from the last one before the check digit.
+    let weighted_sum: u32 = digits[..length - 1]
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(position, digit)| u32::from(*digit) * if position % 2 == 0 { 3 } else { 1 })
+        .sum();
+    let check_digit = ((10 - weighted_sum % 10) % 10) as u8;
+
+    match digits.get(length - 1) {
+        Some(digit) if *digit != check_digit => {
+            return Err(format!("EAN-{length} check digit of {data} should be {check_digit}"));
+        }
+        Some(_) => {}
+        None => digits.push(check_digit),
+    }
+
+    // EAN-13 encodes its first digit in the parities of the left half.
+    let (parities, digits) = match length {
+        13 => (EAN13_PARITIES[digits[0] as usize], &digits[1..]),
+        _ => (0, &digits[..]),
+    };
+    let (left, right) = digits.split_at(digits.len() / 2);
+
+    let mut modules = Vec::new();
+    let mut push_bits = |bits: u8, count: u32| modules.extend((0..count).rev().map(|bit| bits >> bit & 1 == 1));
+
+    push_bits(0b101, 3);
+    for (position, digit) in left.iter().enumerate() {
+        let code = EAN_L_CODES[*digit as usize];
+        let even = parities >> (left.len() - 1 - position) & 1 == 1;
+        push_bits(if even { (!code & 0x7F).reverse_bits() >> 1 } else { code }, 7);
+    }
+    push_bits(0b01010, 5);
+    for digit in right {
+        push_bits(!EAN_L_CODES[*digit as usize] & 0x7F, 7);
+    }
+    push_bits(0b101, 3);
+
+    Ok(modules)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Expands the widths of alternating bars and spaces into modules.
+    fn from_widths(symbols: &[&str]) -> Vec<bool> {
+        symbols
+            .concat()
+            .bytes()
+            .enumerate()
+            .flat_map(|(element, width)| std::iter::repeat_n(element % 2 == 0, (width - b'0') as usize))
+            .collect()
+    }
+
+    fn from_bits(bits: &str) -> Vec<bool> {
+        bits.bytes().map(|bit| bit == b'1').collect()
+    }
+
+    #[test]
+    fn code128_uses_set_b_for_text() {
+        // Start B, "W", "i", "k", "i", checksum 101, stop.
+        let expected = from_widths(&["211214", "311321", "142112", "241211", "142112", "311141", "2331112"]);
+        assert_eq!(encode_code128("Wiki").unwrap(), expected);
+    }
+
+    #[test]
+    fn code128_pairs_digits_with_set_c() {
+        // Start C, 12, 34, checksum 82, stop.
+        let expected = from_widths(&["211232", "112232", "131123", "121241", "2331112"]);
+        assert_eq!(encode_code128("1234").unwrap(), expected);
+
+        // Start C, 12, checksum 14, stop.
+        let expected = from_widths(&["211232", "112232", "122231", "2331112"]);
+        assert_eq!(encode_code128("12").unwrap(), expected);
+    }
+
+    #[test]
+    fn code128_switches_to_set_c_after_an_odd_digit() {
+        // Start B, "A", "1", Code C, 23, 45, 67, checksum 54, stop.
+        let expected = from_widths(&[
+            "211214", "111323", "123221", "113141", "312131", "113123", "141122", "311123", "2331112",
+        ]);
+        assert_eq!(encode_code128("A1234567").unwrap(), expected);
+    }
+
+    #[test]
+    fn code128_rejects_text_outside_printable_ascii() {
+        assert!(encode_code128("").is_err());
+        assert!(encode_code128("café").is_err());
+    }
+
+    #[test]
+    fn ean13_encodes_parities_and_check_digit() {
+        let expected = from_bits(
+            "10100011010100111010111101111010001001011001101010100001010000101000010111010010000101100110101",
+        );
+        assert_eq!(encode_ean("4006381333931", 13).unwrap(), expected);
+        assert_eq!(encode_ean("400638133393", 13).unwrap(), expected);
+    }
+
+    #[test]
+    fn ean8_encodes_check_digit() {
+        let expected = from_bits("1010001011010111101111010110111010101001110111001010001001011100101");
+        assert_eq!(encode_ean("96385074", 8).unwrap(), expected);
+        assert_eq!(encode_ean("9638507", 8).unwrap(), expected);
+    }
+
+    #[test]
+    fn ean_rejects_wrong_check_digit_and_length() {
+        assert_eq!(
+            encode_ean("4006381333932", 13).unwrap_err(),
+            "EAN-13 check digit of 4006381333932 should be 1"
+        );
+        assert!(encode_ean("40063813339", 13).is_err());
+        assert!(encode_ean("9638507a", 8).is_err());
+    }
+}
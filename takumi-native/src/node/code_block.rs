@@ -0,0 +1,460 @@
+//! Blocks of source code, highlighted natively and set in rows of styled text.
+//!
+//! A code block is expanded into a column of rows before anything else is resolved, one
+//! per line of code, each holding the line's number and a text node with a span per token.
+//! Lines are set on their own, so that each one is cut at the edge of the block on its own.
+
+use serde::Deserialize;
+use takumi::layout::style::{
+    tw::TailwindValues, Color, ColorInput, CssValue, Display, FlexDirection, FlexGrow, FontFamily, FontStyle,
+    FontWeight, Length, LineClamp, Overflow, PercentageNumber, SpacePair, TextDecorationLine, TextOverflow,
+    TextWrapMode, WhiteSpaceCollapse, WordBreak,
+};
+
+use super::{ContainerNode, Direction, NativeStyle, NodeKind, Style, TextNode, TextSpan};
+
+/// The theme code is highlighted with by default.
+const DEFAULT_THEME: &str = "base16-ocean.dark";
+
+/// A node that highlights source code and sets it in a monospace block, a row per line.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeBlockNode {
+    /// Default style presets from HTML element type (lowest priority)
+    pub preset: Option<takumi::layout::style::Style>,
+    /// The styling properties for this code block node
+    pub style: Option<Style>,
+    /// The source code
+    pub code: String,
+    /// The language to highlight the code as, by name or file extension, e.g. `rust` or `cs`
+    pub language: Option<String>,
+    /// The name of the color theme, `base16-ocean.dark` by default
+    pub theme: Option<String>,
+    /// Whether every line starts with its number
+    #[serde(default)]
+    pub line_numbers: bool,
+    /// The number of the first line, 1 by default
+    pub first_line_number: Option<u32>,
+    /// The number of columns between tab stops, 4 by default
+    pub tab_width: Option<u32>,
+    /// How lines too long for the block are cut
+    pub long_lines: Option<LongLines>,
+    /// The tailwind properties for this code block node
+    pub tw: Option<TailwindValues>,
+}
+
+/// How lines of code too long for their block are cut.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LongLines {
+    /// Lines are clipped at the edge of the block.
+    #[default]
+    Clip,
+    /// Lines end with an ellipsis where they reach the edge of the block.
+    Ellipsis,
+    /// Lines wrap, anywhere if they have to.
+    Wrap,
+}
+
+/// A run of code in one style.
+struct Token {
+    text: String,
+    color: Option<Color>,
+    bold: bool,
+    italic: bool,
+    underline: bool,
+}
+
+/// Highlighted lines of code, with the colors of their theme.
+struct Highlighted {
+    lines: Vec<Vec<Token>>,
+    background: Option<Color>,
+    foreground: Option<Color>,
+    gutter_foreground: Option<Color>,
+}
+
+impl CodeBlockNode {
+    /// Expands the code block into a container of its lines.
+    pub(crate) fn expand(&self) -> Result<ContainerNode, String> {
+        let code = expand_tabs(&self.code, self.tab_width.unwrap_or(4).max(1) as usize);
+        let highlighted = highlight(
+            &code,
+            self.language.as_deref(),
+            self.theme.as_deref().unwrap_or(DEFAULT_THEME),
+        )?;
+
+        let first_line_number = self.first_line_number.unwrap_or(1);
+        let last_line_number = first_line_number as usize + highlighted.lines.len().saturating_sub(1);
+        let number_width = last_line_number.to_string().len();
+        let long_lines = self.long_lines.unwrap_or_default();
+
+        let rows = highlighted
+            .lines
+            .into_iter()
+            .enumerate()
+            .map(|(index, tokens)| {
+                let mut cells = Vec::with_capacity(2);
+
+                if self.line_numbers {
+                    let number = first_line_number as usize + index;
+                    cells.push(NodeKind::Text(line_number(
+                        number,
+                        number_width,
+                        highlighted.gutter_foreground,
+                    )));
+                }
+                cells.push(NodeKind::Text(line(tokens, long_lines)));
+
+                NodeKind::Container(ContainerNode {
+                    preset: None,
+                    style: None,
+                    children: Some(cells.into_boxed_slice()),
+                    tw: None,
+                    lang: None,
+                    fragments: None,
+                })
+            })
+            .collect();
+
+        let mut preset = self.preset.clone().unwrap_or_default();
+        set_default(&mut preset.display, Display::Flex);
+        set_default(&mut preset.flex_direction, FlexDirection::Column);
+        set_default(&mut preset.font_family, Some(FontFamily::from("monospace")));
+        if let Some(background) = highlighted.background {
+            set_default(&mut preset.background_color, Some(ColorInput::Value(background)));
+        }
+        if let Some(foreground) = highlighted.foreground {
+            set_default(&mut preset.color, ColorInput::Value(foreground));
+        }
+
+        // Code reads left to right, whatever the direction of the text around it.
+        let mut style = self.style.clone().unwrap_or_default();
+        style.native.direction.get_or_insert(Direction::Ltr);
+
+        Ok(ContainerNode {
+            preset: Some(preset),
+            style: Some(style),
+            children: Some(rows),
+            tw: self.tw.clone(),
+            lang: None,
+            fragments: None,
+        })
+    }
+}
+
+/// Sets a property of a preset, unless it is already set.
+fn set_default<T, const DEFAULT_INHERIT: bool>(property: &mut CssValue<T, DEFAULT_INHERIT>, value: T) {
+    if matches!(property, CssValue::Unset) {
+        *property = CssValue::Value(value);
+    }
+}
+
+/// Lays out the number of a line, padded to the width of the largest number.
+fn line_number(number: usize, width: usize, color: Option<Color>) -> TextNode {
+    let mut style = takumi::layout::style::Style {
+        flex_shrink: CssValue::Value(Some(FlexGrow(0.0))),
+        padding_right: CssValue::Value(Some(Length::Em(1.0))),
+        white_space_collapse: CssValue::Value(Some(WhiteSpaceCollapse::Preserve)),
+        ..Default::default()
+    };
+    match color {
+        Some(color) => style.color = CssValue::Value(ColorInput::Value(color)),
+        None => style.opacity = CssValue::Value(PercentageNumber(0.5)),
+    }
+
+    TextNode {
+        preset: None,
+        style: Some(Style {
+            base: style,
+            native: NativeStyle::default(),
+        }),
+        text: format!("{number:>width$}"),
+        spans: None,
+        tw: None,
+        fit: None,
+        lang: None,
+    }
+}
+
+/// Lays out a line of code, cut at the edge of the block as `long_lines` asks.
+fn line(tokens: Vec<Token>, long_lines: LongLines) -> TextNode {
+    let mut style = takumi::layout::style::Style {
+        flex_grow: CssValue::Value(Some(FlexGrow(1.0))),
+        min_width: CssValue::Value(Length::Px(0.0)),
+        overflow: CssValue::Value(SpacePair::from_single(Overflow::Hidden)),
+        white_space_collapse: CssValue::Value(Some(WhiteSpaceCollapse::Preserve)),
+        ..Default::default()
+    };
+
+    match long_lines {
+        LongLines::Clip => style.text_wrap_mode = CssValue::Value(Some(TextWrapMode::NoWrap)),
+        // takumi only places an ellipsis where a clamped line breaks, so the line breaks anywhere.
+        LongLines::Ellipsis => {
+            style.word_break = CssValue::Value(WordBreak::BreakAll);
+            style.text_overflow = CssValue::Value(TextOverflow::Ellipsis);
+            style.line_clamp = CssValue::Value(Some(LineClamp {
+                count: 1,
+                ellipsis: None,
+            }));
+        }
+        LongLines::Wrap => style.word_break = CssValue::Value(WordBreak::BreakWord),
+    }
+
+    // An empty line still takes up the height of a line.
+    let (text, spans) = match tokens.is_empty() {
+        true => (" ".to_string(), None),
+        false => (String::new(), Some(tokens.into_iter().map(span).collect())),
+    };
+
+    TextNode {
+        preset: None,
+        style: Some(Style {
+            base: style,
+            native: NativeStyle::default(),
+        }),
+        text,
+        spans,
+        tw: None,
+        fit: None,
+        lang: None,
+    }
+}
+
+fn span(token: Token) -> TextSpan {
+    let mut style = takumi::layout::style::Style::default();
+
+    if let Some(color) = token.color {
+        style.color = CssValue::Value(ColorInput::Value(color));
+    }
+    if token.bold {
+        style.font_weight = CssValue::Value(FontWeight::from(700.0));
+    }
+    if token.italic {
+        style.font_style = CssValue::Value(FontStyle::italic());
+    }
+    if token.underline {
+        style.text_decoration_line = CssValue::Value(Some(Box::new([TextDecorationLine::Underline])));
+    }
+
+    TextSpan {
+        style: Some(Style {
+            base: style,
+            native: NativeStyle::default(),
+        }),
+        text: token.text,
+        image: None,
+        tw: None,
+    }
+}
+
+/// Replaces every tab with the spaces up to the next tab stop.
+fn expand_tabs(code: &str, tab_width: usize) -> String {
+    if !code.contains('\t') {
+        return code.to_owned();
+    }
+
+    let mut expanded = String::with_capacity(code.len());
+    let mut column = 0;
+
+    for c in code.chars() {
+        match c {
+            '\t' => {
+                let spaces = tab_width - column % tab_width;
+                expanded.extend(std::iter::repeat_n(' ', spaces));
+                column += spaces;
+            }
+            '\n' => {
+                expanded.push(c);
+                column = 0;
+            }
+            _ => {
+                expanded.push(c);
+                column += 1;
+            }
+        }
+    }
+
+    expanded
+}
+
+/// Splits code into lines, without their line endings.
+fn lines(code: &str) -> impl Iterator<Item = &str> {
+    code.split('\n').map(|line| line.strip_suffix('\r').unwrap_or(line))
+}
+
+/// Highlights code with the syntax of a language and a theme. Code in a language without
+/// a syntax is set in the theme's foreground color.
+#[cfg(feature = "syntax-highlighting")]
+fn highlight(code: &str, language: Option<&str>, theme: &str) -> Result<Highlighted, String> {
+    use std::sync::LazyLock;
+
+    use syntect::{
+        easy::HighlightLines,
+        highlighting::{FontStyle, ThemeSet},
+        parsing::SyntaxSet,
+    };
+
+    /// The syntaxes and themes are deserialized from the embedded dumps on first use.
+    static SYNTAXES: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+    static THEMES: LazyLock<ThemeSet> = LazyLock::new(ThemeSet::load_defaults);
+
+    let Some(theme) = THEMES.themes.get(theme) else {
+        let names: Vec<&str> = THEMES.themes.keys().map(String::as_str).collect();
+        return Err(format!(
+            "unknown code theme \"{theme}\", expected one of: {}",
+            names.join(", ")
+        ));
+    };
+
+    let syntax = language
+        .and_then(|language| SYNTAXES.find_syntax_by_token(language))
+        .unwrap_or_else(|| SYNTAXES.find_syntax_plain_text());
+    let mut highlighter = HighlightLines::new(syntax, theme);
+    let color = |color: syntect::highlighting::Color| Color([color.r, color.g, color.b, color.a]);
+
+    let mut highlighted_lines = Vec::new();
+    for line in lines(code) {
+        // The syntaxes match line endings, so every line is highlighted with one.
+        let line = format!("{line}\n");
+        let regions = highlighter
+            .highlight_line(&line, &SYNTAXES)
+            .map_err(|e| e.to_string())?;
+
+        let tokens = regions
+            .into_iter()
+            .map(|(style, text)| (style, text.trim_end_matches('\n')))
+            .filter(|(_, text)| !text.is_empty())
+            .map(|(style, text)| Token {
+                text: text.to_owned(),
+                color: Some(color(style.foreground)),
+                bold: style.font_style.contains(FontStyle::BOLD),
+                italic: style.font_style.contains(FontStyle::ITALIC),
+                underline: style.font_style.contains(FontStyle::UNDERLINE),
+            })
+            .collect();
+        highlighted_lines.push(tokens);
+    }
+
+    Ok(Highlighted {
+        lines: highlighted_lines,
+        background: theme.settings.background.map(color),
+        foreground: theme.settings.foreground.map(color),
+        gutter_foreground: theme.settings.gutter_foreground.map(color),
+    })
+}
+
+/// Sets code in the block's own colors, without a syntax or theme to highlight it with.
+#[cfg(not(feature = "syntax-highlighting"))]
+fn highlight(code: &str, _language: Option<&str>, _theme: &str) -> Result<Highlighted, String> {
+    let lines = lines(code)
+        .map(|line| {
+            let token = Token {
+                text: line.to_owned(),
+                color: None,
+                bold: false,
+                italic: false,
+                underline: false,
+            };
+            [token].into_iter().filter(|token| !token.text.is_empty()).collect()
+        })
+        .collect();
+
+    Ok(Highlighted {
+        lines,
+        background: None,
+        foreground: None,
+        gutter_foreground: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn expand(code_block: serde_json::Value) -> Result<ContainerNode, String> {
+        serde_json::from_value::<CodeBlockNode>(code_block).unwrap().expand()
+    }
+
+    /// The text of every cell of every row.
+    fn rows(block: &ContainerNode) -> Vec<Vec<String>> {
+        block
+            .children
+            .iter()
+            .flatten()
+            .map(|row| {
+                let NodeKind::Container(row) = row else {
+                    panic!("lines are not set in rows");
+                };
+                row.children
+                    .iter()
+                    .flatten()
+                    .map(|cell| match cell {
+                        NodeKind::Text(text) => {
+                            let spans = text.spans.iter().flatten().map(|span| span.text.as_str());
+                            std::iter::once(text.text.as_str()).chain(spans).collect()
+                        }
+                        _ => panic!("cells are not text"),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn tabs_expand_to_the_next_tab_stop() {
+        assert_eq!(expand_tabs("a\tb\n\tc\t", 4), "a   b\n    c   ");
+        assert_eq!(expand_tabs("ab\tc", 2), "ab  c");
+    }
+
+    #[test]
+    fn lines_are_numbered_and_padded_to_the_widest_number() {
+        let block = expand(json!({
+            "code": "let a = 1;\r\n\r\nlet b = 2;",
+            "lineNumbers": true,
+            "firstLineNumber": 9,
+        }))
+        .unwrap();
+
+        assert_eq!(
+            rows(&block),
+            [
+                vec![" 9".to_owned(), "let a = 1;".to_owned()],
+                vec!["10".to_owned(), " ".to_owned()],
+                vec!["11".to_owned(), "let b = 2;".to_owned()],
+            ]
+        );
+    }
+
+    #[test]
+    fn code_reads_left_to_right() {
+        let block = expand(json!({ "code": "x", "style": { "direction": "rtl" } })).unwrap();
+        assert_eq!(block.style.unwrap().native.direction, Some(Direction::Rtl));
+
+        let block = expand(json!({ "code": "x" })).unwrap();
+        assert_eq!(block.style.unwrap().native.direction, Some(Direction::Ltr));
+    }
+
+    #[cfg(feature = "syntax-highlighting")]
+    #[test]
+    fn code_is_highlighted_with_the_theme() {
+        let block = expand(json!({ "code": "fn main() {}", "language": "rs" })).unwrap();
+
+        let NodeKind::Container(row) = &block.children.as_deref().unwrap()[0] else {
+            panic!("lines are not set in rows");
+        };
+        let NodeKind::Text(line) = &row.children.as_deref().unwrap()[0] else {
+            panic!("lines are not text");
+        };
+        let spans = line.spans.as_deref().unwrap();
+        assert!(spans.len() > 1);
+
+        let colors: Vec<_> = spans.iter().map(|span| span.style.as_ref().unwrap().base.color).collect();
+        assert!(colors.iter().all(|color| matches!(color, CssValue::Value(ColorInput::Value(_)))));
+        assert!(colors.windows(2).any(|pair| pair[0] != pair[1]));
+
+        let error = expand(json!({ "code": "x", "theme": "nope" })).unwrap_err();
+        assert!(error.starts_with("unknown code theme \"nope\", expected one of: "), "{error}");
+    }
+}
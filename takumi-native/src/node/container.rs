@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use takumi::layout::{style::tw::TailwindValues, Viewport};
+
+use super::{cascade::Declarations, direction, vertical, Direction, Fragment, InheritedStyle, NodeKind, Style};
+
+/// A container node that can hold child nodes.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ContainerNode {
+    /// Default style presets from HTML element type (lowest priority)
+    pub preset: Option<takumi::layout::style::Style>,
+    /// The styling properties for this container
+    pub style: Option<Style>,
+    /// The child nodes contained within this container
+    pub children: Option<Box<[NodeKind]>>,
+    /// The tailwind properties for this container node
+    pub tw: Option<TailwindValues>,
+    /// The language of the content as a BCP 47 tag, inherited by the children and used for
+    /// case mapping and font fallback
+    pub lang: Option<String>,
+    /// The fragments the nodes inside this container can instantiate, by ID
+    pub fragments: Option<HashMap<String, Fragment>>,
+}
+
+impl ContainerNode {
+    /// Resolves the native properties this container passes down to its children.
+    pub(crate) fn inherited_style(&self, parent: &InheritedStyle, viewport: Viewport) -> InheritedStyle {
+        let declared = Declarations::new(
+            self.preset.as_ref(),
+            self.tw.as_ref(),
+            self.style.as_ref().map(|style| &style.base),
+            viewport,
+        );
+
+        parent.inherit(self.lang.as_deref(), self.style.as_ref().map(|style| &style.native), &declared)
+    }
+
+    pub(crate) fn into_takumi(self, parent: &InheritedStyle, viewport: Viewport) -> takumi::layout::node::NodeKind {
+        let inherited = self.inherited_style(parent, viewport);
+
+        let mut node = takumi::layout::node::ContainerNode {
+            preset: self.preset,
+            style: self.style.map(|style| style.base),
+            children: self.children.map(|children| {
+                children
+                    .into_vec()
+                    .into_iter()
+                    .map(|child| child.into_takumi(&inherited, viewport))
+                    .collect()
+            }),
+            tw: self.tw,
+        }
+        .into();
+
+        if inherited.writing_mode.is_vertical() {
+            vertical::rotate_container(&mut node, inherited.writing_mode, viewport);
+        } else if inherited.direction == Some(Direction::Rtl) {
+            if let Some(property) = direction::mirror_container(&mut node, viewport) {
+                if let takumi::layout::node::NodeKind::Container(container) = &mut node {
+                    for child in container.children.iter_mut().flatten() {
+                        direction::mirror_self_alignment(child, property, viewport);
+                    }
+                }
+            }
+        }
+
+        node
+    }
+}
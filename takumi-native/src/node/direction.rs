@@ -0,0 +1,323 @@
+//! Laying out right-to-left content.
+//!
+//! The text layout resolves bidirectional text on its own, taking the base direction of
+//! a paragraph from its first strong character. An explicit direction is given to it by
+//! a directional mark in front of the text, which `textAlign: start/end` then follows.
+//!
+//! takumi lays out flex and grid containers from left to right only, so a right-to-left
+//! container is mirrored before layout instead: rows and grid columns are reversed, and
+//! start and end are swapped for the alignment along the inline axis, both on the
+//! container and on the self alignment of its children.
+//!
+//! Once the columns of a grid are reversed, column line `n` counted from the right is
+//! line `-n` counted from the left, so the column placement of its children is mirrored
+//! by negating their lines and swapping start and end. Items left to auto-placement in
+//! the inline axis are still placed from the left.
+
+use takumi::layout::{
+    node::NodeKind,
+    style::{
+        tw::TailwindProperty, AlignItems, CssValue, Display, FlexDirection, GridLine, GridPlacement, GridRepeatTrack,
+        GridTemplateAreas, GridTemplateComponent, JustifyContent, Style,
+    },
+    Viewport,
+};
+
+use super::{cascade::Declarations, Direction};
+
+const LEFT_TO_RIGHT_MARK: char = '\u{200E}';
+const RIGHT_TO_LEFT_MARK: char = '\u{200F}';
+
+/// The self alignment property of the children of a mirrored container that runs along
+/// the inline axis.
+#[derive(Clone, Copy)]
+pub(crate) enum InlineSelfAlignment {
+    AlignSelf,
+    /// Along with the column placement of grid items.
+    JustifySelf,
+}
+
+/// Sets the base direction of the text, if one is given.
+pub(crate) fn mark_text_direction(text: String, direction: Option<Direction>) -> String {
+    let mark = match direction {
+        Some(Direction::Ltr) => LEFT_TO_RIGHT_MARK,
+        Some(Direction::Rtl) => RIGHT_TO_LEFT_MARK,
+        None => return text,
+    };
+
+    let mut marked = String::with_capacity(text.len() + mark.len_utf8());
+    marked.push(mark);
+    marked.push_str(&text);
+    marked
+}
+
+/// Mirrors the layout of a right-to-left container.
+///
+/// Returns the self alignment property its children need mirrored as well, if any.
+pub(crate) fn mirror_container(node: &mut NodeKind, viewport: Viewport) -> Option<InlineSelfAlignment> {
+    let declared = Declarations::of(node, viewport);
+
+    let display = declared.resolve(|style| &style.display, |property| match property {
+        TailwindProperty::Display(display) => Some(*display),
+        _ => None,
+    });
+
+    match display {
+        Display::Flex => {
+            let flex_direction = declared.resolve(|style| &style.flex_direction, |property| match property {
+                TailwindProperty::FlexDirection(flex_direction) => Some(*flex_direction),
+                _ => None,
+            });
+            let justify_content = justify_content(&declared);
+            let align_items = align_items(&declared);
+            let align_content = align_content(&declared);
+
+            let style = style_mut(node);
+
+            match flex_direction {
+                FlexDirection::Row | FlexDirection::RowReverse => {
+                    let mirrored = match flex_direction {
+                        FlexDirection::Row => FlexDirection::RowReverse,
+                        _ => FlexDirection::Row,
+                    };
+
+                    // Reversing the row already moves flex-start and flex-end.
+                    style.flex_direction = CssValue::Value(mirrored);
+                    style.justify_content = CssValue::Value(swap_justify_content(justify_content, false));
+
+                    None
+                }
+                FlexDirection::Column | FlexDirection::ColumnReverse => {
+                    style.align_items = CssValue::Value(swap_align_items(align_items));
+                    style.align_content = CssValue::Value(swap_justify_content(align_content, true));
+
+                    Some(InlineSelfAlignment::AlignSelf)
+                }
+            }
+        }
+        Display::Grid => {
+            let justify_content = justify_content(&declared);
+            let justify_items = declared.resolve(|style| &style.justify_items, |property| match property {
+                TailwindProperty::JustifyItems(justify_items) => Some(*justify_items),
+                _ => None,
+            });
+
+            let template_columns = declared.resolve(|style| &style.grid_template_columns, |property| match property {
+                TailwindProperty::GridTemplateColumns(template) => Some(Some(template.0.clone())),
+                _ => None,
+            });
+            let template_areas = declared.resolve(|style| &style.grid_template_areas, |_| None);
+
+            let style = style_mut(node);
+            style.justify_content = CssValue::Value(swap_justify_content(justify_content, true));
+            style.justify_items = CssValue::Value(swap_align_items(justify_items));
+
+            if let Some(template_columns) = template_columns {
+                style.grid_template_columns = CssValue::Value(Some(mirror_template(template_columns)));
+            }
+            if let Some(GridTemplateAreas(rows)) = template_areas {
+                let rows = rows.into_iter().map(|row| row.into_iter().rev().collect()).collect();
+                style.grid_template_areas = CssValue::Value(Some(GridTemplateAreas(rows)));
+            }
+
+            Some(InlineSelfAlignment::JustifySelf)
+        }
+        Display::None | Display::Inline | Display::Block => None,
+    }
+}
+
+/// Mirrors the self alignment of a child of a right-to-left container.
+pub(crate) fn mirror_self_alignment(node: &mut NodeKind, property: InlineSelfAlignment, viewport: Viewport) {
+    let declared = Declarations::of(node, viewport);
+
+    match property {
+        InlineSelfAlignment::AlignSelf => {
+            let align_self = declared.resolve(|style| &style.align_self, |property| match property {
+                TailwindProperty::AlignSelf(align_self) => Some(*align_self),
+                _ => None,
+            });
+            style_mut(node).align_self = CssValue::Value(swap_align_items(align_self));
+        }
+        InlineSelfAlignment::JustifySelf => {
+            let justify_self = declared.resolve(|style| &style.justify_self, |property| match property {
+                TailwindProperty::JustifySelf(justify_self) => Some(*justify_self),
+                _ => None,
+            });
+            let grid_column = declared.resolve_merged(|style| &style.grid_column, |grid_column, property| {
+                match property {
+                    TailwindProperty::GridColumn(line) => *grid_column = Some(line.clone()),
+                    TailwindProperty::GridColumnSpan(span) => *grid_column = Some(GridLine::span(*span)),
+                    TailwindProperty::GridColumnStart(start) => match grid_column {
+                        Some(line) => line.start = start.clone(),
+                        None => *grid_column = Some(GridLine::start(start.clone())),
+                    },
+                    TailwindProperty::GridColumnEnd(end) => match grid_column {
+                        Some(line) => line.end = end.clone(),
+                        None => *grid_column = Some(GridLine::end(end.clone())),
+                    },
+                    _ => {}
+                }
+            });
+
+            let style = style_mut(node);
+            style.justify_self = CssValue::Value(swap_align_items(justify_self));
+            if let Some(grid_column) = grid_column {
+                style.grid_column = CssValue::Value(Some(mirror_grid_line(grid_column)));
+            }
+        }
+    }
+}
+
+/// Reverses the column tracks of a grid template, keeping every line name on its line.
+fn mirror_template(template: Vec<GridTemplateComponent>) -> Vec<GridTemplateComponent> {
+    template
+        .into_iter()
+        .rev()
+        .map(|component| match component {
+            GridTemplateComponent::Repeat(count, tracks) => {
+                GridTemplateComponent::Repeat(count, mirror_tracks(&tracks))
+            }
+            component => component,
+        })
+        .collect()
+}
+
+/// Reverses the tracks of a `repeat()`, whose line names are stored on the track after
+/// each line, or on the last track for the line after it.
+fn mirror_tracks(tracks: &[GridRepeatTrack]) -> Vec<GridRepeatTrack> {
+    (0..tracks.len())
+        .rev()
+        .map(|index| GridRepeatTrack {
+            size: tracks[index].size,
+            names: match tracks.get(index + 1) {
+                Some(next) => next.names.clone(),
+                None => tracks[index].end_names.clone().unwrap_or_default(),
+            },
+            end_names: (index == 0).then(|| tracks[0].names.clone()),
+        })
+        .collect()
+}
+
+/// Mirrors the column placement of a grid item for reversed columns.
+fn mirror_grid_line(line: GridLine) -> GridLine {
+    let mirror = |placement| match placement {
+        GridPlacement::Line(line) => GridPlacement::Line(line.saturating_neg()),
+        placement => placement,
+    };
+
+    GridLine {
+        start: mirror(line.end),
+        end: mirror(line.start),
+    }
+}
+
+/// Swaps start and end, and flex-start and flex-end if `flex` is set.
+fn swap_justify_content(value: JustifyContent, flex: bool) -> JustifyContent {
+    match value {
+        JustifyContent::Start => JustifyContent::End,
+        JustifyContent::End => JustifyContent::Start,
+        JustifyContent::FlexStart if flex => JustifyContent::FlexEnd,
+        JustifyContent::FlexEnd if flex => JustifyContent::FlexStart,
+        value => value,
+    }
+}
+
+fn swap_align_items(value: AlignItems) -> AlignItems {
+    match value {
+        AlignItems::Start => AlignItems::End,
+        AlignItems::End => AlignItems::Start,
+        AlignItems::FlexStart => AlignItems::FlexEnd,
+        AlignItems::FlexEnd => AlignItems::FlexStart,
+        value => value,
+    }
+}
+
+fn style_mut(node: &mut NodeKind) -> &mut Style {
+    let style = match node {
+        NodeKind::Container(container) => &mut container.style,
+        NodeKind::Image(image) => &mut image.style,
+        NodeKind::Text(text) => &mut text.style,
+    };
+
+    style.get_or_insert_with(Style::default)
+}
+
+fn justify_content(declared: &Declarations) -> JustifyContent {
+    declared.resolve(|style| &style.justify_content, |property| match property {
+        TailwindProperty::Justify(justify_content) => Some(*justify_content),
+        _ => None,
+    })
+}
+
+fn align_items(declared: &Declarations) -> AlignItems {
+    declared.resolve(|style| &style.align_items, |property| match property {
+        TailwindProperty::Items(align_items) => Some(*align_items),
+        _ => None,
+    })
+}
+
+fn align_content(declared: &Declarations) -> JustifyContent {
+    declared.resolve(|style| &style.align_content, |property| match property {
+        TailwindProperty::Content(align_content) => Some(*align_content),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use takumi::{
+        layout::Viewport,
+        rendering::{measure_layout, RenderOptionsBuilder},
+        GlobalContext,
+    };
+
+    use crate::node::NodeKind;
+
+    /// Lays out `json` and returns the left edge and width of each child of the root.
+    fn child_columns(json: &str) -> Vec<(f32, f32)> {
+        let global = GlobalContext::default();
+        let viewport = Viewport::new(Some(300), Some(120));
+        let (tree, _) = NodeKind::from_json(json).unwrap().prepare(viewport, &global).unwrap();
+
+        let options = RenderOptionsBuilder::default()
+            .viewport(viewport)
+            .node(tree)
+            .global(&global)
+            .build()
+            .unwrap();
+
+        measure_layout(options)
+            .unwrap()
+            .children
+            .iter()
+            .map(|child| (child.transform[4], child.width))
+            .collect()
+    }
+
+    fn grid(direction: &str) -> String {
+        format!(
+            r#"{{
+                "type": "container",
+                "style": {{
+                    "direction": "{direction}",
+                    "display": "grid",
+                    "gridTemplateColumns": "50px 100px 150px",
+                    "gridTemplateRows": "40px 40px",
+                    "width": 300,
+                    "height": 80
+                }},
+                "children": [
+                    {{ "type": "container", "style": {{ "gridColumn": "1 / 2", "gridRow": "1 / 2" }} }},
+                    {{ "type": "container", "style": {{ "gridColumn": "2 / 4", "gridRow": "1 / 2" }} }},
+                    {{ "type": "container", "style": {{ "gridRow": "2 / 3" }}, "tw": "col-start-3" }}
+                ]
+            }}"#
+        )
+    }
+
+    #[test]
+    fn grid_columns_are_mirrored() {
+        assert_eq!(child_columns(&grid("ltr")), [(0.0, 50.0), (50.0, 250.0), (150.0, 150.0)]);
+        assert_eq!(child_columns(&grid("rtl")), [(250.0, 50.0), (0.0, 250.0), (0.0, 150.0)]);
+    }
+}
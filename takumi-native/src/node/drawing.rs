@@ -0,0 +1,363 @@
+//! Vector graphics drawn natively once their node is laid out.
+//!
+//! SVG markup is rasterized natively rather than by takumi, which keeps the pixels of SVG
+//! images premultiplied by their alpha and so darkens translucent paint and antialiased
+//! edges. Shapes and paint nodes are drawn in pixels of their node's content box, which
+//! is only known after layout. So SVG nodes are lowered into images of their markup, sized
+//! by it like any image, and shape and paint nodes into empty containers; once the tree is
+//! laid out, each one is drawn at the device size of its content box and passed to takumi
+//! as a fetched resource.
+//!
+//! takumi only measures border boxes, so the content boxes are measured with probes: a
+//! container with the node's style, fixed to its laid-out size, whose content box is
+//! filled by an empty block.
+
+use std::sync::Arc;
+
+use takumi::{
+    image::RgbaImage,
+    layout::{
+        node::{ContainerNode, ImageNode, NodeKind},
+        style::{BoxSizing, Color, CssValue, Length, Style},
+        Viewport,
+    },
+    rendering::{measure_layout, MeasuredNode, RenderOptionsBuilder},
+    resources::image::ImageSource,
+    GlobalContext,
+};
+
+use super::{
+    paint::{self, PaintArea},
+    probe, InheritedStyle, Resources,
+};
+
+/// A node drawn natively, found by its path from the root.
+pub(crate) struct Drawing {
+    path: Vec<usize>,
+    content: Content,
+}
+
+/// The content box of a drawing in device pixels, with its size rounded.
+struct ContentBox {
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+}
+
+/// What a drawing is drawn from.
+enum Content {
+    /// The markup the lowered image already has.
+    Markup,
+    /// The markup inside the root `<svg>` tag of a shape node, and the color `currentColor`
+    /// takes.
+    Shapes(String, Color),
+    /// The name the paint callback draws a paint node by.
+    Paint(String),
+}
+
+/// Finds every SVG, shape, code and paint node in the tree, writing the markup of the
+/// shapes.
+pub(crate) fn collect_drawings(root: &super::NodeKind, viewport: Viewport) -> Vec<Drawing> {
+    fn collect(
+        node: &super::NodeKind,
+        parent: &InheritedStyle,
+        viewport: Viewport,
+        path: &mut Vec<usize>,
+        drawings: &mut Vec<Drawing>,
+    ) {
+        match node {
+            super::NodeKind::Container(container) => {
+                let inherited = container.inherited_style(parent, viewport);
+
+                for (index, child) in container.children.iter().flatten().enumerate() {
+                    path.push(index);
+                    collect(child, &inherited, viewport, path, drawings);
+                    path.pop();
+                }
+            }
+            super::NodeKind::Svg(_) | super::NodeKind::QrCode(_) | super::NodeKind::Barcode(_) => drawings.push(Drawing {
+                path: path.clone(),
+                content: Content::Markup,
+            }),
+            super::NodeKind::Shape(shape) => drawings.push(Drawing {
+                path: path.clone(),
+                content: Content::Shapes(
+                    shape.write_content(),
+                    shape.inherited_style(parent, viewport).color.unwrap_or(Color::black()),
+                ),
+            }),
+            super::NodeKind::Paint(paint) => drawings.push(Drawing {
+                path: path.clone(),
+                content: Content::Paint(paint.name.clone()),
+            }),
+            super::NodeKind::Image(_)
+            | super::NodeKind::Text(_)
+            | super::NodeKind::CodeBlock(_)
+            | super::NodeKind::Markdown(_)
+            | super::NodeKind::Html(_)
+            | super::NodeKind::Fragment(_) => {}
+        }
+    }
+
+    let mut drawings = Vec::new();
+    collect(root, &InheritedStyle::default(), viewport, &mut Vec::new(), &mut drawings);
+    drawings
+}
+
+/// Draws every drawing at the laid-out size of its content box into a bitmap, which the
+/// node is pointed at and which is returned keyed by its source.
+pub(crate) fn draw(
+    tree: &mut NodeKind,
+    drawings: &[Drawing],
+    viewport: Viewport,
+    global: &GlobalContext,
+) -> Result<Resources, String> {
+    let mut resources = Resources::new();
+
+    if drawings.is_empty() {
+        return Ok(resources);
+    }
+
+    let content_boxes = measure_content_boxes(tree, drawings, viewport, global)?;
+
+    for (index, (drawing, content_box)) in drawings.iter().zip(content_boxes).enumerate() {
+        let Some(ContentBox {
+            x,
+            y,
+            width: pixel_width,
+            height: pixel_height,
+        }) = content_box
+        else {
+            continue;
+        };
+        if pixel_width < 1.0 || pixel_height < 1.0 {
+            continue;
+        }
+
+        let width = pixel_width / viewport.device_pixel_ratio;
+        let height = pixel_height / viewport.device_pixel_ratio;
+        let node = probe::node_at(tree, &drawing.path);
+
+        let bitmap = match (&drawing.content, &*node) {
+            (Content::Markup, NodeKind::Image(image)) => rasterize(&image.src, pixel_width as u32, pixel_height as u32)?,
+            (Content::Markup, _) => continue,
+            (Content::Shapes(content, color), _) => {
+                let [red, green, blue, alpha] = color.0;
+                let markup = format!(
+                    r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" color="rgba({red}, {green}, {blue}, {})">{content}</svg>"#,
+                    f32::from(alpha) / 255.0,
+                );
+                rasterize(&markup, pixel_width as u32, pixel_height as u32)?
+            }
+            (Content::Paint(name), _) => {
+                let area = PaintArea {
+                    x,
+                    y,
+                    width: pixel_width as u32,
+                    height: pixel_height as u32,
+                    device_pixel_ratio: viewport.device_pixel_ratio,
+                };
+                Some(paint::paint(name, area)?)
+            }
+        };
+
+        // Markup that does not parse is left to takumi, like any other broken image source.
+        let Some(bitmap) = bitmap else {
+            continue;
+        };
+
+        let src: Arc<str> = format!("takumi-native://drawing/{index}").into();
+        resources.insert(src.clone(), Arc::new(ImageSource::from(bitmap)));
+
+        match node {
+            NodeKind::Image(image) => {
+                image.src = src;
+                image.width = Some(width);
+                image.height = Some(height);
+            }
+            NodeKind::Container(container) => {
+                let image = ImageNode {
+                    preset: None,
+                    style: Some(Style {
+                        width: CssValue::Value(Length::Px(width)),
+                        height: CssValue::Value(Length::Px(height)),
+                        ..Default::default()
+                    }),
+                    src,
+                    width: Some(width),
+                    height: Some(height),
+                    tw: None,
+                };
+                container.children = Some(Box::new([image.into()]));
+            }
+            NodeKind::Text(_) => {}
+        }
+    }
+
+    Ok(resources)
+}
+
+/// Measures the position and size of the content box of every drawing in device pixels,
+/// or `None` where the node cannot be found in the measured tree.
+fn measure_content_boxes(
+    tree: &NodeKind,
+    drawings: &[Drawing],
+    viewport: Viewport,
+    global: &GlobalContext,
+) -> Result<Vec<Option<ContentBox>>, String> {
+    let measured = measure(tree.clone(), viewport, global)?;
+
+    let mut probed = tree.clone();
+    for drawing in drawings {
+        let Some(border_box) = measured_node_at(&measured, &drawing.path) else {
+            continue;
+        };
+
+        let node = probe::node_at(&mut probed, &drawing.path);
+        *node = create_probe(node, border_box, viewport.device_pixel_ratio);
+    }
+
+    let measured = measure(probed, viewport, global)?;
+
+    Ok(drawings
+        .iter()
+        .map(|drawing| {
+            measured_node_at(&measured, &drawing.path)
+                .and_then(|probe| probe.children.first())
+                .map(|content| {
+                    let [.., x, y] = content.transform;
+                    ContentBox {
+                        x,
+                        y,
+                        width: content.width.round(),
+                        height: content.height.round(),
+                    }
+                })
+        })
+        .collect())
+}
+
+fn measure(tree: NodeKind, viewport: Viewport, global: &GlobalContext) -> Result<MeasuredNode, String> {
+    let options = RenderOptionsBuilder::default()
+        .viewport(viewport)
+        .node(tree)
+        .global(global)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    measure_layout(options).map_err(|e| e.to_string())
+}
+
+fn measured_node_at<'m>(root: &'m MeasuredNode, path: &[usize]) -> Option<&'m MeasuredNode> {
+    path.iter().try_fold(root, |node, index| node.children.get(*index))
+}
+
+/// Replaces a node with a container of the same style and laid-out size, whose content
+/// box is filled by an empty block.
+fn create_probe(node: &NodeKind, border_box: &MeasuredNode, device_pixel_ratio: f32) -> NodeKind {
+    let (preset, style, tw) = match node {
+        NodeKind::Container(container) => (&container.preset, &container.style, &container.tw),
+        NodeKind::Image(image) => (&image.preset, &image.style, &image.tw),
+        NodeKind::Text(text) => (&text.preset, &text.style, &text.tw),
+    };
+
+    let mut style = style.clone().unwrap_or_default();
+    style.box_sizing = CssValue::Value(BoxSizing::BorderBox);
+    style.width = CssValue::Value(Length::Px(border_box.width / device_pixel_ratio));
+    style.height = CssValue::Value(Length::Px(border_box.height / device_pixel_ratio));
+
+    let content = ContainerNode {
+        preset: None,
+        style: Some(Style {
+            width: CssValue::Value(Length::Percentage(100.0)),
+            height: CssValue::Value(Length::Percentage(100.0)),
+            ..Default::default()
+        }),
+        children: None,
+        tw: None,
+    };
+
+    ContainerNode {
+        preset: preset.clone(),
+        style: Some(style),
+        children: Some(Box::new([content.into()])),
+        tw: tw.clone(),
+    }
+    .into()
+}
+
+/// Rasterizes SVG markup, stretched to `width` by `height` device pixels, into a bitmap
+/// with straight alpha, or `None` if the markup does not parse.
+fn rasterize(markup: &str, width: u32, height: u32) -> Result<Option<RgbaImage>, String> {
+    let Ok(svg) = usvg::Tree::from_str(markup, &usvg::Options::default()) else {
+        return Ok(None);
+    };
+
+    let mut pixmap = resvg::tiny_skia::Pixmap::new(width, height).ok_or("drawing is too large")?;
+    let size = svg.size();
+    let transform = resvg::tiny_skia::Transform::from_scale(width as f32 / size.width(), height as f32 / size.height());
+    resvg::render(&svg, transform, &mut pixmap.as_mut());
+
+    let pixels = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let pixel = pixel.demultiply();
+            [pixel.red(), pixel.green(), pixel.blue(), pixel.alpha()]
+        })
+        .collect();
+
+    RgbaImage::from_raw(width, height, pixels)
+        .map(Some)
+        .ok_or_else(|| "drawing bitmap has the wrong size".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    const SQUARE: &str = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 10 10">
+        <rect width="10" height="10" fill="rgba(255, 0, 0, 0.5)"/>
+    </svg>"#;
+
+    #[test]
+    fn svg_is_drawn_at_the_size_of_its_content_box() {
+        let tree = json!({
+            "type": "container",
+            "children": [{
+                "type": "svg",
+                "svg": SQUARE,
+                "style": { "width": 40, "height": 30, "padding": 5, "borderWidth": 2 },
+            }],
+        });
+
+        let viewport = Viewport {
+            device_pixel_ratio: 2.0,
+            ..Viewport::new(Some(100), Some(100))
+        };
+        let (_, resources) = super::super::NodeKind::from_value(&tree)
+            .unwrap()
+            .prepare(viewport, &GlobalContext::default())
+            .unwrap();
+
+        let bitmaps: Vec<_> = resources.values().collect();
+        let [bitmap] = bitmaps.as_slice() else {
+            panic!("the SVG node is not drawn once");
+        };
+        let ImageSource::Bitmap(bitmap) = &***bitmap else {
+            panic!("drawings are bitmaps");
+        };
+
+        assert_eq!(bitmap.dimensions(), (52, 32));
+    }
+
+    #[test]
+    fn drawings_keep_straight_alpha() {
+        let bitmap = rasterize(SQUARE, 4, 4).unwrap().unwrap();
+
+        assert_eq!(bitmap.get_pixel(2, 2).0, [255, 0, 0, 128]);
+    }
+}
@@ -0,0 +1,277 @@
+//! Fitting text nodes into their laid-out box.
+//!
+//! Every text node with a [`TextFit`] is fitted in document order, binary searching
+//! the largest font size at which its text, measured with a probe, neither overflows
+//! the node's content box nor wraps to more lines than allowed.
+//!
+//! The tree is lowered once per fitted node; each probe only lowers the fitted node
+//! again at the probed font size and puts it in place of the lowered one.
+
+use takumi::{layout::Viewport, GlobalContext};
+
+use super::{probe, InheritedStyle, NodeKind, TextFit, TextNode};
+use crate::{handle::NodeHandle, set_last_error};
+
+/// The font size precision the search stops at, in pixels.
+const FONT_SIZE_PRECISION: f32 = 0.5;
+
+/// Tolerance for rounding differences between the box and the text measurement.
+const EPSILON: f32 = 0.01;
+
+/// Picks the font size of every fitted text node in the tree, returning the sizes in
+/// document order of the fitted text nodes.
+pub(crate) fn fit_text(root: &mut NodeKind, viewport: Viewport, global: &GlobalContext) -> Result<Vec<f32>, String> {
+    let mut fitted = Vec::new();
+    collect_fitted(root, &InheritedStyle::default(), viewport, &mut Vec::new(), &mut fitted);
+
+    // Nodes that are not fitted yet take up as much space as they could.
+    for fitted in &fitted {
+        let text = text_at(root, &fitted.path);
+        if let Some(fit) = text.fit {
+            text.set_font_size(fit.max_font_size);
+        }
+    }
+
+    let mut font_sizes = Vec::with_capacity(fitted.len());
+
+    for fitted in &fitted {
+        let Some(fit) = text_at(root, &fitted.path).fit else {
+            continue;
+        };
+
+        let font_size = search_font_size(root, fitted, fit, viewport, global)?;
+        text_at(root, &fitted.path).set_font_size(font_size);
+        font_sizes.push(font_size);
+    }
+
+    Ok(font_sizes)
+}
+
+/// A text node with `fit` set.
+struct FittedText {
+    /// The child indices leading to the node from the root.
+    path: Vec<usize>,
+    /// The style the node inherits, to lower it on its own.
+    parent: InheritedStyle,
+}
+
+fn collect_fitted(
+    node: &NodeKind,
+    parent: &InheritedStyle,
+    viewport: Viewport,
+    path: &mut Vec<usize>,
+    fitted: &mut Vec<FittedText>,
+) {
+    match node {
+        NodeKind::Container(container) => {
+            let inherited = container.inherited_style(parent, viewport);
+
+            for (index, child) in container.children.iter().flatten().enumerate() {
+                path.push(index);
+                collect_fitted(child, &inherited, viewport, path, fitted);
+                path.pop();
+            }
+        }
+        NodeKind::Text(TextNode { fit: Some(_), .. }) => fitted.push(FittedText {
+            path: path.clone(),
+            parent: parent.clone(),
+        }),
+        _ => {}
+    }
+}
+
+fn text_at<'n>(root: &'n mut NodeKind, path: &[usize]) -> &'n mut TextNode {
+    let mut node = root;
+    for index in path {
+        node = &mut node.children_mut().expect("fit path points into a container")[*index];
+    }
+
+    match node {
+        NodeKind::Text(text) => text,
+        _ => unreachable!("fit path points to a text node"),
+    }
+}
+
+/// Binary searches the largest font size between the fit bounds that still fits.
+fn search_font_size(
+    root: &mut NodeKind,
+    fitted: &FittedText,
+    fit: TextFit,
+    viewport: Viewport,
+    global: &GlobalContext,
+) -> Result<f32, String> {
+    let text = text_at(root, &fitted.path).clone();
+    if text.inherited_style(&fitted.parent, viewport).writing_mode.is_vertical() {
+        return Err("fit is not supported on text in a vertical writing mode".to_string());
+    }
+
+    let mut tree = root.clone().into_takumi(&InheritedStyle::default(), viewport);
+    let mut fits = |font_size| fits(&mut tree, &text, fitted, fit, font_size, viewport, global);
+
+    let (mut low, mut high) = (fit.min_font_size, fit.max_font_size.max(fit.min_font_size));
+
+    if fits(high)? {
+        return Ok(high);
+    }
+
+    while high - low > FONT_SIZE_PRECISION {
+        let middle = (low + high) / 2.0;
+
+        if fits(middle)? {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+
+    Ok(low)
+}
+
+fn fits(
+    tree: &mut takumi::layout::node::NodeKind,
+    text: &TextNode,
+    fitted: &FittedText,
+    fit: TextFit,
+    font_size: f32,
+    viewport: Viewport,
+    global: &GlobalContext,
+) -> Result<bool, String> {
+    let mut text = text.clone();
+    text.set_font_size(font_size);
+    *probe::node_at(tree, &fitted.path) = text.into_takumi(&fitted.parent, viewport);
+
+    let Some(measured) = probe::measure_text(tree, &fitted.path, viewport, global)? else {
+        return Err("fit is not supported on text laid out inline".to_string());
+    };
+    let (content, paragraph) = (&measured.content, &measured.paragraph);
+
+    if !measured.is_complete() || paragraph.height > content.height + EPSILON {
+        return Ok(false);
+    }
+
+    if paragraph.runs.iter().any(|run| run.x + run.width > paragraph.width + EPSILON) {
+        return Ok(false);
+    }
+
+    Ok(fit.max_lines.is_none_or(|max_lines| measured.lines().len() as u32 <= max_lines))
+}
+
+/// Picks the font size of every text node with `fit` set in a parsed node tree, as
+/// rendering it with the same viewport does, without rendering it.
+///
+/// Sizes are in document order of the fitted text nodes. Writes up to `len` sizes to
+/// `out_sizes` and the total number of fitted text nodes to `out_count`, so a call with
+/// a buffer that is too small can be repeated with one of the right size.
+///
+/// # Safety
+///
+/// - `handle` must be a handle returned by [`crate::handle::takumi_node_parse`] that was not released yet.
+/// - `out_sizes` must be null or a valid pointer to at least `len` floats.
+/// - `out_count` must be a valid pointer to a `u64`.
+#[no_mangle]
+pub unsafe extern "C" fn takumi_node_fit_font_sizes(
+    handle: *const NodeHandle,
+    viewport: crate::Viewport,
+    out_sizes: *mut f32,
+    len: u64,
+    out_count: *mut u64,
+) -> bool {
+    let Some(handle) = (unsafe { handle.as_ref() }) else {
+        set_last_error("handle is null");
+        return false;
+    };
+
+    if out_count.is_null() {
+        set_last_error("out_count is null");
+        return false;
+    }
+
+    // The sizes come from lowering the tree, which rendering it with the same viewport reuses.
+    let lowered = match unsafe { handle.lower(viewport) } {
+        Ok(lowered) => lowered,
+        Err(e) => {
+            set_last_error(e);
+            return false;
+        }
+    };
+    let font_sizes = &lowered.font_sizes;
+
+    if !out_sizes.is_null() {
+        let count = font_sizes.len().min(len as usize);
+        std::ptr::copy_nonoverlapping(font_sizes.as_ptr(), out_sizes, count);
+    }
+
+    unsafe { *out_count = font_sizes.len() as u64 };
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::json;
+    use takumi::parley::fontique::Blob;
+
+    use super::*;
+
+    const FONT: &[u8] = include_bytes!("../../../font.ttf");
+
+    const TEXT: &str = "The quick brown fox jumps over the lazy dog";
+
+    /// Fits a text node in a box of `width` by 60 pixels, with the test font loaded.
+    fn fit_in_box(width: u32, fit: serde_json::Value, mut style: serde_json::Value) -> Result<Vec<f32>, String> {
+        let mut global = GlobalContext::default();
+        let collection = &mut global.font_context.collection;
+        let (family, _) = collection.register_fonts(Blob::new(Arc::new(FONT)), None).remove(0);
+        let family = collection.family_name(family).unwrap().to_owned();
+
+        style["fontFamily"] = json!(family);
+        style["width"] = json!(width);
+        style["height"] = json!(60);
+
+        let tree = json!({
+            "type": "container",
+            "children": [{ "type": "text", "text": TEXT, "style": style, "fit": fit }],
+        });
+
+        let node = NodeKind::from_value(&tree)?;
+        Ok(node.lower(Viewport::new(Some(400), Some(200)), &global)?.font_sizes)
+    }
+
+    #[test]
+    fn text_shrinks_to_fit_its_box() {
+        let fit = json!({ "minFontSize": 4, "maxFontSize": 200 });
+        let font_size = fit_in_box(300, fit, json!({})).unwrap()[0];
+
+        assert!(font_size > 4.0 && font_size < 200.0, "{font_size}");
+
+        let fit = json!({ "minFontSize": 4, "maxFontSize": 200 });
+        let wider = fit_in_box(400, fit, json!({})).unwrap()[0];
+        assert!(wider >= font_size, "{wider} < {font_size}");
+    }
+
+    #[test]
+    fn fewer_lines_fit_a_smaller_size() {
+        let wrapped = fit_in_box(300, json!({ "minFontSize": 4, "maxFontSize": 200 }), json!({})).unwrap()[0];
+
+        let fit = json!({ "minFontSize": 4, "maxFontSize": 200, "maxLines": 1 });
+        let one_line = fit_in_box(300, fit, json!({})).unwrap()[0];
+
+        assert!(one_line < wrapped, "{one_line} >= {wrapped}");
+    }
+
+    #[test]
+    fn text_that_fits_keeps_the_largest_size() {
+        let font_sizes = fit_in_box(400, json!({ "minFontSize": 4, "maxFontSize": 6 }), json!({})).unwrap();
+
+        assert_eq!(font_sizes, [6.0]);
+    }
+
+    #[test]
+    fn vertical_text_cannot_be_fitted() {
+        let fit = json!({ "minFontSize": 4, "maxFontSize": 200 });
+        let error = fit_in_box(300, fit, json!({ "writingMode": "vertical-rl" })).unwrap_err();
+
+        assert_eq!(error, "fit is not supported on text in a vertical writing mode");
+    }
+}
@@ -0,0 +1,211 @@
+//! Fragments: subtrees defined once by ID and instantiated by fragment nodes.
+//!
+//! A container defines fragments for the nodes inside it, and a template registered on
+//! the context can be instantiated as a fragment anywhere. Fragments are kept as JSON
+//! until they are instantiated, so that each instance fills the `{{name}}` placeholders
+//! of the fragment with its own variables, like a template.
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use super::{ElementStyle, NodeKind};
+use crate::template;
+
+/// The most nodes the instances of fragments in a tree may add up to, so that fragments
+/// instantiating each other many times cannot expand the tree exponentially.
+const MAX_INSTANCE_NODES: usize = 100_000;
+
+/// A subtree defined once, instantiated by fragment nodes.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(transparent)]
+pub struct Fragment(Value);
+
+/// A node replaced by an instance of a fragment before layout.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FragmentNode {
+    /// The ID of the fragment, defined by a container around this node or registered as a template
+    pub id: String,
+    /// The values of the placeholders in the fragment
+    pub variables: Option<Map<String, Value>>,
+    /// The style properties set on the root of the instance, over the fragment's own
+    pub style: Option<ElementStyle>,
+    /// The tailwind classes added to the root of the instance, after the fragment's own
+    pub tw: Option<String>,
+}
+
+/// The fragments defined around the node being expanded.
+#[derive(Default)]
+pub(crate) struct FragmentScope {
+    /// The fragments defined by each container around the node, innermost last.
+    definitions: Vec<HashMap<String, Fragment>>,
+    /// The IDs of the fragments being instantiated, to stop a fragment from instantiating itself.
+    instantiating: Vec<String>,
+    /// The nodes of every instance built so far.
+    instance_nodes: usize,
+}
+
+impl FragmentScope {
+    /// Brings the fragments a container defines into scope, until [`FragmentScope::leave`].
+    pub(crate) fn enter(&mut self, fragments: Option<HashMap<String, Fragment>>) {
+        self.definitions.push(fragments.unwrap_or_default());
+    }
+
+    pub(crate) fn leave(&mut self) {
+        self.definitions.pop();
+    }
+
+    /// Builds the tree of a fragment node's instance, which may hold fragment nodes itself.
+    pub(crate) fn instantiate(&mut self, node: &FragmentNode) -> Result<NodeKind, String> {
+        let id = &node.id;
+        if self.instantiating.contains(id) {
+            return Err(format!("fragment \"{id}\" instantiates itself"));
+        }
+
+        let defined = self.definitions.iter().rev().find_map(|fragments| fragments.get(id));
+        let Some(fragment) = defined
+            .map(|fragment| &fragment.0)
+            .or_else(|| unsafe { template::registered(id) })
+        else {
+            return Err(format!("no fragment is defined as \"{id}\""));
+        };
+
+        // Fragments defined inside the fragment keep their placeholders for their own instances.
+        let variables = node.variables.clone().unwrap_or_default();
+        let mut tree = template::substitute(fragment, &variables).map_err(|e| format!("fragment \"{id}\": {e}"))?;
+
+        if let Value::Object(root) = &mut tree {
+            if let Some(style) = &node.style {
+                match root.entry("style").or_insert_with(|| Value::Object(Map::new())) {
+                    Value::Object(declarations) => declarations.extend(style.0.clone()),
+                    _ => return Err(format!("fragment \"{id}\": the style of its root is not an object")),
+                }
+            }
+
+            if let Some(tw) = &node.tw {
+                let classes = match root.get("tw").and_then(Value::as_str) {
+                    Some(own) => format!("{own} {tw}"),
+                    None => tw.clone(),
+                };
+                root.insert("tw".to_owned(), Value::String(classes));
+            }
+        }
+
+        let instance = NodeKind::deserialize(&tree).map_err(|e| format!("fragment \"{id}\": {e}"))?;
+
+        self.instance_nodes += count_nodes(&instance);
+        if self.instance_nodes > MAX_INSTANCE_NODES {
+            return Err(format!(
+                "fragment \"{id}\": instances of fragments add up to more than {MAX_INSTANCE_NODES} nodes"
+            ));
+        }
+
+        Ok(instance)
+    }
+
+    /// Expands a subtree instantiated from the fragment `id`.
+    pub(crate) fn expand_instance(&mut self, id: String, node: &mut NodeKind) -> Result<(), String> {
+        self.instantiating.push(id);
+        let result = node.expand(self);
+        self.instantiating.pop();
+        result
+    }
+}
+
+/// Counts a node and the nodes inside it.
+fn count_nodes(node: &NodeKind) -> usize {
+    match node {
+        NodeKind::Container(container) => 1 + container.children.iter().flatten().map(count_nodes).sum::<usize>(),
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn expand(tree: Value) -> Result<NodeKind, String> {
+        let mut node = NodeKind::from_value(&tree)?;
+        node.resolve()?;
+        Ok(node)
+    }
+
+    fn texts(node: &NodeKind) -> Vec<String> {
+        match node {
+            NodeKind::Container(container) => container.children.iter().flatten().flat_map(texts).collect(),
+            NodeKind::Text(text) => vec![text.text.clone()],
+            _ => Vec::new(),
+        }
+    }
+
+    #[test]
+    fn instances_fill_their_own_placeholders() {
+        let tree = json!({
+            "type": "container",
+            "fragments": { "row": { "type": "text", "text": "{{name}}: {{score}}" } },
+            "children": [
+                { "type": "fragment", "id": "row", "variables": { "name": "Ada", "score": 1200 } },
+                { "type": "fragment", "id": "row", "variables": { "name": "Grace", "score": 1100 } },
+            ],
+        });
+
+        assert_eq!(texts(&expand(tree).unwrap()), ["Ada: 1200", "Grace: 1100"]);
+    }
+
+    #[test]
+    fn nested_fragments_are_filled_by_their_own_instances() {
+        let tree = json!({
+            "type": "container",
+            "fragments": {
+                "card": {
+                    "type": "container",
+                    "fragments": { "line": { "type": "text", "text": "{{label}}" } },
+                    "children": [
+                        { "type": "text", "text": "{{title}}" },
+                        { "type": "fragment", "id": "line", "variables": { "label": "by {{author}}" } },
+                    ],
+                },
+            },
+            "children": [{ "type": "fragment", "id": "card", "variables": { "title": "Notes", "author": "Ada" } }],
+        });
+
+        assert_eq!(texts(&expand(tree).unwrap()), ["Notes", "by Ada"]);
+    }
+
+    #[test]
+    fn recursive_fragments_fail() {
+        let tree = json!({
+            "type": "container",
+            "fragments": { "loop": { "type": "container", "children": [{ "type": "fragment", "id": "loop" }] } },
+            "children": [{ "type": "fragment", "id": "loop" }],
+        });
+
+        assert_eq!(expand(tree).unwrap_err(), "fragment \"loop\" instantiates itself");
+    }
+
+    #[test]
+    fn expansion_stops_at_the_node_budget() {
+        // Each level instantiates the next ten times, adding up to more than a million nodes.
+        let mut fragments = Map::new();
+        let levels = ["a", "b", "c", "d", "e", "f"];
+        for (level, next) in levels.iter().zip(&levels[1..]) {
+            let children = vec![json!({ "type": "fragment", "id": next }); 10];
+            fragments.insert(level.to_string(), json!({ "type": "container", "children": children }));
+        }
+        fragments.insert("f".to_string(), json!({ "type": "container" }));
+
+        let tree = json!({
+            "type": "container",
+            "fragments": fragments,
+            "children": [{ "type": "fragment", "id": "a" }],
+        });
+
+        let error = expand(tree).unwrap_err();
+        assert!(error.ends_with(&format!(
+            "instances of fragments add up to more than {MAX_INSTANCE_NODES} nodes"
+        )));
+    }
+}
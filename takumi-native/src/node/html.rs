@@ -0,0 +1,545 @@
+//! A subset of HTML parsed into the node tree.
+//!
+//! An HTML node is expanded into containers, text nodes and images before anything else is
+//! resolved. `div` becomes a container, `p` and `h1`–`h6` text nodes with a span per run of
+//! inline text, and `img` an image, or an inline image inside text. Text and inline elements
+//! directly inside a `div` are set as a paragraph of their own, like an anonymous block box.
+//!
+//! The `style` attribute is parsed declaration by declaration, so that a property takumi
+//! does not know is reported rather than ignored, and the `class` attribute becomes the
+//! node's tailwind classes. Spans are not nested, so the declarations and classes of nested
+//! spans are merged from the outermost one in. Anything outside of the subset is an error.
+
+use scraper::{node::Element, ElementRef, Html, Node};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use takumi::layout::{
+    node::ImageNode,
+    style::{
+        tw::{TailwindValue, TailwindValues},
+        CssValue, Display, FlexDirection, FontWeight, Length, Sides,
+    },
+};
+
+use super::{ContainerNode, NodeKind, Style, TextNode, TextSpan};
+
+/// A node that parses a subset of HTML: `div`, `span`, `p`, `h1`–`h6`, `img` and `br`, with
+/// `style` and `class` attributes.
+#[derive(Debug, Clone, Deserialize)]
+pub struct HtmlNode {
+    /// Default style presets from HTML element type (lowest priority)
+    pub preset: Option<takumi::layout::style::Style>,
+    /// The styling properties for this html node
+    pub style: Option<Style>,
+    /// The HTML source
+    pub html: String,
+    /// The tailwind properties for this html node
+    pub tw: Option<TailwindValues>,
+}
+
+/// The declarations and classes of the inline elements around a run of text, merged from
+/// the outermost one in.
+#[derive(Clone, Default)]
+struct InlineStyle {
+    declarations: Map<String, Value>,
+    classes: String,
+}
+
+impl InlineStyle {
+    /// Layers the `style` and `class` attributes of an element on top of this style.
+    fn merge(&mut self, element: &Element) -> Result<(), String> {
+        if let Some(style) = element.attr("style") {
+            self.declarations.extend(parse_declarations(style, element.name())?);
+        }
+        if let Some(class) = element.attr("class") {
+            check_classes(class, element.name())?;
+            self.classes.push(' ');
+            self.classes.push_str(class);
+        }
+        Ok(())
+    }
+
+    fn style(&self) -> Result<Option<Style>, String> {
+        match self.declarations.is_empty() {
+            true => Ok(None),
+            false => parse_style(&self.declarations).map(Some),
+        }
+    }
+
+    fn tw(&self) -> Result<Option<TailwindValues>, String> {
+        match self.classes.trim().is_empty() {
+            true => Ok(None),
+            false => self.classes.parse().map(Some),
+        }
+    }
+}
+
+impl HtmlNode {
+    /// Parses the HTML into a container of its nodes.
+    pub(crate) fn expand(&self) -> Result<ContainerNode, String> {
+        let fragment = Html::parse_fragment(&self.html);
+        let children = blocks(fragment.root_element())?;
+
+        Ok(ContainerNode {
+            preset: self.preset.clone(),
+            style: self.style.clone(),
+            children: Some(children.into_boxed_slice()),
+            tw: self.tw.clone(),
+            lang: None,
+            fragments: None,
+        })
+    }
+}
+
+/// Converts the children of a container, gathering runs of text and inline elements into
+/// paragraphs.
+fn blocks(parent: ElementRef) -> Result<Vec<NodeKind>, String> {
+    let mut blocks = Vec::new();
+    let mut lines = vec![Vec::new()];
+
+    for child in parent.children() {
+        let block = match child.value() {
+            Node::Element(element) => match element.name() {
+                "div" | "p" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "img" => ElementRef::wrap(child),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        let Some(block) = block else {
+            inline(child, &InlineStyle::default(), &mut lines)?;
+            continue;
+        };
+
+        if let Some(paragraph) = paragraph(Default::default(), None, None, None, std::mem::take(&mut lines))? {
+            blocks.push(paragraph);
+        }
+        lines.push(Vec::new());
+        blocks.push(self::block(block)?);
+    }
+
+    if let Some(paragraph) = paragraph(Default::default(), None, None, None, lines)? {
+        blocks.push(paragraph);
+    }
+
+    Ok(blocks)
+}
+
+/// Converts a block-level element.
+fn block(element: ElementRef) -> Result<NodeKind, String> {
+    let value = element.value();
+    let name = value.name();
+
+    if name == "img" {
+        return Ok(NodeKind::Image(image(value)?));
+    }
+
+    check_attributes(value, &["style", "class", "lang"])?;
+    let style = value
+        .attr("style")
+        .map(|style| parse_declarations(style, name))
+        .transpose()?;
+    let style = style.as_ref().map(parse_style).transpose()?;
+    let tw = value
+        .attr("class")
+        .map(|class| check_classes(class, name).and_then(|_| class.parse()))
+        .transpose()?;
+    let lang = value.attr("lang").map(str::to_owned);
+
+    if name == "div" {
+        return Ok(NodeKind::Container(ContainerNode {
+            preset: Some(takumi::layout::style::Style {
+                display: CssValue::Value(Display::Block),
+                ..Default::default()
+            }),
+            style,
+            children: Some(blocks(element)?.into_boxed_slice()),
+            tw,
+            lang,
+            fragments: None,
+        }));
+    }
+
+    let mut lines = vec![Vec::new()];
+    for child in element.children() {
+        inline(child, &InlineStyle::default(), &mut lines)?;
+    }
+
+    let preset = preset(name);
+    Ok(paragraph(preset, style, tw, lang, lines)?.unwrap_or_else(|| {
+        NodeKind::Text(TextNode {
+            preset: None,
+            style: None,
+            text: String::new(),
+            spans: None,
+            tw: None,
+            fit: None,
+            lang: None,
+        })
+    }))
+}
+
+/// Adds the text inside an inline node to the lines of a paragraph.
+fn inline(node: ego_tree::NodeRef<Node>, parent: &InlineStyle, lines: &mut Vec<Vec<TextSpan>>) -> Result<(), String> {
+    let span = |text: String, image: Option<ImageNode>, style: &InlineStyle| -> Result<TextSpan, String> {
+        Ok(TextSpan {
+            style: style.style()?,
+            text,
+            image,
+            tw: style.tw()?,
+        })
+    };
+
+    match node.value() {
+        Node::Text(text) => {
+            if let Some(line) = lines.last_mut() {
+                line.push(span(text.to_string(), None, parent)?);
+            }
+        }
+        Node::Element(element) => match element.name() {
+            "span" => {
+                check_attributes(element, &["style", "class"])?;
+                let mut style = parent.clone();
+                style.merge(element)?;
+
+                for child in node.children() {
+                    inline(child, &style, lines)?;
+                }
+            }
+            "br" => {
+                check_attributes(element, &[])?;
+                lines.push(Vec::new());
+            }
+            "img" => {
+                if let Some(line) = lines.last_mut() {
+                    line.push(span(String::new(), Some(image(element)?), parent)?);
+                }
+            }
+            "div" | "p" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                return Err(format!("<{}> is a block and cannot be inside text", element.name()));
+            }
+            name => return Err(format!("unsupported HTML element <{name}>")),
+        },
+        Node::Comment(_) => {}
+        _ => return Err("unsupported HTML content".to_string()),
+    }
+
+    Ok(())
+}
+
+/// Lays out the lines of a paragraph as a text node, or as a column of text nodes when it
+/// has line breaks. Returns `None` for a paragraph of nothing but whitespace.
+fn paragraph(
+    preset: takumi::layout::style::Style,
+    style: Option<Style>,
+    tw: Option<TailwindValues>,
+    lang: Option<String>,
+    mut lines: Vec<Vec<TextSpan>>,
+) -> Result<Option<NodeKind>, String> {
+    let is_blank = |spans: &Vec<TextSpan>| {
+        spans
+            .iter()
+            .all(|span| span.image.is_none() && span.text.trim().is_empty())
+    };
+    if lines.len() == 1 && is_blank(&lines[0]) {
+        return Ok(None);
+    }
+
+    let text = |preset, style, tw, lang, spans: Vec<TextSpan>| {
+        NodeKind::Text(TextNode {
+            preset,
+            style,
+            text: String::new(),
+            spans: Some(spans.into_boxed_slice()),
+            tw,
+            fit: None,
+            lang,
+        })
+    };
+
+    if lines.len() == 1 {
+        return Ok(Some(text(Some(preset), style, tw, lang, lines.remove(0))));
+    }
+
+    let mut preset = preset;
+    preset.display = CssValue::Value(Display::Flex);
+    preset.flex_direction = CssValue::Value(FlexDirection::Column);
+
+    let lines = lines
+        .into_iter()
+        .map(|spans| text(None, None, None, None, spans))
+        .collect();
+    Ok(Some(NodeKind::Container(ContainerNode {
+        preset: Some(preset),
+        style,
+        children: Some(lines),
+        tw,
+        lang,
+        fragments: None,
+    })))
+}
+
+/// The default style of a paragraph or heading, as browsers set it.
+fn preset(name: &str) -> takumi::layout::style::Style {
+    let (font_size, margin) = match name {
+        "h1" => (2.0, 0.67),
+        "h2" => (1.5, 0.83),
+        "h3" => (1.17, 1.0),
+        "h4" => (1.0, 1.33),
+        "h5" => (0.83, 1.67),
+        "h6" => (0.67, 2.33),
+        _ => {
+            return takumi::layout::style::Style {
+                margin: CssValue::Value(Sides([
+                    Length::Em(1.0),
+                    Length::Px(0.0),
+                    Length::Em(1.0),
+                    Length::Px(0.0),
+                ])),
+                ..Default::default()
+            }
+        }
+    };
+
+    takumi::layout::style::Style {
+        font_size: CssValue::Value(Some(Length::Em(font_size))),
+        font_weight: CssValue::Value(FontWeight::from(700.0)),
+        margin: CssValue::Value(Sides([
+            Length::Em(margin),
+            Length::Px(0.0),
+            Length::Em(margin),
+            Length::Px(0.0),
+        ])),
+        ..Default::default()
+    }
+}
+
+fn image(element: &Element) -> Result<ImageNode, String> {
+    check_attributes(element, &["style", "class", "src", "width", "height", "alt"])?;
+
+    let Some(src) = element.attr("src") else {
+        return Err("<img> needs a src attribute".to_string());
+    };
+    let dimension = |name: &str| {
+        element
+            .attr(name)
+            .map(|value| {
+                value
+                    .trim()
+                    .trim_end_matches("px")
+                    .parse::<f32>()
+                    .map_err(|_| format!("invalid {name} \"{value}\" on <img>, expected a number of pixels"))
+            })
+            .transpose()
+    };
+
+    let style = element
+        .attr("style")
+        .map(|style| parse_declarations(style, "img"))
+        .transpose()?;
+    let tw = element
+        .attr("class")
+        .map(|class| check_classes(class, "img").and_then(|_| class.parse()))
+        .transpose()?;
+
+    Ok(ImageNode {
+        preset: None,
+        style: style.as_ref().map(parse_style).transpose()?.map(|style| style.base),
+        src: src.into(),
+        width: dimension("width")?,
+        height: dimension("height")?,
+        tw,
+    })
+}
+
+fn check_attributes(element: &Element, supported: &[&str]) -> Result<(), String> {
+    match element.attrs().find(|(name, _)| !supported.contains(name)) {
+        Some((name, _)) => Err(format!("unsupported attribute \"{name}\" on <{}>", element.name())),
+        None => Ok(()),
+    }
+}
+
+fn check_classes(classes: &str, element: &str) -> Result<(), String> {
+    match classes
+        .split_whitespace()
+        .find(|class| TailwindValue::parse(class).is_none())
+    {
+        Some(class) => Err(format!("unknown tailwind class \"{class}\" on <{element}>")),
+        None => Ok(()),
+    }
+}
+
+/// Parses the declarations of a `style` attribute, each on its own so that a property
+/// takumi does not know is an error rather than ignored.
+fn parse_declarations(style: &str, element: &str) -> Result<Map<String, Value>, String> {
+    let mut declarations = Map::new();
+
+    for declaration in split_declarations(style) {
+        let Some((property, value)) = declaration.split_once(':') else {
+            return Err(format!(
+                "invalid CSS declaration \"{}\" on <{element}>",
+                declaration.trim()
+            ));
+        };
+        let property = property.trim().to_ascii_lowercase();
+        let name = camel_case(&property);
+        let value = Value::String(value.trim().to_owned());
+
+        let style = Style::parse_declaration(&name, value.clone())
+            .map_err(|e| format!("invalid value for CSS property \"{property}\" on <{element}>: {e}"))?;
+        if style.is_none() {
+            return Err(format!("unsupported CSS property \"{property}\" on <{element}>"));
+        }
+
+        declarations.insert(name, value);
+    }
+
+    Ok(declarations)
+}
+
+fn parse_style(declarations: &Map<String, Value>) -> Result<Style, String> {
+    serde_json::from_value(Value::Object(declarations.clone())).map_err(|e| e.to_string())
+}
+
+/// Splits the declarations of a `style` attribute at the semicolons outside of strings and
+/// parentheses, like the ones in `url(data:...;base64,...)`.
+fn split_declarations(style: &str) -> impl Iterator<Item = &str> {
+    let mut declarations = Vec::new();
+    let (mut start, mut depth, mut quote) = (0, 0, None);
+
+    for (index, c) in style.char_indices() {
+        match (c, quote) {
+            ('"' | '\'', None) => quote = Some(c),
+            (c, Some(open)) if c == open => quote = None,
+            (_, Some(_)) => {}
+            ('(', None) => depth += 1,
+            (')', None) => depth -= 1,
+            (';', None) if depth <= 0 => {
+                declarations.push(&style[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    declarations.push(&style[start..]);
+
+    declarations
+        .into_iter()
+        .filter(|declaration| !declaration.trim().is_empty())
+}
+
+fn camel_case(property: &str) -> String {
+    let mut parts = property.split('-');
+    let mut name = parts.next().unwrap_or_default().to_owned();
+
+    for part in parts {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            name.extend(first.to_uppercase());
+            name.push_str(chars.as_str());
+        }
+    }
+
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use takumi::layout::style::{tw::TailwindProperty, Color, ColorInput};
+
+    use super::*;
+
+    fn expand(html: &str) -> Result<ContainerNode, String> {
+        HtmlNode {
+            preset: None,
+            style: None,
+            html: html.to_owned(),
+            tw: None,
+        }
+        .expand()
+    }
+
+    /// Outlines a tree: containers in brackets, text nodes as their spans split by `|`.
+    fn outline(node: &NodeKind) -> String {
+        match node {
+            NodeKind::Container(container) => {
+                let children: Vec<_> = container.children.iter().flatten().map(outline).collect();
+                format!("[{}]", children.join(", "))
+            }
+            NodeKind::Text(text) => {
+                let spans: Vec<_> = text
+                    .spans
+                    .iter()
+                    .flatten()
+                    .map(|span| match &span.image {
+                        Some(image) => format!("img({})", image.src),
+                        None => span.text.clone(),
+                    })
+                    .collect();
+                spans.join("|")
+            }
+            NodeKind::Image(image) => format!("img({})", image.src),
+            _ => panic!("HTML is not expanded into containers, text and images"),
+        }
+    }
+
+    #[test]
+    fn elements_map_to_nodes() {
+        let root = expand(concat!(
+            r#"<div class="flex">Loose <span>text</span>"#,
+            r#"<h1>Title</h1><p>One<br>two <img src="icon.png"></p><img src="photo.png" width="10px">"#,
+            "</div>",
+        ))
+        .unwrap();
+
+        assert_eq!(
+            outline(&NodeKind::Container(root)),
+            "[[Loose |text, Title, [One, two |img(icon.png)], img(photo.png)]]"
+        );
+    }
+
+    #[test]
+    fn nested_spans_merge_their_styles_and_classes() {
+        let root = expand(concat!(
+            r#"<p lang="de"><span style="color: red" class="font-bold">"#,
+            r#"<span style="font-size: 20px; color: blue" class="italic">x</span></span></p>"#,
+        ))
+        .unwrap();
+
+        let Some(NodeKind::Text(paragraph)) = root.children.as_deref().and_then(<[_]>::first) else {
+            panic!("<p> is not a text node");
+        };
+        assert_eq!(paragraph.lang.as_deref(), Some("de"));
+
+        let span = &paragraph.spans.as_deref().unwrap()[0];
+        let style = &span.style.as_ref().unwrap().base;
+        assert_eq!(style.font_size, CssValue::Value(Some(Length::Px(20.0))));
+        assert_eq!(style.color, CssValue::Value(ColorInput::Value(Color([0, 0, 255, 255]))));
+
+        let properties: Vec<_> = span.tw.iter().flat_map(TailwindValues::iter).map(|value| &value.property).collect();
+        assert!(matches!(
+            properties.as_slice(),
+            [TailwindProperty::FontWeight(_), TailwindProperty::FontStyle(_)]
+        ));
+    }
+
+    #[test]
+    fn declarations_split_outside_of_parentheses_and_strings() {
+        let declarations: Vec<_> =
+            split_declarations("background-image: url(data:image/png;base64,AA); font-family: 'a;b';").collect();
+
+        assert_eq!(declarations, ["background-image: url(data:image/png;base64,AA)", " font-family: 'a;b'"]);
+    }
+
+    #[test]
+    fn html_outside_of_the_subset_fails() {
+        let error = |html| expand(html).unwrap_err();
+
+        assert_eq!(error("<table></table>"), "unsupported HTML element <table>");
+        assert_eq!(error(r#"<div id="x"></div>"#), "unsupported attribute \"id\" on <div>");
+        assert_eq!(error(r#"<p style="float: left">x</p>"#), "unsupported CSS property \"float\" on <p>");
+        assert_eq!(error(r#"<p class="not-a-class">x</p>"#), "unknown tailwind class \"not-a-class\" on <p>");
+        assert_eq!(error("<span><h1>x</h1></span>"), "<h1> is a block and cannot be inside text");
+        assert_eq!(error("<img>"), "<img> needs a src attribute");
+    }
+}
@@ -0,0 +1,142 @@
+//! Drawing a hyphen where a line breaks at a soft hyphen.
+//!
+//! The text layout breaks lines at soft hyphens (U+00AD) but never draws them. Once the
+//! tree is lowered, every soft hyphen a line of a paragraph ends at is replaced by a
+//! hyphen followed by a zero width space, keeping the break in place. A wider hyphen can
+//! push the break elsewhere, so the text is measured again until every drawn hyphen ends
+//! a line; the ones that no longer do are dropped along with their break opportunity.
+
+use takumi::{
+    layout::{node::NodeKind, Viewport},
+    GlobalContext,
+};
+
+use super::probe;
+
+const SOFT_HYPHEN: char = '\u{00AD}';
+
+/// A hyphen that is drawn, followed by a break opportunity.
+const HARD_HYPHEN: &str = "-\u{200B}";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Hyphen {
+    Soft,
+    Hard,
+    Removed,
+}
+
+/// Draws a hyphen at every soft hyphen a line of a paragraph in the tree breaks at.
+pub(crate) fn draw_hyphens(tree: &mut NodeKind, viewport: Viewport, global: &GlobalContext) -> Result<(), String> {
+    let mut paths = Vec::new();
+    collect_hyphenated_paths(tree, &mut Vec::new(), &mut paths);
+
+    for path in paths {
+        let Some(runs) = probe::paragraph_runs(probe::node_at(tree, &path)) else {
+            continue;
+        };
+
+        let segments: Vec<Vec<String>> = runs
+            .iter()
+            .map(|run| run.text.split(SOFT_HYPHEN).map(str::to_string).collect())
+            .collect();
+        let mut hyphens = vec![Hyphen::Soft; segments.iter().map(|run| run.len() - 1).sum()];
+
+        // Every round either draws a soft hyphen or removes a drawn one, so this ends.
+        while let Some(measured) = probe::measure_text(tree, &path, viewport, global)? {
+
+            // Removed hyphens are no longer in the text, so skip them when counting.
+            let present: Vec<usize> = (0..hyphens.len()).filter(|index| hyphens[*index] != Hyphen::Removed).collect();
+            let line_ends: Vec<usize> = hyphen_ordinals_at_line_ends(&measured)
+                .into_iter()
+                .filter_map(|ordinal| present.get(ordinal).copied())
+                .collect();
+            let mut changed = false;
+
+            for (index, hyphen) in hyphens.iter_mut().enumerate() {
+                let next = match (*hyphen, line_ends.contains(&index)) {
+                    (Hyphen::Soft, true) => Hyphen::Hard,
+                    (Hyphen::Hard, false) => Hyphen::Removed,
+                    (state, _) => state,
+                };
+
+                changed |= next != *hyphen;
+                *hyphen = next;
+            }
+
+            if !changed {
+                break;
+            }
+
+            if let Some(runs) = probe::paragraph_runs_mut(probe::node_at(tree, &path)) {
+                let mut remaining = hyphens.as_slice();
+
+                for (run, segments) in runs.into_iter().zip(&segments) {
+                    let (own, rest) = remaining.split_at(segments.len() - 1);
+                    run.text = join(segments, own);
+                    remaining = rest;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn collect_hyphenated_paths(node: &NodeKind, path: &mut Vec<usize>, paths: &mut Vec<Vec<usize>>) {
+    if let Some(runs) = probe::paragraph_runs(node) {
+        if runs.iter().any(|run| run.text.contains(SOFT_HYPHEN)) {
+            paths.push(path.clone());
+        }
+        return;
+    }
+
+    if let NodeKind::Container(container) = node {
+        for (index, child) in container.children.iter().flatten().enumerate() {
+            path.push(index);
+            collect_hyphenated_paths(child, path, paths);
+            path.pop();
+        }
+    }
+}
+
+fn join(segments: &[String], hyphens: &[Hyphen]) -> String {
+    let mut text = segments[0].clone();
+
+    for (segment, hyphen) in segments[1..].iter().zip(hyphens) {
+        match hyphen {
+            Hyphen::Soft => text.push(SOFT_HYPHEN),
+            Hyphen::Hard => text.push_str(HARD_HYPHEN),
+            Hyphen::Removed => {}
+        }
+        text.push_str(segment);
+    }
+
+    text
+}
+
+/// Returns the ordinals of the hyphens in the text, soft or drawn, that a line ends at.
+fn hyphen_ordinals_at_line_ends(measured: &probe::MeasuredText) -> Vec<usize> {
+    let mut ordinals = Vec::new();
+    let mut ordinal = 0;
+
+    for line in measured.lines() {
+        // Glyph runs split from one shaped run, e.g. by a span changing the color, each
+        // report the text of the whole shaped run.
+        let mut texts: Vec<&str> = line.iter().map(|run| run.text.as_str()).collect();
+        texts.dedup();
+
+        let text = texts.concat();
+        ordinal += count_hyphens(&text);
+
+        let text = text.trim_end();
+        if ordinal > 0 && (text.ends_with(SOFT_HYPHEN) || text.ends_with(HARD_HYPHEN)) {
+            ordinals.push(ordinal - 1);
+        }
+    }
+
+    ordinals
+}
+
+fn count_hyphens(text: &str) -> usize {
+    text.matches(SOFT_HYPHEN).count() + text.matches(HARD_HYPHEN).count()
+}
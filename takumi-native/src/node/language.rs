@@ -0,0 +1,74 @@
+//! Text that depends on the language of a node.
+//!
+//! takumi lays out text without a language, so both of these are resolved natively
+//! from the inherited `lang`: `textTransform` is applied with the case mapping rules of
+//! the language (e.g. the dotted and dotless i in Turkish), and the fallback fonts
+//! registered for the language are appended to the font families of the text.
+//!
+//! Selecting the `locl` features of a font by language is not supported, and is not
+//! emulated here. The shaper picks them from the locale of parley's text style, which
+//! takumi always builds as `locale: None` (`layout/style/stylesheets.rs`) with no way
+//! to set it from outside. Supporting it takes a takumi release that passes the
+//! language through; until then, glyph variants that differ by language, like those of
+//! Han characters, come from the fallback fonts.
+
+use std::borrow::Cow;
+
+use icu_casemap::{options::TitlecaseOptions, CaseMapper};
+use icu_locale_core::LanguageIdentifier;
+use takumi::{
+    layout::style::{FontFamily, TextTransform},
+    parley::FontStack,
+};
+
+use crate::font;
+
+/// Applies the text transform with the case mapping rules of the language.
+///
+/// Returns `None` when the language is not a valid BCP 47 tag, leaving the transform to
+/// takumi.
+pub(crate) fn transform_text<'t>(text: &'t str, transform: TextTransform, lang: &str) -> Option<Cow<'t, str>> {
+    let langid = LanguageIdentifier::try_from_str(lang).ok()?;
+    let case_mapper = CaseMapper::new();
+
+    Some(match transform {
+        TextTransform::None => Cow::Borrowed(text),
+        TextTransform::Uppercase => case_mapper.uppercase_to_string(text, &langid),
+        TextTransform::Lowercase => case_mapper.lowercase_to_string(text, &langid),
+        TextTransform::Capitalize => {
+            let mut capitalized = String::with_capacity(text.len());
+            let mut rest = text;
+
+            // Words are split like takumi does: numbers do not start a new word.
+            while !rest.is_empty() {
+                let is_word = |c: char| c.is_alphabetic() || c.is_numeric();
+                let word_start = rest.find(is_word).unwrap_or(rest.len());
+                capitalized.push_str(&rest[..word_start]);
+                rest = &rest[word_start..];
+
+                let word_end = rest.find(|c: char| !is_word(c)).unwrap_or(rest.len());
+                capitalized.push_str(&case_mapper.titlecase_segment_with_only_case_data_to_string(
+                    &rest[..word_end],
+                    &langid,
+                    TitlecaseOptions::default(),
+                ));
+                rest = &rest[word_end..];
+            }
+
+            Cow::Owned(capitalized)
+        }
+    })
+}
+
+/// Appends the fallback font families registered for the language to the font families
+/// of the text.
+pub(crate) fn font_family_with_fallback(font_family: Option<&FontFamily>, lang: &str) -> Option<FontFamily> {
+    let fallback = unsafe { font::language_fallback(lang) }?;
+
+    let font_family = font_family.cloned().unwrap_or_default();
+    let FontStack::Source(families) = FontStack::from(&font_family) else {
+        return None;
+    };
+
+    Some(FontFamily::from(format!("{families}, {fallback}").as_str()))
+}
@@ -0,0 +1,523 @@
+//! Markdown set as a tree of nodes.
+//!
+//! A Markdown node is expanded into containers and text nodes before anything else is
+//! resolved: every heading and paragraph becomes a text node, with a span per run of inline
+//! text, every list a column of rows holding the item's marker and its blocks, and every
+//! code block a code block node.
+//!
+//! Spans are not nested, so the style of a run of text is the styles of the inline elements
+//! around it merged from the outermost one in, the way CSS rules of equal specificity are
+//! layered. The styles are kept as declared until then, and only parsed once merged.
+
+use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use takumi::layout::style::{tw::TailwindValues, CssValue, Display, FlexDirection, FlexGrow, FontWeight, Length};
+
+use super::{CodeBlockNode, ContainerNode, NodeKind, Style, TextNode, TextSpan};
+
+/// A node that sets a subset of Markdown: headings, paragraphs, emphasis, strong emphasis,
+/// inline code, links, lists and code blocks.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MarkdownNode {
+    /// Default style presets from HTML element type (lowest priority)
+    pub preset: Option<takumi::layout::style::Style>,
+    /// The styling properties for this markdown node
+    pub style: Option<Style>,
+    /// The Markdown source
+    pub markdown: String,
+    /// The styles of the elements, taking precedence over their default styles
+    pub styles: Option<Box<MarkdownStyles>>,
+    /// The tailwind properties for this markdown node
+    pub tw: Option<TailwindValues>,
+}
+
+/// The styles of the elements of a Markdown node.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct MarkdownStyles {
+    /// The style of first-level headings
+    pub heading1: Option<ElementStyle>,
+    /// The style of second-level headings
+    pub heading2: Option<ElementStyle>,
+    /// The style of third-level headings
+    pub heading3: Option<ElementStyle>,
+    /// The style of fourth-level headings
+    pub heading4: Option<ElementStyle>,
+    /// The style of fifth-level headings
+    pub heading5: Option<ElementStyle>,
+    /// The style of sixth-level headings
+    pub heading6: Option<ElementStyle>,
+    /// The style of paragraphs
+    pub paragraph: Option<ElementStyle>,
+    /// The style of lists
+    pub list: Option<ElementStyle>,
+    /// The style of list items, each a row of its marker and its blocks
+    pub list_item: Option<ElementStyle>,
+    /// The style of the bullets and numbers of list items
+    pub list_marker: Option<ElementStyle>,
+    /// The style of code blocks
+    pub code_block: Option<ElementStyle>,
+    /// The style of emphasized text, italic by default
+    pub emphasis: Option<ElementStyle>,
+    /// The style of strongly emphasized text, bold by default
+    pub strong: Option<ElementStyle>,
+    /// The style of inline code, set in the monospace family by default
+    pub code: Option<ElementStyle>,
+    /// The style of links, blue by default
+    pub link: Option<ElementStyle>,
+}
+
+/// The style of an element, kept as declared so that it can be merged with other styles,
+/// like the ones of the Markdown elements around it.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(transparent)]
+pub struct ElementStyle(pub(crate) Map<String, Value>);
+
+impl ElementStyle {
+    /// Layers `other` on top of this style.
+    fn merge(&mut self, other: &ElementStyle) {
+        self.0
+            .extend(other.0.iter().map(|(name, value)| (name.clone(), value.clone())));
+    }
+
+    fn parse(&self) -> Result<Style, String> {
+        serde_json::from_value(Value::Object(self.0.clone())).map_err(|e| format!("invalid markdown style: {e}"))
+    }
+}
+
+/// A block containing other blocks, waiting for its end.
+struct OpenBlock {
+    element: Element,
+    children: Vec<NodeKind>,
+}
+
+/// The element an open block is made of.
+enum Element {
+    Root,
+    /// A list, with the number of its next item if it is ordered.
+    List(Option<u64>),
+    /// A list item, with its marker.
+    Item(String),
+}
+
+/// A heading or paragraph being set, a list of spans per line.
+struct TextBlock {
+    preset: takumi::layout::style::Style,
+    style: Option<ElementStyle>,
+    lines: Vec<Vec<TextSpan>>,
+}
+
+/// Expands Markdown into blocks, following the events of its parser.
+struct Expander<'s> {
+    styles: &'s MarkdownStyles,
+    blocks: Vec<OpenBlock>,
+    text: Option<TextBlock>,
+    /// The language and source of the code block being read.
+    code: Option<(Option<String>, String)>,
+    /// The merged styles of the inline elements around the text being read, outermost first.
+    inline: Vec<ElementStyle>,
+}
+
+impl MarkdownNode {
+    /// Expands the Markdown into a container of its blocks.
+    pub(crate) fn expand(&self) -> Result<ContainerNode, String> {
+        let default_styles = MarkdownStyles::default();
+        let mut expander = Expander {
+            styles: self.styles.as_deref().unwrap_or(&default_styles),
+            blocks: vec![OpenBlock {
+                element: Element::Root,
+                children: Vec::new(),
+            }],
+            text: None,
+            code: None,
+            inline: Vec::new(),
+        };
+
+        for event in Parser::new_ext(&self.markdown, Options::empty()) {
+            expander.handle(event)?;
+        }
+        expander.end_text()?;
+
+        let root = expander.blocks.swap_remove(0);
+
+        Ok(ContainerNode {
+            preset: Some(column(self.preset.clone(), 0.5)),
+            style: self.style.clone(),
+            children: Some(root.children.into_boxed_slice()),
+            tw: self.tw.clone(),
+            lang: None,
+            fragments: None,
+        })
+    }
+}
+
+impl Expander<'_> {
+    fn handle(&mut self, event: Event) -> Result<(), String> {
+        let styles = self.styles;
+
+        match event {
+            Event::Start(Tag::Paragraph) => self.start_text(Default::default(), styles.paragraph.clone())?,
+            Event::Start(Tag::Heading { level, .. }) => {
+                let (font_size, style) = match level as usize {
+                    1 => (2.0, &styles.heading1),
+                    2 => (1.5, &styles.heading2),
+                    3 => (1.25, &styles.heading3),
+                    4 => (1.0, &styles.heading4),
+                    5 => (0.875, &styles.heading5),
+                    _ => (0.85, &styles.heading6),
+                };
+                let preset = takumi::layout::style::Style {
+                    font_size: CssValue::Value(Some(Length::Em(font_size))),
+                    font_weight: CssValue::Value(FontWeight::from(700.0)),
+                    ..Default::default()
+                };
+                self.start_text(preset, style.clone())?;
+            }
+            Event::End(TagEnd::Paragraph | TagEnd::Heading(_)) => self.end_text()?,
+
+            Event::Start(Tag::List(first_number)) => {
+                self.end_text()?;
+                self.open(Element::List(first_number));
+            }
+            Event::Start(Tag::Item) => {
+                self.end_text()?;
+                let marker = match self.blocks.last_mut().map(|block| &mut block.element) {
+                    Some(Element::List(Some(number))) => {
+                        *number += 1;
+                        format!("{}.", *number - 1)
+                    }
+                    _ => "•".to_string(),
+                };
+                self.open(Element::Item(marker));
+            }
+            Event::End(TagEnd::List(_) | TagEnd::Item) => {
+                self.end_text()?;
+                self.close()?;
+            }
+
+            Event::Start(Tag::CodeBlock(kind)) => {
+                self.end_text()?;
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => info.split_whitespace().next().map(str::to_owned),
+                    CodeBlockKind::Indented => None,
+                };
+                self.code = Some((language, String::new()));
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                let Some((language, mut code)) = self.code.take() else {
+                    return Ok(());
+                };
+                code.truncate(code.trim_end_matches('\n').len());
+                let code_block = CodeBlockNode {
+                    preset: None,
+                    style: styles.code_block.as_ref().map(ElementStyle::parse).transpose()?,
+                    code,
+                    language,
+                    theme: None,
+                    line_numbers: false,
+                    first_line_number: None,
+                    tab_width: None,
+                    long_lines: None,
+                    tw: None,
+                };
+                self.push(NodeKind::CodeBlock(code_block));
+            }
+
+            Event::Start(Tag::Emphasis) => self.start_inline(json!({ "fontStyle": "italic" }), &styles.emphasis),
+            Event::Start(Tag::Strong) => self.start_inline(json!({ "fontWeight": 700 }), &styles.strong),
+            Event::Start(Tag::Link { .. }) => self.start_inline(json!({ "color": "#2563eb" }), &styles.link),
+            Event::End(TagEnd::Emphasis | TagEnd::Strong | TagEnd::Link) => {
+                self.inline.pop();
+            }
+
+            Event::Text(text) => match &mut self.code {
+                Some((_, code)) => code.push_str(&text),
+                None => self.push_span(&text, None)?,
+            },
+            Event::Code(code) => {
+                let style = merged(json!({ "fontFamily": "monospace" }), &styles.code);
+                self.push_span(&code, Some(&style))?;
+            }
+            Event::SoftBreak => self.push_span(" ", None)?,
+            Event::HardBreak => {
+                if let Some(text) = &mut self.text {
+                    text.lines.push(Vec::new());
+                }
+            }
+
+            // Everything else is left out, and the text inside it set like the text around it.
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn open(&mut self, element: Element) {
+        self.blocks.push(OpenBlock {
+            element,
+            children: Vec::new(),
+        });
+    }
+
+    /// Closes the innermost open block, adding it to the block around it.
+    fn close(&mut self) -> Result<(), String> {
+        let Some(block) = self.blocks.pop_if(|block| !matches!(block.element, Element::Root)) else {
+            return Ok(());
+        };
+
+        let node = match block.element {
+            Element::Root => unreachable!("the root block is never closed"),
+            Element::List(_) => container(column(None, 0.25), &self.styles.list, block.children)?,
+            Element::Item(marker) => {
+                let marker_preset = takumi::layout::style::Style {
+                    flex_shrink: CssValue::Value(Some(FlexGrow(0.0))),
+                    min_width: CssValue::Value(Length::Em(1.25)),
+                    padding_right: CssValue::Value(Some(Length::Em(0.5))),
+                    ..Default::default()
+                };
+                let marker = text(marker_preset, &self.styles.list_marker, vec![span(&marker, None)?])?;
+
+                let mut body_preset = column(None, 0.5);
+                body_preset.flex_grow = CssValue::Value(Some(FlexGrow(1.0)));
+                body_preset.min_width = CssValue::Value(Length::Px(0.0));
+                let body = NodeKind::Container(ContainerNode {
+                    preset: Some(body_preset),
+                    style: None,
+                    children: Some(block.children.into_boxed_slice()),
+                    tw: None,
+                    lang: None,
+                    fragments: None,
+                });
+
+                let row_preset = takumi::layout::style::Style {
+                    display: CssValue::Value(Display::Flex),
+                    flex_direction: CssValue::Value(FlexDirection::Row),
+                    ..Default::default()
+                };
+                container(row_preset, &self.styles.list_item, vec![NodeKind::Text(marker), body])?
+            }
+        };
+
+        self.push(node);
+        Ok(())
+    }
+
+    /// Adds a finished block to the innermost open block.
+    fn push(&mut self, node: NodeKind) {
+        if let Some(block) = self.blocks.last_mut() {
+            block.children.push(node);
+        }
+    }
+
+    fn start_text(&mut self, preset: takumi::layout::style::Style, style: Option<ElementStyle>) -> Result<(), String> {
+        self.end_text()?;
+        self.text = Some(TextBlock {
+            preset,
+            style,
+            lines: vec![Vec::new()],
+        });
+        Ok(())
+    }
+
+    /// Finishes the heading or paragraph being set. A paragraph with hard line breaks is set
+    /// as a column of lines.
+    fn end_text(&mut self) -> Result<(), String> {
+        let Some(mut block) = self.text.take() else {
+            return Ok(());
+        };
+
+        let node = match block.lines.len() {
+            1 => NodeKind::Text(text(block.preset, &block.style, block.lines.remove(0))?),
+            _ => {
+                let lines = block
+                    .lines
+                    .into_iter()
+                    .map(|spans| text(Default::default(), &None, spans).map(NodeKind::Text))
+                    .collect::<Result<_, _>>()?;
+
+                container(column(Some(block.preset), 0.0), &block.style, lines)?
+            }
+        };
+
+        self.push(node);
+        Ok(())
+    }
+
+    fn start_inline(&mut self, default: Value, style: &Option<ElementStyle>) {
+        let mut merged_style = self.inline.last().cloned().unwrap_or_default();
+        merged_style.merge(&merged(default, style));
+        self.inline.push(merged_style);
+    }
+
+    /// Adds a span of text to the heading or paragraph being set, starting a paragraph for
+    /// text outside of one, like the text of an item of a tight list.
+    fn push_span(&mut self, text: &str, style: Option<&ElementStyle>) -> Result<(), String> {
+        if self.text.is_none() {
+            self.start_text(Default::default(), self.styles.paragraph.clone())?;
+        }
+
+        let mut merged_style = self.inline.last().cloned();
+        if let Some(style) = style {
+            merged_style.get_or_insert_with(Default::default).merge(style);
+        }
+
+        let span = span(text, merged_style.as_ref())?;
+        if let Some(line) = self.text.as_mut().and_then(|block| block.lines.last_mut()) {
+            line.push(span);
+        }
+
+        Ok(())
+    }
+}
+
+/// The default style of an element with its declared style layered on top.
+fn merged(default: Value, style: &Option<ElementStyle>) -> ElementStyle {
+    let Value::Object(default) = default else {
+        unreachable!("default styles are objects");
+    };
+
+    let mut merged_style = ElementStyle(default);
+    if let Some(style) = style {
+        merged_style.merge(style);
+    }
+    merged_style
+}
+
+/// A preset laying out blocks in a column, `row_gap` ems apart.
+fn column(preset: Option<takumi::layout::style::Style>, row_gap: f32) -> takumi::layout::style::Style {
+    let mut preset = preset.unwrap_or_default();
+    preset.display = CssValue::Value(Display::Flex);
+    preset.flex_direction = CssValue::Value(FlexDirection::Column);
+    preset.row_gap = CssValue::Value(Some(Length::Em(row_gap)));
+    preset
+}
+
+fn container(
+    preset: takumi::layout::style::Style,
+    style: &Option<ElementStyle>,
+    children: Vec<NodeKind>,
+) -> Result<NodeKind, String> {
+    Ok(NodeKind::Container(ContainerNode {
+        preset: Some(preset),
+        style: style.as_ref().map(ElementStyle::parse).transpose()?,
+        children: Some(children.into_boxed_slice()),
+        tw: None,
+        lang: None,
+        fragments: None,
+    }))
+}
+
+fn text(
+    preset: takumi::layout::style::Style,
+    style: &Option<ElementStyle>,
+    spans: Vec<TextSpan>,
+) -> Result<TextNode, String> {
+    Ok(TextNode {
+        preset: Some(preset),
+        style: style.as_ref().map(ElementStyle::parse).transpose()?,
+        text: String::new(),
+        spans: Some(spans.into_boxed_slice()),
+        tw: None,
+        fit: None,
+        lang: None,
+    })
+}
+
+fn span(text: &str, style: Option<&ElementStyle>) -> Result<TextSpan, String> {
+    Ok(TextSpan {
+        style: style.map(ElementStyle::parse).transpose()?,
+        text: text.to_owned(),
+        image: None,
+        tw: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use takumi::layout::style::FontStyle;
+
+    use super::*;
+
+    fn expand(markdown: &str, styles: Value) -> Result<ContainerNode, String> {
+        serde_json::from_value::<MarkdownNode>(json!({ "markdown": markdown, "styles": styles }))
+            .unwrap()
+            .expand()
+    }
+
+    /// Outlines a tree: containers in brackets, text nodes as their spans split by `|`.
+    fn outline(node: &NodeKind) -> String {
+        match node {
+            NodeKind::Container(container) => {
+                let children: Vec<_> = container.children.iter().flatten().map(outline).collect();
+                format!("[{}]", children.join(", "))
+            }
+            NodeKind::Text(text) => {
+                let spans: Vec<_> = text.spans.iter().flatten().map(|span| span.text.as_str()).collect();
+                spans.join("|")
+            }
+            NodeKind::CodeBlock(code_block) => {
+                format!("code({}: {})", code_block.language.as_deref().unwrap_or_default(), code_block.code)
+            }
+            _ => panic!("markdown is not expanded into containers and text"),
+        }
+    }
+
+    fn spans(node: &NodeKind) -> &[TextSpan] {
+        match node {
+            NodeKind::Text(text) => text.spans.as_deref().unwrap(),
+            _ => panic!("blocks of text are not text nodes"),
+        }
+    }
+
+    #[test]
+    fn blocks_are_set_in_a_column() {
+        let root = expand("# Title\n\nSome *emphasis* and `code`.\n\n```rust\nfn main() {}\n```\n", json!(null));
+
+        assert_eq!(
+            outline(&NodeKind::Container(root.unwrap())),
+            "[Title, Some |emphasis| and |code|., code(rust: fn main() {})]"
+        );
+    }
+
+    #[test]
+    fn list_items_are_rows_of_their_marker_and_blocks() {
+        let root = expand("3. three\n4. four\n\n- a\n  - b\n", json!(null)).unwrap();
+
+        assert_eq!(
+            outline(&NodeKind::Container(root)),
+            "[[[3., [three]], [4., [four]]], [[•, [a, [[•, [b]]]]]]]"
+        );
+    }
+
+    #[test]
+    fn hard_breaks_set_a_column_of_lines() {
+        let root = expand("one  \ntwo\nthree", json!(null)).unwrap();
+
+        assert_eq!(outline(&NodeKind::Container(root)), "[[one, two| |three]]");
+    }
+
+    #[test]
+    fn inline_styles_merge_from_the_outermost_in() {
+        let root = expand("*a **b***", json!({ "strong": { "color": "red", "fontStyle": "normal" } })).unwrap();
+        let paragraph = &root.children.as_deref().unwrap()[0];
+        let [a, b] = spans(paragraph) else {
+            panic!("the paragraph is not split in two spans");
+        };
+
+        let a = &a.style.as_ref().unwrap().base;
+        assert_eq!(a.font_style, CssValue::Value(FontStyle::italic()));
+
+        let b = &b.style.as_ref().unwrap().base;
+        assert_eq!(b.font_style, CssValue::Value(FontStyle::normal()));
+        assert_eq!(b.font_weight, CssValue::Value(FontWeight::from(700.0)));
+        assert!(matches!(b.color, CssValue::Value(_)));
+    }
+
+    #[test]
+    fn invalid_styles_fail() {
+        let error = expand("text", json!({ "paragraph": { "width": {} } })).unwrap_err();
+
+        assert!(error.starts_with("invalid markdown style: "), "{error}");
+    }
+}
@@ -0,0 +1,249 @@
+//! The node tree accepted by the native library.
+//!
+//! These nodes mirror [`takumi::layout::node::NodeKind`] and add features that are
+//! resolved natively before layout. A tree is lowered into takumi's own nodes right
+//! before it is measured or rendered.
+
+mod breaking;
+mod cascade;
+mod code;
+mod code_block;
+mod container;
+mod direction;
+mod drawing;
+mod fit;
+mod fragment;
+mod html;
+mod hyphens;
+mod language;
+mod markdown;
+mod paint;
+mod probe;
+mod react;
+mod shape;
+mod style;
+mod svg;
+mod text;
+mod vertical;
+
+pub use code::*;
+pub use code_block::*;
+pub use container::*;
+pub use fragment::*;
+pub use html::*;
+pub use markdown::*;
+pub use paint::*;
+pub use shape::*;
+pub use style::*;
+pub use svg::*;
+pub use text::*;
+
+use std::{collections::HashMap, sync::Arc};
+
+use serde::Deserialize;
+use takumi::{
+    layout::{node::ImageNode, Viewport},
+    resources::image::ImageSource,
+    GlobalContext,
+};
+
+/// Images drawn natively, keyed by the source of the image nodes drawing them.
+pub(crate) type Resources = HashMap<Arc<str>, Arc<ImageSource>>;
+
+/// A tree lowered into takumi's nodes for a viewport, whose natively drawn nodes are
+/// still to be drawn.
+pub(crate) struct Lowered {
+    tree: takumi::layout::node::NodeKind,
+    drawings: Vec<drawing::Drawing>,
+    /// The font sizes picked for the text nodes with `fit` set, in document order.
+    pub(crate) font_sizes: Vec<f32>,
+}
+
+impl Lowered {
+    /// Draws the natively drawn nodes into a copy of the tree, returning it along with
+    /// the images drawn for them. Paint nodes are painted again on every call.
+    pub(crate) fn draw(
+        &self,
+        viewport: Viewport,
+        global: &GlobalContext,
+    ) -> Result<(takumi::layout::node::NodeKind, Resources), String> {
+        let mut tree = self.tree.clone();
+        let resources = drawing::draw(&mut tree, &self.drawings, viewport, global)?;
+
+        Ok((tree, resources))
+    }
+}
+
+/// Represents the nodes enum.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum NodeKind {
+    /// A node that contains other nodes.
+    Container(ContainerNode),
+    /// A node that displays an image.
+    Image(ImageNode),
+    /// A node that displays text.
+    Text(TextNode),
+    /// A node that draws SVG markup.
+    Svg(SvgNode),
+    /// A node that draws vector shapes.
+    Shape(ShapeNode),
+    /// A node that draws data as a QR code.
+    #[serde(rename = "qrcode")]
+    QrCode(QrCodeNode),
+    /// A node that draws data as a barcode.
+    Barcode(BarcodeNode),
+    /// A node drawn by the host.
+    Paint(PaintNode),
+    /// A node that highlights source code.
+    #[serde(rename = "codeblock")]
+    CodeBlock(CodeBlockNode),
+    /// A node that sets Markdown.
+    Markdown(MarkdownNode),
+    /// A node that parses HTML.
+    Html(HtmlNode),
+    /// A node that instantiates a fragment.
+    Fragment(FragmentNode),
+}
+
+impl NodeKind {
+    /// Parses a tree from JSON, either as nodes or as a serialized React element.
+    pub(crate) fn from_json(json: &str) -> Result<NodeKind, String> {
+        serde_json::from_str(json).or_else(|error| match serde_json::from_str::<serde_json::Value>(json) {
+            Ok(value) if react::is_element(&value) => react::to_node(&value),
+            _ => Err(error.to_string()),
+        })
+    }
+
+    /// Builds a tree from parsed JSON, either nodes or a serialized React element.
+    pub(crate) fn from_value(value: &serde_json::Value) -> Result<NodeKind, String> {
+        NodeKind::deserialize(value).or_else(|error| match react::is_element(value) {
+            true => react::to_node(value),
+            false => Err(error.to_string()),
+        })
+    }
+
+    /// Expands the composite nodes of the tree and checks it, which does not depend on the
+    /// viewport and is a no-op on a tree already resolved.
+    pub(crate) fn resolve(&mut self) -> Result<(), String> {
+        self.expand(&mut FragmentScope::default())?;
+        self.check_codes()
+    }
+
+    /// Resolves every native feature of the tree and lowers it into takumi's nodes, along
+    /// with the images drawn natively for them.
+    pub(crate) fn prepare(
+        self,
+        viewport: Viewport,
+        global: &GlobalContext,
+    ) -> Result<(takumi::layout::node::NodeKind, Resources), String> {
+        let Lowered { mut tree, drawings, .. } = self.lower(viewport, global)?;
+        let resources = drawing::draw(&mut tree, &drawings, viewport, global)?;
+
+        Ok((tree, resources))
+    }
+
+    /// Resolves every native feature of the tree and lowers it into takumi's nodes, except
+    /// for the nodes drawn natively once the tree is laid out.
+    pub(crate) fn lower(mut self, viewport: Viewport, global: &GlobalContext) -> Result<Lowered, String> {
+        self.resolve()?;
+        let font_sizes = fit::fit_text(&mut self, viewport, global)?;
+
+        let vertical_texts = vertical::collect_texts(&self, viewport);
+        let drawings = drawing::collect_drawings(&self, viewport);
+
+        let mut tree = self.into_takumi(&InheritedStyle::default(), viewport);
+        hyphens::draw_hyphens(&mut tree, viewport, global)?;
+        vertical::size_cells(&mut tree, &vertical_texts, viewport, global)?;
+
+        Ok(Lowered {
+            tree,
+            drawings,
+            font_sizes,
+        })
+    }
+
+    /// Lowers the tree into takumi's nodes as-is.
+    pub(crate) fn into_takumi(self, parent: &InheritedStyle, viewport: Viewport) -> takumi::layout::node::NodeKind {
+        match self {
+            NodeKind::Container(container) => container.into_takumi(parent, viewport),
+            NodeKind::Image(image) => image.into(),
+            NodeKind::Text(text) => text.into_takumi(parent, viewport),
+            NodeKind::Svg(svg) => svg.into_takumi(parent, viewport),
+            NodeKind::Shape(shape) => shape.into_takumi(),
+            NodeKind::QrCode(qr_code) => qr_code.into_takumi(parent, viewport),
+            NodeKind::Barcode(barcode) => barcode.into_takumi(parent, viewport),
+            NodeKind::Paint(paint) => paint.into_takumi(),
+            NodeKind::CodeBlock(_) | NodeKind::Markdown(_) | NodeKind::Html(_) | NodeKind::Fragment(_) => {
+                unreachable!("composite nodes are expanded before lowering")
+            }
+        }
+    }
+
+    /// Replaces every node made of other nodes, like a code block or a fragment, with the
+    /// nodes it is made of.
+    fn expand(&mut self, scope: &mut FragmentScope) -> Result<(), String> {
+        match self {
+            NodeKind::CodeBlock(code_block) => *self = NodeKind::Container(code_block.expand()?),
+            NodeKind::Markdown(markdown) => *self = NodeKind::Container(markdown.expand()?),
+            NodeKind::Html(html) => *self = NodeKind::Container(html.expand()?),
+            NodeKind::Fragment(fragment) => {
+                let id = fragment.id.clone();
+                *self = scope.instantiate(fragment)?;
+                return scope.expand_instance(id, self);
+            }
+            _ => {}
+        }
+
+        // The fragments a container defines are in scope for the nodes inside it.
+        let fragments = match self {
+            NodeKind::Container(container) => container.fragments.take(),
+            _ => None,
+        };
+
+        scope.enter(fragments);
+        let result = self
+            .children_mut()
+            .into_iter()
+            .flatten()
+            .try_for_each(|child| child.expand(scope));
+        scope.leave();
+
+        result
+    }
+
+    /// Checks that the data of every QR code and barcode in the tree can be encoded.
+    fn check_codes(&self) -> Result<(), String> {
+        match self {
+            NodeKind::Container(container) => container.children.iter().flatten().try_for_each(NodeKind::check_codes),
+            NodeKind::QrCode(qr_code) => qr_code.encode().map(|_| ()),
+            NodeKind::Barcode(barcode) => barcode.encode().map(|_| ()),
+            NodeKind::Image(_)
+            | NodeKind::Text(_)
+            | NodeKind::Svg(_)
+            | NodeKind::Shape(_)
+            | NodeKind::Paint(_)
+            | NodeKind::CodeBlock(_)
+            | NodeKind::Markdown(_)
+            | NodeKind::Html(_)
+            | NodeKind::Fragment(_) => Ok(()),
+        }
+    }
+
+    fn children_mut(&mut self) -> Option<&mut [NodeKind]> {
+        match self {
+            NodeKind::Container(container) => container.children.as_deref_mut(),
+            NodeKind::Image(_)
+            | NodeKind::Text(_)
+            | NodeKind::Svg(_)
+            | NodeKind::Shape(_)
+            | NodeKind::QrCode(_)
+            | NodeKind::Barcode(_)
+            | NodeKind::Paint(_)
+            | NodeKind::CodeBlock(_)
+            | NodeKind::Markdown(_)
+            | NodeKind::Html(_)
+            | NodeKind::Fragment(_) => None,
+        }
+    }
+}
@@ -0,0 +1,142 @@
+//! Content drawn by the host into the laid-out box of a node.
+
+use std::ffi::{c_char, CString};
+
+use serde::Deserialize;
+use takumi::{image::RgbaImage, layout::style::tw::TailwindValues};
+
+use super::Style;
+
+/// A node whose content box is drawn by the host's paint callback, by name.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PaintNode {
+    /// Default style presets from HTML element type (lowest priority)
+    pub preset: Option<takumi::layout::style::Style>,
+    /// The styling properties for this paint node
+    pub style: Option<Style>,
+    /// The name the paint callback draws this node by
+    pub name: String,
+    /// The tailwind properties for this paint node
+    pub tw: Option<TailwindValues>,
+}
+
+/// Where a paint node is drawn in the rendered image.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct PaintArea {
+    /// The distance of the content box from the left edge of the image in pixels.
+    pub x: f32,
+    /// The distance of the content box from the top edge of the image in pixels.
+    pub y: f32,
+    /// The width of the content box and the pixel buffer in pixels.
+    pub width: u32,
+    /// The height of the content box and the pixel buffer in pixels.
+    pub height: u32,
+    /// The device pixel ratio the image is rendered at.
+    pub device_pixel_ratio: f32,
+}
+
+/// A host function drawing the paint node named `name` into `pixels`.
+///
+/// The buffer holds `area.width * area.height` RGBA pixels with straight alpha, row by
+/// row, and starts out transparent. It is only valid for the duration of the call.
+pub type PaintCallback = unsafe extern "C" fn(name: *const c_char, area: PaintArea, pixels: *mut u8, len: usize);
+
+static mut PAINT_CALLBACK: Option<PaintCallback> = None;
+
+impl PaintNode {
+    /// Lowers the paint node into an empty takumi container, which is drawn by the host
+    /// once it is laid out.
+    pub(crate) fn into_takumi(self) -> takumi::layout::node::NodeKind {
+        takumi::layout::node::ContainerNode {
+            preset: self.preset,
+            style: self.style.map(|style| style.base),
+            children: None,
+            tw: self.tw,
+        }
+        .into()
+    }
+}
+
+/// Has the paint callback draw the paint node named `name` into a bitmap of its area.
+pub(crate) fn paint(name: &str, area: PaintArea) -> Result<RgbaImage, String> {
+    let Some(callback) = (unsafe { *std::ptr::addr_of!(PAINT_CALLBACK) }) else {
+        return Err(format!("paint node \"{name}\" cannot be drawn without a paint callback"));
+    };
+
+    let name = CString::new(name).map_err(|e| e.to_string())?;
+    let mut pixels = vec![0; area.width as usize * area.height as usize * 4];
+    unsafe { callback(name.as_ptr(), area, pixels.as_mut_ptr(), pixels.len()) };
+
+    RgbaImage::from_raw(area.width, area.height, pixels).ok_or_else(|| "paint bitmap has the wrong size".to_string())
+}
+
+/// Sets the function paint nodes are drawn with, or removes it when `callback` is null.
+///
+/// The callback is called on the rendering thread for every paint node with a non-empty
+/// content box, each time its tree is rendered, which includes calculating the buffer
+/// size. Rendering a tree with paint nodes fails while no callback is set.
+///
+/// # Safety
+///
+/// - `callback` must be null or a function that stays valid until it is replaced.
+/// - This function must not be called concurrently with rendering.
+#[no_mangle]
+pub unsafe extern "C" fn set_paint_callback(callback: Option<PaintCallback>) {
+    unsafe { PAINT_CALLBACK = callback };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CStr;
+
+    use serde_json::json;
+    use takumi::{layout::Viewport, resources::image::ImageSource, GlobalContext};
+
+    use super::*;
+
+    /// Fills the area of the paint node named "chart" with its position and width.
+    unsafe extern "C" fn fill(name: *const c_char, area: PaintArea, pixels: *mut u8, len: usize) {
+        if unsafe { CStr::from_ptr(name) }.to_bytes() != b"chart" {
+            return;
+        }
+
+        let pixels = unsafe { std::slice::from_raw_parts_mut(pixels, len) };
+        for pixel in pixels.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&[area.x as u8, area.y as u8, area.width as u8, 255]);
+        }
+    }
+
+    // The paint callback is global, so every case runs in this one test.
+    #[test]
+    fn paint_nodes_are_drawn_by_the_callback_into_their_content_box() {
+        let tree = json!({
+            "type": "container",
+            "style": { "padding": 4 },
+            "children": [{ "type": "paint", "name": "chart", "style": { "width": 20, "height": 10, "padding": 2 } }],
+        });
+        let prepare = || {
+            super::super::NodeKind::from_value(&tree)
+                .unwrap()
+                .prepare(Viewport::new(Some(100), Some(100)), &GlobalContext::default())
+        };
+
+        unsafe { set_paint_callback(Some(fill)) };
+        let painted = prepare();
+        unsafe { set_paint_callback(None) };
+
+        let (_, resources) = painted.unwrap();
+        let bitmaps: Vec<_> = resources.values().collect();
+        let [bitmap] = bitmaps.as_slice() else {
+            panic!("the paint node is not drawn once");
+        };
+        let ImageSource::Bitmap(bitmap) = &***bitmap else {
+            panic!("paint nodes are drawn into bitmaps");
+        };
+
+        assert_eq!(bitmap.dimensions(), (16, 6));
+        assert_eq!(bitmap.get_pixel(0, 0).0, [6, 6, 16, 255]);
+
+        assert_eq!(prepare().unwrap_err(), "paint node \"chart\" cannot be drawn without a paint callback");
+    }
+}
@@ -0,0 +1,224 @@
+//! Measuring how the text of a single paragraph lays out inside the whole tree.
+//!
+//! A paragraph is a text node, or a container of inline text and image nodes such as a
+//! lowered text node with spans. It is replaced by a probe: a container carrying the node's own
+//! style (so it lays out as the same box), whose content box is filled by a block holding
+//! the text as inline runs. takumi only reports text runs for inline layouts, which
+//! the paragraph is, so its runs describe the lines the text node would draw.
+
+use takumi::{
+    layout::{
+        node::{ContainerNode, NodeKind, TextNode},
+        style::{CssValue, Display, Length, Style},
+        Viewport,
+    },
+    rendering::{measure_layout, MeasuredNode, MeasuredTextRun, RenderOptionsBuilder},
+    GlobalContext,
+};
+
+/// The measured layout of a text node's content.
+pub(crate) struct MeasuredText {
+    /// The content box of the text node.
+    pub(crate) content: MeasuredNode,
+    /// The text laid out at the top of the content box, which may overflow it.
+    pub(crate) paragraph: MeasuredNode,
+    /// The number of visible characters in the text of the paragraph.
+    visible_chars: usize,
+}
+
+impl MeasuredText {
+    /// Returns whether every line of the text was laid out. takumi stops breaking lines
+    /// at the bottom of the viewport and drops the rest of the text.
+    pub(crate) fn is_complete(&self) -> bool {
+        let laid_out: usize = self.paragraph.runs.iter().map(|run| visible_chars(&run.text)).sum();
+        laid_out >= self.visible_chars
+    }
+
+    /// Groups the text runs into lines, starting a new line whenever a run sits below the last one.
+    pub(crate) fn lines(&self) -> Vec<Vec<&MeasuredTextRun>> {
+        let mut lines: Vec<Vec<&MeasuredTextRun>> = Vec::new();
+
+        for run in &self.paragraph.runs {
+            match lines.last_mut() {
+                Some(line) if run.y <= line[0].y + line[0].height / 2.0 => line.push(run),
+                _ => lines.push(vec![run]),
+            }
+        }
+
+        lines
+    }
+}
+
+/// Measures the paragraph at `path` in the lowered tree.
+///
+/// Returns `None` when the node is not a paragraph once lowered, e.g. in a vertical writing
+/// mode, or cannot be found in the measured tree, e.g. when it is laid out inline.
+pub(crate) fn measure_text(
+    tree: &NodeKind,
+    path: &[usize],
+    viewport: Viewport,
+    global: &GlobalContext,
+) -> Result<Option<MeasuredText>, String> {
+    let mut tree = tree.clone();
+    let node = node_at(&mut tree, path);
+    let Some(runs) = paragraph_runs(node) else {
+        return Ok(None);
+    };
+    let visible_chars = runs.iter().map(|run| visible_chars(&run.text)).sum();
+    *node = create_probe(node.clone());
+
+    let options = RenderOptionsBuilder::default()
+        .viewport(viewport)
+        .node(tree)
+        .global(global)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let measured = measure_layout(options).map_err(|e| e.to_string())?;
+
+    let Some(content) = measured_node_at(&measured, path).and_then(|outer| outer.children.first()) else {
+        return Ok(None);
+    };
+    let Some(paragraph) = content.children.first() else {
+        return Ok(None);
+    };
+
+    Ok(Some(MeasuredText {
+        content: content.clone(),
+        paragraph: paragraph.clone(),
+        visible_chars,
+    }))
+}
+
+/// Counts the characters of a text that are not whitespace, which line breaking may drop.
+fn visible_chars(text: &str) -> usize {
+    text.chars().filter(|c| !c.is_whitespace()).count()
+}
+
+/// Returns the node at `path`, a list of child indices from the root.
+pub(crate) fn node_at<'n>(root: &'n mut NodeKind, path: &[usize]) -> &'n mut NodeKind {
+    let mut node = root;
+    for index in path {
+        node = match node {
+            NodeKind::Container(container) => {
+                &mut container.children.as_deref_mut().expect("probe path points into a container")[*index]
+            }
+            _ => unreachable!("probe path points into a container"),
+        };
+    }
+    node
+}
+
+/// Returns the runs of text of a paragraph, or `None` if the node is not one.
+pub(crate) fn paragraph_runs(node: &NodeKind) -> Option<Vec<&TextNode>> {
+    match node {
+        NodeKind::Text(text) => Some(vec![text]),
+        NodeKind::Container(container) if is_paragraph(container) => Some(
+            container
+                .children
+                .iter()
+                .flatten()
+                .filter_map(|child| match child {
+                    NodeKind::Text(text) => Some(text),
+                    _ => None,
+                })
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+/// Returns the runs of text of a paragraph mutably, or `None` if the node is not one.
+pub(crate) fn paragraph_runs_mut(node: &mut NodeKind) -> Option<Vec<&mut TextNode>> {
+    match node {
+        NodeKind::Text(text) => Some(vec![text]),
+        NodeKind::Container(container) if is_paragraph(container) => Some(
+            container
+                .children
+                .iter_mut()
+                .flatten()
+                .filter_map(|child| match child {
+                    NodeKind::Text(text) => Some(text),
+                    _ => None,
+                })
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+fn is_paragraph(container: &ContainerNode<NodeKind>) -> bool {
+    let is_inline = |style: &Option<Style>| style.as_ref().is_some_and(|style| style.display == CssValue::Value(Display::Inline));
+
+    container.children.as_deref().is_some_and(|children| {
+        !children.is_empty()
+            && children.iter().all(|child| match child {
+                NodeKind::Text(text) => is_inline(&text.style),
+                NodeKind::Image(image) => is_inline(&image.style),
+                NodeKind::Container(_) => false,
+            })
+    })
+}
+
+fn measured_node_at<'m>(root: &'m MeasuredNode, path: &[usize]) -> Option<&'m MeasuredNode> {
+    path.iter().try_fold(root, |node, index| node.children.get(*index))
+}
+
+fn create_probe(node: NodeKind) -> NodeKind {
+    let content = Style {
+        display: CssValue::Value(Display::Block),
+        width: CssValue::Value(Length::Percentage(100.0)),
+        height: CssValue::Value(Length::Percentage(100.0)),
+        ..Default::default()
+    };
+
+    let paragraph = Style {
+        display: CssValue::Value(Display::Block),
+        ..Default::default()
+    };
+
+    let (preset, style, runs, tw) = match node {
+        NodeKind::Text(text) => {
+            let bare_text = TextNode {
+                preset: None,
+                style: Some(Style {
+                    display: CssValue::Value(Display::Inline),
+                    ..Default::default()
+                }),
+                text: text.text,
+                tw: None,
+            };
+
+            (text.preset, text.style, Box::new([bare_text.into()]) as Box<[NodeKind]>, text.tw)
+        }
+        NodeKind::Container(container) => (
+            container.preset,
+            container.style,
+            container.children.unwrap_or_default(),
+            container.tw,
+        ),
+        NodeKind::Image(_) => unreachable!("probed node is a paragraph"),
+    };
+
+    let paragraph = ContainerNode {
+        preset: None,
+        style: Some(paragraph),
+        children: Some(runs),
+        tw: None,
+    };
+
+    let content = ContainerNode {
+        preset: None,
+        style: Some(content),
+        children: Some(Box::new([paragraph.into()])),
+        tw: None,
+    };
+
+    ContainerNode {
+        preset,
+        style,
+        children: Some(Box::new([content.into()])),
+        tw,
+    }
+    .into()
+}
@@ -0,0 +1,378 @@
+//! React element trees, the way `@vercel/og` (Satori) templates serialize them, converted
+//! into nodes.
+//!
+//! An element is `{ "type": "div", "props": { "style": {...}, "children": [...] } }`. `img`
+//! elements become images, and every other element a container, like in Satori, unless all
+//! of its children are text or inline elements like `span` and `b`: then it becomes a text
+//! node with a span per inline element. Spans are not nested, so the styles of nested inline
+//! elements are merged from the outermost one in.
+
+use serde_json::{Map, Value};
+use takumi::layout::{node::ImageNode, style::tw::TailwindValues};
+
+use super::{ContainerNode, NodeKind, Style, TextNode, TextSpan};
+
+/// The elements set inline inside text, with the style each one starts out with.
+const INLINE_ELEMENTS: &[(&str, Option<(&str, &str)>)] = &[
+    ("span", None),
+    ("a", None),
+    ("b", Some(("fontWeight", "bold"))),
+    ("strong", Some(("fontWeight", "bold"))),
+    ("i", Some(("fontStyle", "italic"))),
+    ("em", Some(("fontStyle", "italic"))),
+    ("code", Some(("fontFamily", "monospace"))),
+    ("small", Some(("fontSize", "0.83em"))),
+];
+
+/// The part of an element that matters for layout.
+struct Element<'v> {
+    kind: &'v str,
+    props: &'v Map<String, Value>,
+}
+
+/// A child of an element: a run of text or another element.
+enum Child<'v> {
+    Text(String),
+    Element(Element<'v>),
+}
+
+/// Returns whether a JSON value is a serialized React element rather than a node.
+pub(crate) fn is_element(value: &Value) -> bool {
+    value.get("type").is_some_and(Value::is_string) && value.get("props").is_some_and(Value::is_object)
+}
+
+/// Converts a serialized React element into a node.
+pub(crate) fn to_node(value: &Value) -> Result<NodeKind, String> {
+    node(&element(value)?)
+}
+
+fn element(value: &Value) -> Result<Element<'_>, String> {
+    let Some(kind) = value.get("type").and_then(Value::as_str) else {
+        return Err(format!(
+            "invalid React element {value}, expected a \"type\" naming an HTML element"
+        ));
+    };
+    let Some(props) = value.get("props").and_then(Value::as_object) else {
+        return Err(format!(
+            "invalid React element <{kind}>, expected its \"props\" to be an object"
+        ));
+    };
+
+    Ok(Element { kind, props })
+}
+
+fn node(element: &Element) -> Result<NodeKind, String> {
+    if element.kind == "img" {
+        return image(element).map(NodeKind::Image);
+    }
+
+    let children = children(element.props.get("children"))?;
+
+    if !children.is_empty() && children.iter().all(is_inline) {
+        let mut spans = Vec::new();
+        for child in &children {
+            inline(child, &Map::new(), "", &mut spans)?;
+        }
+
+        // Text without inline elements stays a single run.
+        let (text, spans) = match spans.iter().all(|span| span.style.is_none() && span.tw.is_none()) {
+            true => (spans.into_iter().map(|span| span.text).collect(), None),
+            false => (String::new(), Some(spans.into_boxed_slice())),
+        };
+
+        return Ok(NodeKind::Text(TextNode {
+            preset: inline_preset(element.kind)?,
+            style: style(element)?,
+            text,
+            spans,
+            tw: tw(element.props)?,
+            fit: None,
+            lang: lang(element.props),
+        }));
+    }
+
+    let children = children
+        .into_iter()
+        .map(|child| match child {
+            Child::Text(text) => Ok(NodeKind::Text(TextNode {
+                preset: None,
+                style: None,
+                text,
+                spans: None,
+                tw: None,
+                fit: None,
+                lang: None,
+            })),
+            Child::Element(element) => node(&element),
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    Ok(NodeKind::Container(ContainerNode {
+        preset: None,
+        style: style(element)?,
+        children: Some(children.into_boxed_slice()),
+        tw: tw(element.props)?,
+        lang: lang(element.props),
+        fragments: None,
+    }))
+}
+
+/// Flattens the `children` prop into text and elements. Like React, it skips `null` and
+/// booleans and joins adjacent text.
+fn children(value: Option<&Value>) -> Result<Vec<Child<'_>>, String> {
+    fn collect<'v>(value: &'v Value, children: &mut Vec<Child<'v>>) -> Result<(), String> {
+        let text = match value {
+            Value::Null | Value::Bool(_) => return Ok(()),
+            Value::String(text) => text.clone(),
+            Value::Number(number) => number.to_string(),
+            Value::Array(values) => return values.iter().try_for_each(|value| collect(value, children)),
+            Value::Object(_) => {
+                children.push(Child::Element(element(value)?));
+                return Ok(());
+            }
+        };
+
+        match children.last_mut() {
+            Some(Child::Text(last)) => last.push_str(&text),
+            _ => children.push(Child::Text(text)),
+        }
+        Ok(())
+    }
+
+    let mut children = Vec::new();
+    if let Some(value) = value {
+        collect(value, &mut children)?;
+    }
+    Ok(children)
+}
+
+/// Returns whether a child can be set as part of a run of text.
+fn is_inline(child: &Child) -> bool {
+    match child {
+        Child::Text(_) => true,
+        Child::Element(element) => {
+            INLINE_ELEMENTS.iter().any(|(kind, _)| *kind == element.kind)
+                && children(element.props.get("children")).is_ok_and(|children| children.iter().all(is_inline))
+        }
+    }
+}
+
+/// Adds the text inside an inline child to `spans`, styled by the elements around it.
+fn inline(
+    child: &Child,
+    parent_style: &Map<String, Value>,
+    parent_tw: &str,
+    spans: &mut Vec<TextSpan>,
+) -> Result<(), String> {
+    let element = match child {
+        Child::Text(text) => {
+            let style = match parent_style.is_empty() {
+                true => None,
+                false => Some(parse_style(parent_style.clone())?),
+            };
+            let tw = match parent_tw.trim().is_empty() {
+                true => None,
+                false => Some(parent_tw.parse()?),
+            };
+
+            spans.push(TextSpan {
+                style,
+                text: text.clone(),
+                image: None,
+                tw,
+            });
+            return Ok(());
+        }
+        Child::Element(element) => element,
+    };
+
+    let mut declarations = parent_style.clone();
+    if let Some((_, Some((property, value)))) = INLINE_ELEMENTS.iter().find(|(kind, _)| *kind == element.kind) {
+        declarations.insert(property.to_string(), Value::String(value.to_string()));
+    }
+    // The element's own style is checked on its own, so that an invalid one names it.
+    style(element)?;
+    if let Some(Value::Object(own)) = element.props.get("style") {
+        declarations.extend(own.clone());
+    }
+
+    let tw = match element.props.get("tw").and_then(Value::as_str) {
+        Some(classes) => format!("{parent_tw} {classes}"),
+        None => parent_tw.to_owned(),
+    };
+
+    for child in children(element.props.get("children"))? {
+        inline(&child, &declarations, &tw, spans)?;
+    }
+    Ok(())
+}
+
+/// The style an inline element set as a text node of its own starts out with.
+fn inline_preset(kind: &str) -> Result<Option<takumi::layout::style::Style>, String> {
+    match INLINE_ELEMENTS.iter().find(|(inline_kind, _)| *inline_kind == kind) {
+        Some((_, Some((property, value)))) => {
+            let declarations = Map::from_iter([(property.to_string(), Value::String(value.to_string()))]);
+            parse_style(declarations).map(|style| Some(style.base))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn image(element: &Element) -> Result<ImageNode, String> {
+    let Some(src) = element.props.get("src").and_then(Value::as_str) else {
+        return Err("<img> needs a src prop".to_string());
+    };
+    let dimension = |name: &str| match element.props.get(name) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::Number(number)) => Ok(number.as_f64().map(|number| number as f32)),
+        Some(Value::String(value)) => value
+            .trim_end_matches("px")
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("invalid {name} \"{value}\" of <img>, expected a number of pixels")),
+        Some(value) => Err(format!("invalid {name} {value} of <img>, expected a number of pixels")),
+    };
+
+    Ok(ImageNode {
+        preset: None,
+        style: style(element)?.map(|style| style.base),
+        src: src.into(),
+        width: dimension("width")?,
+        height: dimension("height")?,
+        tw: tw(element.props)?,
+    })
+}
+
+fn style(element: &Element) -> Result<Option<Style>, String> {
+    match element.props.get("style") {
+        Some(Value::Object(declarations)) => parse_style(declarations.clone())
+            .map(Some)
+            .map_err(|e| format!("invalid style of <{}>: {e}", element.kind)),
+        Some(Value::Null) | None => Ok(None),
+        Some(value) => Err(format!(
+            "invalid style of <{}>: {value}, expected an object",
+            element.kind
+        )),
+    }
+}
+
+fn parse_style(declarations: Map<String, Value>) -> Result<Style, String> {
+    serde_json::from_value(Value::Object(declarations)).map_err(|e| e.to_string())
+}
+
+fn tw(props: &Map<String, Value>) -> Result<Option<TailwindValues>, String> {
+    match props.get("tw").and_then(Value::as_str) {
+        Some(classes) => classes.parse().map(Some),
+        None => Ok(None),
+    }
+}
+
+fn lang(props: &Map<String, Value>) -> Option<String> {
+    props.get("lang").and_then(Value::as_str).map(str::to_owned)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use takumi::layout::style::{CssValue, FontStyle, FontWeight};
+
+    use super::*;
+
+    #[test]
+    fn only_elements_with_props_are_react_elements() {
+        assert!(is_element(&json!({ "type": "div", "props": {} })));
+        assert!(!is_element(&json!({ "type": "container", "children": [] })));
+    }
+
+    #[test]
+    fn text_children_join_into_a_single_run() {
+        let node = to_node(&json!({ "type": "p", "props": { "children": ["Count: ", 3, null, false, "!"] } })).unwrap();
+
+        let NodeKind::Text(text) = node else {
+            panic!("an element of text is not a text node");
+        };
+        assert_eq!(text.text, "Count: 3!");
+        assert!(text.spans.is_none());
+    }
+
+    #[test]
+    fn inline_children_become_spans_with_merged_styles() {
+        let node = to_node(&json!({
+            "type": "div",
+            "props": {
+                "children": [
+                    "Hello ",
+                    {
+                        "type": "b",
+                        "props": {
+                            "tw": "underline",
+                            "children": [
+                                "big ",
+                                { "type": "i", "props": { "style": { "fontWeight": 300 }, "children": "world" } },
+                            ],
+                        },
+                    },
+                ],
+            },
+        }))
+        .unwrap();
+
+        let NodeKind::Text(text) = node else {
+            panic!("an element of inline children is not a text node");
+        };
+        let [hello, big, world] = text.spans.as_deref().unwrap() else {
+            panic!("the inline children are not three spans");
+        };
+
+        assert_eq!((hello.text.as_str(), big.text.as_str(), world.text.as_str()), ("Hello ", "big ", "world"));
+        assert!(hello.style.is_none() && hello.tw.is_none());
+
+        let big_style = &big.style.as_ref().unwrap().base;
+        assert_eq!(big_style.font_weight, CssValue::Value(FontWeight::from(700.0)));
+        assert!(big.tw.is_some());
+
+        let world_style = &world.style.as_ref().unwrap().base;
+        assert_eq!(world_style.font_weight, CssValue::Value(FontWeight::from(300.0)));
+        assert_eq!(world_style.font_style, CssValue::Value(FontStyle::italic()));
+        assert!(world.tw.is_some());
+    }
+
+    #[test]
+    fn block_children_become_a_container() {
+        let node = to_node(&json!({
+            "type": "div",
+            "props": {
+                "children": [
+                    "Caption",
+                    { "type": "img", "props": { "src": "photo.png", "width": "64px", "height": 48 } },
+                ],
+            },
+        }))
+        .unwrap();
+
+        let NodeKind::Container(container) = node else {
+            panic!("an element of block children is not a container");
+        };
+        let [NodeKind::Text(caption), NodeKind::Image(image)] = container.children.as_deref().unwrap() else {
+            panic!("the children are not a text node and an image");
+        };
+
+        assert_eq!(caption.text, "Caption");
+        assert_eq!((&*image.src, image.width, image.height), ("photo.png", Some(64.0), Some(48.0)));
+    }
+
+    #[test]
+    fn invalid_elements_fail() {
+        let error = |element| to_node(&element).unwrap_err();
+
+        assert_eq!(error(json!({ "type": "img", "props": {} })), "<img> needs a src prop");
+        assert_eq!(
+            error(json!({ "type": "p", "props": { "children": { "type": "em", "props": { "style": 1 } } } })),
+            "invalid style of <em>: 1, expected an object"
+        );
+        assert_eq!(
+            error(json!({ "type": "div", "props": { "children": [{ "type": "div" }] } })),
+            "invalid React element <div>, expected its \"props\" to be an object"
+        );
+    }
+}
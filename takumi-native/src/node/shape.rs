@@ -0,0 +1,428 @@
+//! Vector shapes drawn in the local coordinate system of a node.
+
+use std::fmt::Write;
+
+use serde::Deserialize;
+use takumi::layout::{style::tw::TailwindValues, Viewport};
+
+use super::{cascade::Declarations, InheritedStyle, Style};
+
+/// A node that draws vector shapes, in pixels from the top left corner of its content box.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ShapeNode {
+    /// Default style presets from HTML element type (lowest priority)
+    pub preset: Option<takumi::layout::style::Style>,
+    /// The styling properties for this shape node
+    pub style: Option<Style>,
+    /// The shapes to draw, in painting order
+    pub shapes: Box<[Shape]>,
+    /// The gradients shapes can paint with, referenced as `url(#id)`
+    pub gradients: Option<Box<[GradientKind]>>,
+    /// The tailwind properties for this shape node
+    pub tw: Option<TailwindValues>,
+}
+
+/// A shape with its paint. A shape draws nothing until it has a fill or a stroke.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Shape {
+    /// The outline of the shape
+    pub geometry: GeometryKind,
+    /// The color or `url(#id)` gradient the shape is filled with
+    pub fill: Option<String>,
+    /// The color or `url(#id)` gradient the outline is stroked with
+    pub stroke: Option<String>,
+    /// The width of the stroke in pixels, 1 by default
+    pub stroke_width: Option<f32>,
+    /// The lengths of alternating dashes and gaps of the stroke
+    pub stroke_dasharray: Option<Box<[f32]>>,
+    /// How far into the dash pattern the stroke starts
+    pub stroke_dashoffset: Option<f32>,
+    /// The shape of the ends of open strokes: `butt`, `round` or `square`
+    pub stroke_linecap: Option<String>,
+    /// The shape of the corners of strokes: `miter`, `round` or `bevel`
+    pub stroke_linejoin: Option<String>,
+    /// The opacity of the whole shape, from 0 to 1
+    pub opacity: Option<f32>,
+}
+
+/// The outline of a shape.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum GeometryKind {
+    /// A straight line between two points.
+    Line(LineGeometry),
+    /// Straight lines through a list of points.
+    Polyline(PolylineGeometry),
+    /// A rectangle, optionally with rounded corners.
+    Rect(RectGeometry),
+    /// A circle around a center point.
+    Circle(CircleGeometry),
+    /// A part of the outline of a circle.
+    Arc(ArcGeometry),
+    /// An outline given as SVG path data.
+    Path(PathGeometry),
+}
+
+/// A straight line between two points.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LineGeometry {
+    /// The x coordinate of the start of the line
+    pub x1: f32,
+    /// The y coordinate of the start of the line
+    pub y1: f32,
+    /// The x coordinate of the end of the line
+    pub x2: f32,
+    /// The y coordinate of the end of the line
+    pub y2: f32,
+}
+
+/// Straight lines through a list of points.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PolylineGeometry {
+    /// The points the lines run through
+    pub points: Box<[Point]>,
+    /// Whether a line joins the last point back to the first
+    #[serde(default)]
+    pub closed: bool,
+}
+
+/// A point in the coordinate system of a shape node.
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct Point {
+    /// The x coordinate, from the left of the content box
+    pub x: f32,
+    /// The y coordinate, from the top of the content box
+    pub y: f32,
+}
+
+/// A rectangle, optionally with rounded corners.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RectGeometry {
+    /// The x coordinate of the left edge
+    pub x: f32,
+    /// The y coordinate of the top edge
+    pub y: f32,
+    /// The width of the rectangle
+    pub width: f32,
+    /// The height of the rectangle
+    pub height: f32,
+    /// The radius of the rounded corners
+    pub radius: Option<f32>,
+}
+
+/// A circle around a center point.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CircleGeometry {
+    /// The x coordinate of the center
+    pub cx: f32,
+    /// The y coordinate of the center
+    pub cy: f32,
+    /// The radius of the circle
+    pub r: f32,
+}
+
+/// A part of the outline of a circle, running clockwise between two angles.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArcGeometry {
+    /// The x coordinate of the center of the circle
+    pub cx: f32,
+    /// The y coordinate of the center of the circle
+    pub cy: f32,
+    /// The radius of the circle
+    pub r: f32,
+    /// The angle the arc starts at, in degrees clockwise from the top
+    pub start_angle: f32,
+    /// The angle the arc ends at, in degrees clockwise from the top
+    pub end_angle: f32,
+}
+
+/// An outline given as SVG path data.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PathGeometry {
+    /// The path data, as in the `d` attribute of an SVG path
+    pub d: String,
+}
+
+/// A gradient shapes can paint with.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum GradientKind {
+    /// A gradient along a line between two points.
+    Linear(LinearGradient),
+    /// A gradient from a center point out to a circle around it.
+    Radial(RadialGradient),
+}
+
+/// A gradient along a line between two points.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LinearGradient {
+    /// The ID shapes reference the gradient by
+    pub id: String,
+    /// The x coordinate where the gradient starts
+    pub x1: f32,
+    /// The y coordinate where the gradient starts
+    pub y1: f32,
+    /// The x coordinate where the gradient ends
+    pub x2: f32,
+    /// The y coordinate where the gradient ends
+    pub y2: f32,
+    /// The colors along the line
+    pub stops: Box<[GradientStop]>,
+}
+
+/// A gradient from a center point out to a circle around it.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RadialGradient {
+    /// The ID shapes reference the gradient by
+    pub id: String,
+    /// The x coordinate of the center
+    pub cx: f32,
+    /// The y coordinate of the center
+    pub cy: f32,
+    /// The radius of the circle the gradient ends at
+    pub r: f32,
+    /// The colors from the center out
+    pub stops: Box<[GradientStop]>,
+}
+
+/// A color at a position of a gradient.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GradientStop {
+    /// The position of the color along the gradient, from 0 to 1
+    pub offset: f32,
+    /// The color at the offset
+    pub color: String,
+}
+
+impl ShapeNode {
+    /// Resolves the native properties of this shape node.
+    pub(crate) fn inherited_style(&self, parent: &InheritedStyle, viewport: Viewport) -> InheritedStyle {
+        let declared = Declarations::new(
+            self.preset.as_ref(),
+            self.tw.as_ref(),
+            self.style.as_ref().map(|style| &style.base),
+            viewport,
+        );
+
+        parent.inherit(None, self.style.as_ref().map(|style| &style.native), &declared)
+    }
+
+    /// Lowers the shape node into an empty container, which the shapes are drawn into once
+    /// it is laid out.
+    pub(crate) fn into_takumi(self) -> takumi::layout::node::NodeKind {
+        takumi::layout::node::ContainerNode {
+            preset: self.preset,
+            style: self.style.map(|style| style.base),
+            children: None,
+            tw: self.tw,
+        }
+        .into()
+    }
+
+    /// Writes the markup of the gradients and shapes, to be placed in a root `<svg>` tag.
+    pub(crate) fn write_content(&self) -> String {
+        let mut content = String::new();
+
+        if let Some(gradients) = self.gradients.as_deref().filter(|gradients| !gradients.is_empty()) {
+            content.push_str("<defs>");
+            for gradient in gradients {
+                write_gradient(&mut content, gradient);
+            }
+            content.push_str("</defs>");
+        }
+
+        for shape in &self.shapes {
+            write_shape(&mut content, shape);
+        }
+
+        content
+    }
+}
+
+fn write_shape(markup: &mut String, shape: &Shape) {
+    let _ = match &shape.geometry {
+        GeometryKind::Line(line) => write!(markup, r#"<line x1="{}" y1="{}" x2="{}" y2="{}""#, line.x1, line.y1, line.x2, line.y2),
+        GeometryKind::Polyline(polyline) => {
+            let points: Vec<String> = polyline.points.iter().map(|point| format!("{},{}", point.x, point.y)).collect();
+            let element = if polyline.closed { "polygon" } else { "polyline" };
+            write!(markup, r#"<{element} points="{}""#, points.join(" "))
+        }
+        GeometryKind::Rect(rect) => write!(
+            markup,
+            r#"<rect x="{}" y="{}" width="{}" height="{}" rx="{}""#,
+            rect.x,
+            rect.y,
+            rect.width,
+            rect.height,
+            rect.radius.unwrap_or(0.0)
+        ),
+        GeometryKind::Circle(circle) => write!(markup, r#"<circle cx="{}" cy="{}" r="{}""#, circle.cx, circle.cy, circle.r),
+        GeometryKind::Arc(arc) => write!(markup, r#"<path d="{}""#, arc_path(arc)),
+        GeometryKind::Path(path) => write!(markup, r#"<path d="{}""#, escape(&path.d)),
+    };
+
+    let _ = write!(markup, r#" fill="{}""#, escape(shape.fill.as_deref().unwrap_or("none")));
+    write_attribute(markup, "stroke", shape.stroke.as_deref());
+    write_attribute(markup, "stroke-width", shape.stroke_width);
+    write_attribute(markup, "stroke-dashoffset", shape.stroke_dashoffset);
+    write_attribute(markup, "stroke-linecap", shape.stroke_linecap.as_deref());
+    write_attribute(markup, "stroke-linejoin", shape.stroke_linejoin.as_deref());
+    write_attribute(markup, "opacity", shape.opacity);
+
+    if let Some(dashes) = shape.stroke_dasharray.as_deref().filter(|dashes| !dashes.is_empty()) {
+        let dashes: Vec<String> = dashes.iter().map(f32::to_string).collect();
+        let _ = write!(markup, r#" stroke-dasharray="{}""#, dashes.join(" "));
+    }
+
+    markup.push_str("/>");
+}
+
+fn write_gradient(markup: &mut String, gradient: &GradientKind) {
+    let (element, stops) = match gradient {
+        GradientKind::Linear(linear) => {
+            let _ = write!(
+                markup,
+                r#"<linearGradient id="{}" gradientUnits="userSpaceOnUse" x1="{}" y1="{}" x2="{}" y2="{}">"#,
+                escape(&linear.id),
+                linear.x1,
+                linear.y1,
+                linear.x2,
+                linear.y2
+            );
+            ("linearGradient", &linear.stops)
+        }
+        GradientKind::Radial(radial) => {
+            let _ = write!(
+                markup,
+                r#"<radialGradient id="{}" gradientUnits="userSpaceOnUse" cx="{}" cy="{}" r="{}">"#,
+                escape(&radial.id),
+                radial.cx,
+                radial.cy,
+                radial.r
+            );
+            ("radialGradient", &radial.stops)
+        }
+    };
+
+    for stop in stops {
+        let _ = write!(markup, r#"<stop offset="{}" stop-color="{}"/>"#, stop.offset, escape(&stop.color));
+    }
+
+    let _ = write!(markup, "</{element}>");
+}
+
+fn write_attribute(markup: &mut String, name: &str, value: Option<impl ToString>) {
+    if let Some(value) = value {
+        let _ = write!(markup, r#" {name}="{}""#, escape(&value.to_string()));
+    }
+}
+
+/// The path data of an arc, split in two halves when it is a full circle, which a single
+/// SVG arc cannot draw.
+fn arc_path(arc: &ArcGeometry) -> String {
+    let point = |angle: f32| {
+        let radians = angle.to_radians();
+        (arc.cx + arc.r * radians.sin(), arc.cy - arc.r * radians.cos())
+    };
+
+    let sweep = (arc.end_angle - arc.start_angle).clamp(-360.0, 360.0);
+    let sweep_flag = u8::from(sweep >= 0.0);
+    let r = arc.r;
+    let (x0, y0) = point(arc.start_angle);
+
+    if sweep.abs() >= 360.0 {
+        let (x1, y1) = point(arc.start_angle + 180.0);
+        return format!("M {x0} {y0} A {r} {r} 0 0 {sweep_flag} {x1} {y1} A {r} {r} 0 0 {sweep_flag} {x0} {y0}");
+    }
+
+    let (x1, y1) = point(arc.start_angle + sweep);
+    let large_arc = u8::from(sweep.abs() > 180.0);
+    format!("M {x0} {y0} A {r} {r} 0 {large_arc} {sweep_flag} {x1} {y1}")
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn shape_node(shapes: serde_json::Value, gradients: serde_json::Value) -> ShapeNode {
+        serde_json::from_value(json!({ "shapes": shapes, "gradients": gradients })).unwrap()
+    }
+
+    #[test]
+    fn shapes_are_written_in_painting_order() {
+        let node = shape_node(
+            json!([
+                {
+                    "geometry": { "type": "rect", "x": 0, "y": 0, "width": 20, "height": 10, "radius": 2 },
+                    "fill": "url(#fade)",
+                },
+                {
+                    "geometry": {
+                        "type": "polyline",
+                        "points": [{ "x": 0, "y": 0 }, { "x": 5, "y": 5 }],
+                        "closed": true,
+                    },
+                    "stroke": "red",
+                    "strokeWidth": 2,
+                    "strokeDasharray": [4, 2],
+                },
+            ]),
+            json!([{
+                "type": "linear",
+                "id": "fade",
+                "x1": 0, "y1": 0, "x2": 20, "y2": 0,
+                "stops": [{ "offset": 0, "color": "white" }, { "offset": 1, "color": "black" }],
+            }]),
+        );
+
+        assert_eq!(
+            node.write_content(),
+            concat!(
+                r#"<defs><linearGradient id="fade" gradientUnits="userSpaceOnUse" x1="0" y1="0" x2="20" y2="0">"#,
+                r#"<stop offset="0" stop-color="white"/><stop offset="1" stop-color="black"/></linearGradient></defs>"#,
+                r#"<rect x="0" y="0" width="20" height="10" rx="2" fill="url(#fade)"/>"#,
+                r#"<polygon points="0,0 5,5" fill="none" stroke="red" stroke-width="2" stroke-dasharray="4 2"/>"#,
+            )
+        );
+    }
+
+    #[test]
+    fn values_are_escaped() {
+        let node = shape_node(
+            json!([{ "geometry": { "type": "path", "d": "M 0 0\" onload=\"x" }, "fill": "<red>" }]),
+            json!(null),
+        );
+
+        assert_eq!(node.write_content(), r#"<path d="M 0 0&quot; onload=&quot;x" fill="&lt;red&gt;"/>"#);
+    }
+
+    #[test]
+    fn full_circle_arcs_are_split_in_halves() {
+        let arc = |start_angle, end_angle| ArcGeometry {
+            cx: 10.0,
+            cy: 10.0,
+            r: 10.0,
+            start_angle,
+            end_angle,
+        };
+
+        assert!(arc_path(&arc(0.0, 270.0)).starts_with("M 10 0 A 10 10 0 1 1 "));
+        assert!(arc_path(&arc(0.0, -90.0)).starts_with("M 10 0 A 10 10 0 0 0 "));
+
+        let circle = arc_path(&arc(0.0, 400.0));
+        assert_eq!(circle.matches(" A ").count(), 2, "{circle}");
+        assert!(circle.ends_with(" 10 0"), "{circle}");
+    }
+}
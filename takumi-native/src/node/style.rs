@@ -0,0 +1,168 @@
+use std::sync::Arc;
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use takumi::layout::style::{tw::TailwindProperty, Color, ColorInput, FontFamily, TextTransform, WhiteSpaceCollapse};
+
+use super::cascade::Declarations;
+
+/// Defines the style of a node, with the properties takumi handles itself and the ones
+/// resolved natively before layout.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Style {
+    /// The properties handled by takumi
+    #[serde(flatten)]
+    pub base: takumi::layout::style::Style,
+    /// The properties resolved natively
+    #[serde(flatten)]
+    pub native: NativeStyle,
+}
+
+impl Style {
+    /// Parses a single declaration on its own. Returns `None` for a property takumi does
+    /// not know, which parsing it along with other declarations would silently ignore.
+    pub(crate) fn parse_declaration(property: &str, value: Value) -> Result<Option<Style>, String> {
+        if !Style::is_property(property) {
+            return Ok(None);
+        }
+
+        let declaration = Map::from_iter([(property.to_owned(), value)]);
+        serde_json::from_value(Value::Object(declaration))
+            .map(Some)
+            .map_err(|e| e.to_string())
+    }
+}
+
+include!(concat!(env!("OUT_DIR"), "/style_properties.rs"));
+
+/// Style properties resolved natively before layout.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct NativeStyle {
+    /// How words are hyphenated when text wraps, inherited
+    pub hyphens: Option<Hyphens>,
+    /// The direction of text and of the inline axis of flex and grid layouts, inherited
+    pub direction: Option<Direction>,
+    /// Whether text lines run horizontally or vertically, inherited
+    pub writing_mode: Option<WritingMode>,
+    /// How characters are oriented in vertical lines, inherited
+    pub text_orientation: Option<TextOrientation>,
+}
+
+/// Controls how words are hyphenated when text wraps across lines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Hyphens {
+    /// Words are never hyphenated, even at soft hyphens.
+    None,
+    /// Words are only hyphenated at soft hyphens (U+00AD) in the text.
+    #[default]
+    Manual,
+    /// Words are hyphenated where the patterns of the node's `lang` allow.
+    Auto,
+}
+
+/// The direction of text and of the inline axis of a layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    /// Text and inline layouts run from left to right.
+    Ltr,
+    /// Text and inline layouts run from right to left.
+    Rtl,
+}
+
+/// Whether lines of text run horizontally or vertically, and in which order lines stack.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WritingMode {
+    /// Lines run from left to right and stack from top to bottom.
+    #[default]
+    HorizontalTb,
+    /// Lines run from top to bottom and stack from right to left.
+    VerticalRl,
+    /// Lines run from top to bottom and stack from left to right.
+    VerticalLr,
+}
+
+impl WritingMode {
+    pub(crate) fn is_vertical(self) -> bool {
+        self != WritingMode::HorizontalTb
+    }
+}
+
+/// How characters are oriented in vertical lines of text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextOrientation {
+    /// Characters of vertical scripts, like CJK, are upright; the rest are rotated sideways.
+    #[default]
+    Mixed,
+    /// Every character is upright.
+    Upright,
+    /// Every character is rotated sideways, as if the line were horizontal text turned 90 degrees.
+    Sideways,
+}
+
+/// Native style properties and attributes passed down from the ancestors of a node.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct InheritedStyle {
+    /// The language of the node, as a BCP 47 tag.
+    pub(crate) lang: Option<Arc<str>>,
+    pub(crate) hyphens: Hyphens,
+    /// The direction set on the node or an ancestor, or `None` to take it from the text.
+    pub(crate) direction: Option<Direction>,
+    pub(crate) writing_mode: WritingMode,
+    pub(crate) text_orientation: TextOrientation,
+    /// The text transform takumi resolves for the node, applied natively for a `lang`.
+    pub(crate) text_transform: TextTransform,
+    /// The font families takumi resolves for the node, extended natively for a `lang`.
+    pub(crate) font_family: Option<FontFamily>,
+    /// The whitespace collapsing takumi resolves for the node, applied natively across spans.
+    pub(crate) white_space_collapse: WhiteSpaceCollapse,
+    /// The text color takumi resolves for the node, or `None` for the default black.
+    pub(crate) color: Option<Color>,
+}
+
+impl InheritedStyle {
+    /// Resolves the inherited properties of a node from its own style and attributes.
+    pub(crate) fn inherit(&self, lang: Option<&str>, native: Option<&NativeStyle>, declared: &Declarations) -> InheritedStyle {
+        let text_transform = declared.declared(|style| &style.text_transform, |property| match property {
+            TailwindProperty::TextTransform(text_transform) => Some(*text_transform),
+            _ => None,
+        });
+        let font_family = declared.declared(|style| &style.font_family, |property| match property {
+            TailwindProperty::FontFamily(font_family) => Some(Some(font_family.clone())),
+            _ => None,
+        });
+
+        let white_space = declared.declared(|style| &style.white_space, |property| match property {
+            TailwindProperty::WhiteSpace(white_space) => Some(*white_space),
+            _ => None,
+        });
+        let white_space_collapse = declared
+            .declared(|style| &style.white_space_collapse, |_| None)
+            .flatten()
+            .or(white_space.map(|white_space| white_space.white_space_collapse));
+
+        let color = declared.declared(|style| &style.color, |property| match property {
+            TailwindProperty::Color(color) => Some(*color),
+            _ => None,
+        });
+
+        InheritedStyle {
+            lang: lang.map(Arc::from).or_else(|| self.lang.clone()),
+            hyphens: native.and_then(|native| native.hyphens).unwrap_or(self.hyphens),
+            direction: native.and_then(|native| native.direction).or(self.direction),
+            writing_mode: native.and_then(|native| native.writing_mode).unwrap_or(self.writing_mode),
+            text_orientation: native.and_then(|native| native.text_orientation).unwrap_or(self.text_orientation),
+            text_transform: text_transform.unwrap_or(self.text_transform),
+            font_family: font_family.unwrap_or_else(|| self.font_family.clone()),
+            white_space_collapse: white_space_collapse.unwrap_or(self.white_space_collapse),
+            color: match color {
+                Some(ColorInput::Value(color)) => Some(color),
+                _ => self.color,
+            },
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use serde::Deserialize;
+use takumi::layout::{
+    node::ImageNode,
+    style::{tw::TailwindValues, Color},
+    Viewport,
+};
+
+use super::{cascade::Declarations, InheritedStyle, Style};
+
+/// A node that draws SVG markup as vector graphics at its laid-out size.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SvgNode {
+    /// Default style presets from HTML element type (lowest priority)
+    pub preset: Option<takumi::layout::style::Style>,
+    /// The styling properties for this SVG node
+    pub style: Option<Style>,
+    /// The SVG markup, whose `currentColor` takes the node's `color`
+    pub svg: String,
+    /// The tailwind properties for this SVG node
+    pub tw: Option<TailwindValues>,
+}
+
+impl SvgNode {
+    /// Resolves the native properties of this SVG node.
+    pub(crate) fn inherited_style(&self, parent: &InheritedStyle, viewport: Viewport) -> InheritedStyle {
+        let declared = Declarations::new(
+            self.preset.as_ref(),
+            self.tw.as_ref(),
+            self.style.as_ref().map(|style| &style.base),
+            viewport,
+        );
+
+        parent.inherit(None, self.style.as_ref().map(|style| &style.native), &declared)
+    }
+
+    /// Lowers the SVG node into a takumi image node of its markup, which is drawn natively
+    /// once it is laid out.
+    pub(crate) fn into_takumi(self, parent: &InheritedStyle, viewport: Viewport) -> takumi::layout::node::NodeKind {
+        let color = self.inherited_style(parent, viewport).color.unwrap_or(Color::black());
+
+        ImageNode {
+            preset: self.preset,
+            style: self.style.map(|style| style.base),
+            src: normalize_markup(&self.svg, color).into(),
+            width: None,
+            height: None,
+            tw: self.tw,
+        }
+        .into()
+    }
+}
+
+/// Rewrites the markup into the plain form takumi recognizes as SVG, with `currentColor`
+/// replaced by `color`.
+///
+/// takumi only takes markup starting with the `<svg` tag and the SVG namespace written in
+/// double quotes, so the markup is parsed and written back, dropping XML declarations,
+/// comments and styles along the way. Markup that does not parse is passed on as-is and
+/// handled like any other broken image source.
+fn normalize_markup(svg: &str, color: Color) -> String {
+    let [red, green, blue, alpha] = color.0;
+    let options = usvg::Options {
+        style_sheet: Some(format!("svg {{ color: rgba({red}, {green}, {blue}, {}) }}", f32::from(alpha) / 255.0)),
+        ..Default::default()
+    };
+
+    match usvg::Tree::from_str(svg, &options) {
+        Ok(tree) => tree.to_string(&usvg::WriteOptions::default()),
+        Err(_) => svg.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markup_is_written_back_in_the_form_takumi_recognizes() {
+        let svg = r#"<?xml version="1.0"?>
+            <!-- an icon -->
+            <svg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 10 10'><rect width='10' height='10'/></svg>"#;
+
+        let normalized = normalize_markup(svg, Color::black());
+
+        assert!(normalized.starts_with("<svg "), "{normalized}");
+        assert!(normalized.contains(r#"xmlns="http://www.w3.org/2000/svg""#), "{normalized}");
+        assert!(!normalized.contains("an icon"));
+    }
+
+    #[test]
+    fn current_color_takes_the_color_of_the_node() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 10 10">
+            <rect width="10" height="10" fill="currentColor"/>
+        </svg>"#;
+
+        let normalized = normalize_markup(svg, Color([255, 0, 0, 255]));
+
+        assert!(normalized.contains(r##"fill="#ff0000""##), "{normalized}");
+        assert!(!normalized.contains("currentColor"));
+    }
+
+    #[test]
+    fn markup_that_does_not_parse_is_passed_on() {
+        assert_eq!(normalize_markup("<svg", Color::black()), "<svg");
+    }
+}
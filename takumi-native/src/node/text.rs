@@ -0,0 +1,432 @@
+use serde::Deserialize;
+use takumi::{
+    layout::{
+        node::ImageNode,
+        style::{
+            tw::{TailwindProperty, TailwindValues},
+            CssValue, Display, FontFamily, Length, TextTransform, WhiteSpaceCollapse,
+        },
+        Viewport,
+    },
+    parley::FontStack,
+};
+
+use crate::font;
+
+use super::{breaking, cascade::Declarations, direction, language, vertical, InheritedStyle, Style};
+
+/// A node that renders text content.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TextNode {
+    /// Default style presets from HTML element type (lowest priority)
+    pub preset: Option<takumi::layout::style::Style>,
+    /// The styling properties for this text node
+    pub style: Option<Style>,
+    /// The text content to be rendered, followed by the spans
+    #[serde(default)]
+    pub text: String,
+    /// Runs of text with their own style, wrapping together with the text as one paragraph
+    pub spans: Option<Box<[TextSpan]>>,
+    /// The tailwind properties for this text node
+    pub tw: Option<TailwindValues>,
+    /// Shrinks the font size until the text fits the laid-out box, for horizontal text laid out as a block
+    pub fit: Option<TextFit>,
+    /// The language of the text as a BCP 47 tag, used for case mapping and font fallback
+    pub lang: Option<String>,
+}
+
+/// A run of text inside a text node, styled on top of the text node's style.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TextSpan {
+    /// The styling properties for this span
+    pub style: Option<Style>,
+    /// The text content of this span
+    #[serde(default)]
+    pub text: String,
+    /// An image placed inline instead of the text, sitting on the baseline
+    pub image: Option<ImageNode>,
+    /// The tailwind properties for this span
+    pub tw: Option<TailwindValues>,
+}
+
+/// How a text node picks its font size to fit its box.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextFit {
+    /// The smallest font size in pixels to shrink to
+    pub min_font_size: f32,
+    /// The largest font size in pixels to try first
+    pub max_font_size: f32,
+    /// The maximum number of lines the text may wrap to
+    pub max_lines: Option<u32>,
+}
+
+impl TextNode {
+    /// Overrides the font size, taking precedence over the preset, tailwind and inline style.
+    pub(crate) fn set_font_size(&mut self, font_size: f32) {
+        self.style.get_or_insert_with(Style::default).base.font_size = CssValue::Value(Some(Length::Px(font_size)));
+    }
+
+    /// Resolves the native properties of this text node.
+    pub(crate) fn inherited_style(&self, parent: &InheritedStyle, viewport: Viewport) -> InheritedStyle {
+        let declared = Declarations::new(
+            self.preset.as_ref(),
+            self.tw.as_ref(),
+            self.style.as_ref().map(|style| &style.base),
+            viewport,
+        );
+
+        parent.inherit(self.lang.as_deref(), self.style.as_ref().map(|style| &style.native), &declared)
+    }
+
+    /// Lowers the text node into a takumi text node, or into a paragraph of inline text
+    /// nodes when it has spans.
+    pub(crate) fn into_takumi(self, parent: &InheritedStyle, viewport: Viewport) -> takumi::layout::node::NodeKind {
+        let inherited = self.inherited_style(parent, viewport);
+
+        let mut style = self.style.map(|style| style.base);
+        let text = localize(self.text, &mut style, &inherited);
+        let spans = self.spans.filter(|spans| !spans.is_empty());
+
+        if spans.is_none() && !inherited.writing_mode.is_vertical() {
+            return takumi::layout::node::TextNode {
+                preset: self.preset,
+                style,
+                text: mark_breaks(text, &inherited),
+                tw: self.tw,
+            }
+            .into();
+        }
+
+        let mut runs = Vec::new();
+
+        if !text.is_empty() || spans.is_none() {
+            runs.push(Run::Text(Box::new(TextRun {
+                text,
+                style: None,
+                tw: None,
+                inherited: inherited.clone(),
+            })));
+        }
+
+        for span in spans.into_iter().flat_map(<[TextSpan]>::into_vec) {
+            if let Some(image) = span.image {
+                runs.push(Run::Image(Box::new(image)));
+                continue;
+            }
+
+            let declared = Declarations::new(None, span.tw.as_ref(), span.style.as_ref().map(|style| &style.base), viewport);
+            // Spans are set in the writing mode of their text node.
+            let span_inherited = InheritedStyle {
+                writing_mode: inherited.writing_mode,
+                ..inherited.inherit(None, span.style.as_ref().map(|style| &style.native), &declared)
+            };
+
+            let mut style = span.style.map(|style| style.base);
+            runs.push(Run::Text(Box::new(TextRun {
+                text: localize(span.text, &mut style, &span_inherited),
+                style,
+                tw: span.tw,
+                inherited: span_inherited,
+            })));
+        }
+
+        let paragraph = takumi::layout::node::ContainerNode {
+            preset: self.preset,
+            style,
+            children: None,
+            tw: self.tw,
+        };
+
+        if inherited.writing_mode.is_vertical() {
+            let runs = runs.into_iter().map(Run::into_takumi).collect();
+            return vertical::lower_text(paragraph, runs, &inherited);
+        }
+
+        // Only a block of inline children is laid out as a single paragraph.
+        let mut style = paragraph.style.unwrap_or_default();
+        style.display = CssValue::Value(Display::Block);
+
+        // takumi collapses whitespace in every run on its own, trimming the spaces between them.
+        if inherited.white_space_collapse == WhiteSpaceCollapse::Collapse {
+            collapse_white_space(&mut runs);
+            style.white_space_collapse = CssValue::Value(Some(WhiteSpaceCollapse::Preserve));
+        }
+
+        takumi::layout::node::ContainerNode {
+            style: Some(style),
+            children: Some(runs.into_iter().map(Run::into_inline).collect()),
+            ..paragraph
+        }
+        .into()
+    }
+}
+
+/// A run of a paragraph: the text of a text node, or one of its spans.
+enum Run {
+    Text(Box<TextRun>),
+    Image(Box<ImageNode>),
+}
+
+/// A run of text of a paragraph.
+struct TextRun {
+    text: String,
+    style: Option<takumi::layout::style::Style>,
+    tw: Option<TailwindValues>,
+    inherited: InheritedStyle,
+}
+
+impl Run {
+    fn into_takumi(self) -> takumi::layout::node::NodeKind {
+        match self {
+            Run::Text(run) => takumi::layout::node::TextNode {
+                preset: None,
+                style: run.style,
+                text: run.text,
+                tw: run.tw,
+            }
+            .into(),
+            Run::Image(image) => (*image).into(),
+        }
+    }
+
+    /// Lowers the run into an inline node of a horizontal paragraph.
+    fn into_inline(self) -> takumi::layout::node::NodeKind {
+        match self {
+            Run::Text(run) => {
+                let mut style = run.style.unwrap_or_default();
+                style.display = CssValue::Value(Display::Inline);
+                name_fallback_fonts(&mut style, &run.inherited);
+
+                // takumi inherits a relative font size as declared and resolves it again
+                // against the paragraph's, so runs without a font size of their own take 1em.
+                let tw_font_size = run.tw.iter().flat_map(TailwindValues::iter).any(|value| {
+                    matches!(value.property, TailwindProperty::FontSize(_))
+                });
+                if matches!(style.font_size, CssValue::Unset) && !tw_font_size {
+                    style.font_size = CssValue::Value(Some(Length::Em(1.0)));
+                }
+
+                takumi::layout::node::TextNode {
+                    preset: None,
+                    style: Some(style),
+                    text: mark_breaks(run.text, &run.inherited),
+                    tw: run.tw,
+                }
+                .into()
+            }
+            Run::Image(mut image) => {
+                image.style.get_or_insert_with(Default::default).display = CssValue::Value(Display::Inline);
+                (*image).into()
+            }
+        }
+    }
+}
+
+/// Collapses whitespace the way takumi does, but across the runs of a paragraph: runs of
+/// whitespace become a single space and the paragraph is trimmed.
+fn collapse_white_space(runs: &mut [Run]) {
+    let mut last_was_space = true;
+
+    for run in runs.iter_mut() {
+        let Run::Text(run) = run else {
+            // Spaces around an inline image are kept.
+            last_was_space = false;
+            continue;
+        };
+
+        let mut collapsed = String::with_capacity(run.text.len());
+
+        for c in run.text.chars() {
+            if !c.is_whitespace() {
+                collapsed.push(c);
+                last_was_space = false;
+            } else if !last_was_space {
+                collapsed.push(' ');
+                last_was_space = true;
+            }
+        }
+
+        run.text = collapsed;
+    }
+
+    for run in runs.iter_mut().rev() {
+        let Run::Text(run) = run else {
+            break;
+        };
+
+        run.text.truncate(run.text.trim_end().len());
+
+        if !run.text.is_empty() {
+            break;
+        }
+    }
+}
+
+/// Applies the language-specific case mapping and font fallback to a run of text.
+fn localize(text: String, style: &mut Option<takumi::layout::style::Style>, inherited: &InheritedStyle) -> String {
+    let Some(lang) = inherited.lang.as_deref() else {
+        return text;
+    };
+
+    let mut text = text;
+
+    if inherited.text_transform != TextTransform::None {
+        if let Some(transformed) = language::transform_text(&text, inherited.text_transform, lang) {
+            text = transformed.into_owned();
+            style.get_or_insert_with(Default::default).text_transform = CssValue::Value(TextTransform::None);
+        }
+    }
+
+    if let Some(font_family) = language::font_family_with_fallback(inherited.font_family.as_ref(), lang) {
+        style.get_or_insert_with(Default::default).font_family = CssValue::Value(Some(font_family));
+    }
+
+    text
+}
+
+/// Inserts the break opportunities and direction mark of horizontal text.
+fn mark_breaks(text: String, inherited: &InheritedStyle) -> String {
+    let text = breaking::insert_break_opportunities(&text, inherited).into_owned();
+    direction::mark_text_direction(text, inherited.direction)
+}
+
+/// Appends the fallback families by name to the font families of a run.
+///
+/// The shaper caches the face it picked from a fallback family for the whole paragraph,
+/// so a run switching to another weight or style would keep using the face of the run
+/// before it. Named families pick their face again for every run.
+fn name_fallback_fonts(style: &mut takumi::layout::style::Style, inherited: &InheritedStyle) {
+    let font_family = match &style.font_family {
+        CssValue::Value(Some(font_family)) => font_family.clone(),
+        _ => inherited.font_family.clone().unwrap_or_default(),
+    };
+    let FontStack::Source(families) = FontStack::from(&font_family) else {
+        return;
+    };
+
+    let fallbacks = unsafe { font::fallback_family_names() };
+    if fallbacks.is_empty() {
+        return;
+    }
+
+    let fallbacks: Vec<String> = fallbacks.iter().map(|name| format!("\"{name}\"")).collect();
+    style.font_family = CssValue::Value(Some(FontFamily::from(format!("{families}, {}", fallbacks.join(", ")).as_str())));
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use takumi::layout::node::NodeKind;
+
+    use super::*;
+
+    fn lower(text: serde_json::Value) -> NodeKind {
+        let text: TextNode = serde_json::from_value(text).unwrap();
+        text.into_takumi(&InheritedStyle::default(), Viewport::new(Some(400), Some(200)))
+    }
+
+    /// The inline text runs of a lowered paragraph.
+    fn runs(paragraph: &NodeKind) -> Vec<&takumi::layout::node::TextNode> {
+        let NodeKind::Container(paragraph) = paragraph else {
+            panic!("text with spans is not lowered into a paragraph");
+        };
+
+        let style = paragraph.style.as_ref().unwrap();
+        assert!(matches!(style.display, CssValue::Value(Display::Block)));
+
+        paragraph
+            .children
+            .iter()
+            .flatten()
+            .filter_map(|child| match child {
+                NodeKind::Text(run) => Some(run),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn text_without_spans_stays_a_text_node() {
+        let lowered = lower(json!({ "text": "Hello", "spans": [] }));
+
+        assert!(matches!(lowered, NodeKind::Text(text) if text.text == "Hello"));
+    }
+
+    #[test]
+    fn spans_wrap_as_one_paragraph_of_inline_runs() {
+        let lowered = lower(json!({
+            "text": "Hello ",
+            "spans": [
+                { "text": "bold", "style": { "fontWeight": 700, "fontSize": 30 } },
+                { "text": " world" },
+            ],
+        }));
+
+        let runs = runs(&lowered);
+        let texts: Vec<_> = runs.iter().map(|run| run.text.as_str()).collect();
+        assert_eq!(texts, ["Hello ", "bold", " world"]);
+
+        for run in &runs {
+            assert!(matches!(run.style.as_ref().unwrap().display, CssValue::Value(Display::Inline)));
+        }
+
+        // Runs without a font size of their own take the paragraph's.
+        assert!(matches!(runs[0].style.as_ref().unwrap().font_size, CssValue::Value(Some(Length::Em(1.0)))));
+        assert!(matches!(runs[1].style.as_ref().unwrap().font_size, CssValue::Value(Some(Length::Px(30.0)))));
+    }
+
+    #[test]
+    fn white_space_collapses_across_runs() {
+        let lowered = lower(json!({
+            "text": "  one  ",
+            "spans": [{ "text": "  two " }, { "text": "   " }],
+        }));
+
+        let texts: Vec<_> = runs(&lowered).iter().map(|run| run.text.clone()).collect();
+        assert_eq!(texts, ["one ", "two", ""]);
+    }
+
+    #[test]
+    fn image_spans_are_placed_inline() {
+        let lowered = lower(json!({
+            "text": "Made with ",
+            "spans": [
+                { "image": { "src": "heart.png", "width": 16, "height": 16 } },
+                { "text": " by" },
+            ],
+        }));
+
+        let NodeKind::Container(paragraph) = &lowered else {
+            panic!("text with spans is not lowered into a paragraph");
+        };
+        let children = paragraph.children.as_deref().unwrap();
+        assert_eq!(children.len(), 3);
+
+        let NodeKind::Image(image) = &children[1] else {
+            panic!("image spans are not lowered into images");
+        };
+        assert_eq!(&*image.src, "heart.png");
+        assert!(matches!(image.style.as_ref().unwrap().display, CssValue::Value(Display::Inline)));
+
+        // Spaces around an inline image are kept.
+        let texts: Vec<_> = runs(&lowered).iter().map(|run| run.text.clone()).collect();
+        assert_eq!(texts, ["Made with ", " by"]);
+    }
+
+    #[test]
+    fn image_spans_become_cells_of_vertical_text() {
+        let lowered = lower(json!({
+            "text": "日本",
+            "style": { "writingMode": "vertical-rl" },
+            "spans": [{ "image": { "src": "flag.png", "width": 16, "height": 16 } }],
+        }));
+
+        let NodeKind::Container(vertical_box) = &lowered else {
+            panic!("vertical text is not lowered into a box");
+        };
+        let cells = vertical_box.children.as_deref().unwrap();
+        assert_eq!(cells.len(), 3);
+        assert!(matches!(&cells[2], NodeKind::Image(image) if &*image.src == "flag.png"));
+    }
+}
@@ -0,0 +1,440 @@
+//! Vertical writing modes.
+//!
+//! takumi only lays out text horizontally, so a text node in a vertical writing mode is
+//! lowered into a flex column of cells instead, which wraps into more columns stacking
+//! from right to left (`vertical-rl`) or from left to right (`vertical-lr`). Upright
+//! characters each get a cell one em tall and are shaped with the `vert` feature, which
+//! picks their vertical alternates. Sideways runs, e.g. Latin words in `mixed`
+//! orientation, get a cell they are drawn in rotated by 90 degrees.
+//!
+//! How large a cell is depends on its font, so the cells are lowered unsized in a single
+//! column, measured, and only then sized and wrapped into columns.
+//!
+//! Flex containers in a vertical writing mode turn their rows into columns and their
+//! columns into rows stacking in the same order as the lines.
+
+use icu_properties::{
+    props::{GeneralCategory, GeneralCategoryGroup, VerticalOrientation},
+    CodePointMapData,
+};
+use takumi::{
+    layout::{
+        node::{ContainerNode, NodeKind, TextNode},
+        style::{
+            tw::{TailwindProperty, TailwindValues},
+            AlignItems, Angle, CssValue, Display, FlexDirection, FlexGrow, FlexWrap,
+            JustifyContent, Length, LineHeight, Position, Style, TextAlign, WhiteSpace,
+        },
+        Viewport,
+    },
+    parley::FontFeature,
+    rendering::{measure_layout, MeasuredNode, RenderOptionsBuilder},
+    GlobalContext,
+};
+
+use super::{cascade::Declarations, probe, InheritedStyle, TextOrientation, WritingMode};
+
+const ZERO_WIDTH_JOINER: char = '\u{200D}';
+
+/// A text node lowered into a vertical text box, found by its path from the root.
+pub(crate) struct VerticalText {
+    pub(crate) path: Vec<usize>,
+    pub(crate) writing_mode: WritingMode,
+}
+
+/// The text of a single cell of a vertical text box.
+struct Cell {
+    text: String,
+    sideways: bool,
+}
+
+/// Finds every text node in a vertical writing mode in the tree.
+pub(crate) fn collect_texts(root: &super::NodeKind, viewport: Viewport) -> Vec<VerticalText> {
+    fn collect(
+        node: &super::NodeKind,
+        parent: &InheritedStyle,
+        viewport: Viewport,
+        path: &mut Vec<usize>,
+        texts: &mut Vec<VerticalText>,
+    ) {
+        match node {
+            super::NodeKind::Container(container) => {
+                let inherited = container.inherited_style(parent, viewport);
+
+                for (index, child) in container.children.iter().flatten().enumerate() {
+                    path.push(index);
+                    collect(child, &inherited, viewport, path, texts);
+                    path.pop();
+                }
+            }
+            super::NodeKind::Text(text) => {
+                let writing_mode = text.inherited_style(parent, viewport).writing_mode;
+
+                if writing_mode.is_vertical() {
+                    texts.push(VerticalText {
+                        path: path.clone(),
+                        writing_mode,
+                    });
+                }
+            }
+            super::NodeKind::Image(_)
+            | super::NodeKind::Svg(_)
+            | super::NodeKind::Shape(_)
+            | super::NodeKind::QrCode(_)
+            | super::NodeKind::Barcode(_)
+            | super::NodeKind::Paint(_)
+            | super::NodeKind::CodeBlock(_)
+            | super::NodeKind::Markdown(_)
+            | super::NodeKind::Html(_)
+            | super::NodeKind::Fragment(_) => {}
+        }
+    }
+
+    let mut texts = Vec::new();
+    collect(root, &InheritedStyle::default(), viewport, &mut Vec::new(), &mut texts);
+    texts
+}
+
+/// Lowers the runs of a text node in a vertical writing mode into a box of unsized cells.
+///
+/// Runs that are not text, like inline images, become cells of their own.
+pub(crate) fn lower_text(paragraph: ContainerNode<NodeKind>, runs: Vec<NodeKind>, inherited: &InheritedStyle) -> NodeKind {
+    let mut style = paragraph.style.unwrap_or_default();
+    style.display = CssValue::Value(Display::Flex);
+    style.flex_direction = CssValue::Value(FlexDirection::Column);
+    style.flex_wrap = CssValue::Value(FlexWrap::NoWrap);
+    style.align_items = CssValue::Value(AlignItems::FlexStart);
+    style.align_content = CssValue::Value(JustifyContent::FlexStart);
+
+    let mut children = Vec::new();
+
+    for run in runs {
+        let run = match run {
+            NodeKind::Text(run) => run,
+            NodeKind::Image(mut image) => {
+                let style = image.style.get_or_insert_with(Style::default);
+                style.flex_shrink = CssValue::Value(Some(FlexGrow(0.0)));
+                style.align_self = CssValue::Value(AlignItems::Center);
+                children.push(image.into());
+                continue;
+            }
+            NodeKind::Container(_) => {
+                children.push(run);
+                continue;
+            }
+        };
+
+        let features = run.style.as_ref().filter(|style| matches!(style.font_feature_settings, CssValue::Value(Some(_))));
+        let vert = vert_feature(features.unwrap_or(&style));
+
+        for cell in split_cells(&run.text, inherited.text_orientation) {
+            children.push(lower_cell(cell, run.style.clone(), run.tw.clone(), &vert));
+        }
+    }
+
+    ContainerNode {
+        preset: paragraph.preset,
+        style: Some(style),
+        children: Some(children.into_boxed_slice()),
+        tw: paragraph.tw,
+    }
+    .into()
+}
+
+/// Sizes the cells of every vertical text box in the tree and wraps them into columns.
+pub(crate) fn size_cells(
+    tree: &mut NodeKind,
+    texts: &[VerticalText],
+    viewport: Viewport,
+    global: &GlobalContext,
+) -> Result<(), String> {
+    if texts.is_empty() {
+        return Ok(());
+    }
+
+    let options = RenderOptionsBuilder::default()
+        .viewport(viewport)
+        .node(tree.clone())
+        .global(global)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let measured = measure_layout(options).map_err(|e| e.to_string())?;
+
+    for text in texts {
+        let Some(measured_box) = text.path.iter().try_fold(&measured, |node, index| node.children.get(*index)) else {
+            continue;
+        };
+        let NodeKind::Container(vertical_box) = probe::node_at(tree, &text.path) else {
+            continue;
+        };
+
+        if let Some(style) = vertical_box.style.as_mut() {
+            style.flex_wrap = CssValue::Value(match text.writing_mode {
+                WritingMode::VerticalLr => FlexWrap::Wrap,
+                _ => FlexWrap::WrapReverse,
+            });
+        }
+
+        for (cell, measured_cell) in vertical_box.children.iter_mut().flatten().zip(&measured_box.children) {
+            size_cell(cell, measured_cell, viewport.device_pixel_ratio);
+        }
+    }
+
+    Ok(())
+}
+
+/// Turns the flex layout of a container in a vertical writing mode.
+pub(crate) fn rotate_container(node: &mut NodeKind, writing_mode: WritingMode, viewport: Viewport) {
+    let declared = Declarations::of(node, viewport);
+
+    let display = declared.resolve(|style| &style.display, |property| match property {
+        TailwindProperty::Display(display) => Some(*display),
+        _ => None,
+    });
+
+    if display != Display::Flex {
+        return;
+    }
+
+    let flex_direction = declared.resolve(|style| &style.flex_direction, |property| match property {
+        TailwindProperty::FlexDirection(flex_direction) => Some(*flex_direction),
+        _ => None,
+    });
+
+    // Rows follow the lines, columns stack like the lines do.
+    let rotated = match (flex_direction, writing_mode) {
+        (FlexDirection::Row, _) => FlexDirection::Column,
+        (FlexDirection::RowReverse, _) => FlexDirection::ColumnReverse,
+        (FlexDirection::Column, WritingMode::VerticalLr) | (FlexDirection::ColumnReverse, WritingMode::VerticalRl) => {
+            FlexDirection::Row
+        }
+        (FlexDirection::Column, _) | (FlexDirection::ColumnReverse, _) => FlexDirection::RowReverse,
+    };
+
+    let style = match node {
+        NodeKind::Container(container) => &mut container.style,
+        NodeKind::Image(image) => &mut image.style,
+        NodeKind::Text(text) => &mut text.style,
+    };
+    style.get_or_insert_with(Style::default).flex_direction = CssValue::Value(rotated);
+}
+
+/// Splits text into cells: one per upright character, and one per sideways word or
+/// space between words.
+fn split_cells(text: &str, orientation: TextOrientation) -> Vec<Cell> {
+    let general_category = CodePointMapData::<GeneralCategory>::new();
+    let vertical_orientation = CodePointMapData::<VerticalOrientation>::new();
+
+    let mut cells: Vec<Cell> = Vec::new();
+    let mut joins_previous = false;
+
+    for c in text.chars() {
+        // Break opportunities are meaningless between cells.
+        if matches!(c, '\u{00AD}' | '\u{200B}' | '\u{200E}' | '\u{200F}') {
+            continue;
+        }
+
+        let c = if c.is_whitespace() { ' ' } else { c };
+        let sideways = match orientation {
+            TextOrientation::Upright => false,
+            TextOrientation::Sideways => true,
+            TextOrientation::Mixed => matches!(
+                vertical_orientation.get(c),
+                VerticalOrientation::Rotated | VerticalOrientation::TransformedRotated
+            ),
+        };
+
+        let is_mark = GeneralCategoryGroup::Mark.contains(general_category.get(c))
+            || matches!(c, ZERO_WIDTH_JOINER | '\u{FE00}'..='\u{FE0F}');
+
+        match cells.last_mut() {
+            // Marks and joined characters stay in the cell of the character they belong to.
+            Some(cell) if is_mark || joins_previous => cell.text.push(c),
+            // Runs of whitespace collapse into a single space.
+            Some(cell) if c == ' ' && cell.text == " " => {}
+            Some(cell) if sideways && cell.sideways && c != ' ' && !cell.text.ends_with(' ') => cell.text.push(c),
+            _ => cells.push(Cell {
+                text: c.to_string(),
+                sideways: sideways || c == ' ',
+            }),
+        }
+
+        joins_previous = c == ZERO_WIDTH_JOINER;
+    }
+
+    cells
+}
+
+fn lower_cell(cell: Cell, style: Option<Style>, tw: Option<TailwindValues>, vert: &[FontFeature]) -> NodeKind {
+    let text = TextNode {
+        preset: None,
+        style: Some(Style {
+            white_space: CssValue::Value(WhiteSpace::pre()),
+            font_feature_settings: if cell.sideways {
+                style.as_ref().map_or(CssValue::Unset, |style| style.font_feature_settings.clone())
+            } else {
+                CssValue::Value(Some(vert.into()))
+            },
+            flex_shrink: CssValue::Value(Some(FlexGrow(0.0))),
+            ..style.unwrap_or_default()
+        }),
+        text: cell.text,
+        tw,
+    };
+
+    if !cell.sideways {
+        return text.into();
+    }
+
+    ContainerNode {
+        preset: None,
+        style: Some(Style {
+            flex_shrink: CssValue::Value(Some(FlexGrow(0.0))),
+            ..Default::default()
+        }),
+        children: Some(Box::new([text.into()])),
+        tw: None,
+    }
+    .into()
+}
+
+/// Sizes a cell from its size as laid out horizontally.
+fn size_cell(cell: &mut NodeKind, measured: &MeasuredNode, device_pixel_ratio: f32) {
+    let width = measured.width / device_pixel_ratio;
+    let line_height = measured.height / device_pixel_ratio;
+
+    match cell {
+        // Upright characters are one em tall, in a column as wide as a line is tall.
+        NodeKind::Text(text) => {
+            let style = text.style.get_or_insert_with(Style::default);
+            style.width = CssValue::Value(Length::Px(line_height));
+            style.height = CssValue::Value(Length::Em(1.0));
+            style.line_height = CssValue::Value(LineHeight(Length::Em(1.0)));
+            style.text_align = CssValue::Value(TextAlign::Center);
+        }
+        // Sideways runs swap their width and height, and are rotated around their center.
+        NodeKind::Container(container) => {
+            let style = container.style.get_or_insert_with(Style::default);
+            style.width = CssValue::Value(Length::Px(line_height));
+            style.height = CssValue::Value(Length::Px(width));
+
+            if let Some(NodeKind::Text(text)) = container.children.as_deref_mut().and_then(<[_]>::first_mut) {
+                let style = text.style.get_or_insert_with(Style::default);
+                style.position = CssValue::Value(Position::Absolute);
+                style.left = CssValue::Value(Some(Length::Px((line_height - width) / 2.0)));
+                style.top = CssValue::Value(Some(Length::Px((width - line_height) / 2.0)));
+                style.width = CssValue::Value(Length::Px(width));
+                style.height = CssValue::Value(Length::Px(line_height));
+                style.rotate = CssValue::Value(Some(Angle::new(90.0)));
+            }
+        }
+        NodeKind::Image(_) => {}
+    }
+}
+
+/// The font features of the text with `vert` enabled.
+fn vert_feature(style: &Style) -> Vec<FontFeature> {
+    let mut features = match &style.font_feature_settings {
+        CssValue::Value(Some(features)) => features.to_vec(),
+        _ => Vec::new(),
+    };
+
+    features.extend(FontFeature::parse_list("\"vert\" 1"));
+    features
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::json;
+    use takumi::parley::fontique::Blob;
+
+    use super::*;
+
+    const FONT: &[u8] = include_bytes!("../../../font.ttf");
+
+    fn cells(text: &str, orientation: TextOrientation) -> Vec<(String, bool)> {
+        split_cells(text, orientation).into_iter().map(|cell| (cell.text, cell.sideways)).collect()
+    }
+
+    #[test]
+    fn mixed_text_keeps_words_together_sideways() {
+        assert_eq!(
+            cells("日本  ok", TextOrientation::Mixed),
+            [
+                ("日".to_owned(), false),
+                ("本".to_owned(), false),
+                (" ".to_owned(), true),
+                ("ok".to_owned(), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn orientation_overrides_the_characters_own() {
+        assert_eq!(
+            cells("ab", TextOrientation::Upright),
+            [("a".to_owned(), false), ("b".to_owned(), false)]
+        );
+        assert_eq!(cells("日本", TextOrientation::Sideways), [("日本".to_owned(), true)]);
+    }
+
+    #[test]
+    fn marks_stay_with_their_character() {
+        assert_eq!(
+            cells("e\u{0301}x\u{00AD}y", TextOrientation::Upright),
+            [("e\u{0301}".to_owned(), false), ("x".to_owned(), false), ("y".to_owned(), false)]
+        );
+    }
+
+    #[test]
+    fn cells_are_sized_from_their_measured_lines() {
+        let mut global = GlobalContext::default();
+        let collection = &mut global.font_context.collection;
+        let (family, _) = collection.register_fonts(Blob::new(Arc::new(FONT)), None).remove(0);
+        let family = collection.family_name(family).unwrap().to_owned();
+
+        let tree = json!({
+            "type": "container",
+            "children": [{
+                "type": "text",
+                "text": "AB cd",
+                "style": {
+                    "fontFamily": family,
+                    "fontSize": 20,
+                    "writingMode": "vertical-lr",
+                    "textOrientation": "mixed",
+                },
+            }],
+        });
+
+        let mut lowered = super::super::NodeKind::from_value(&tree)
+            .unwrap()
+            .lower(Viewport::new(Some(200), Some(200)), &global)
+            .unwrap();
+        let NodeKind::Container(vertical_box) = probe::node_at(&mut lowered.tree, &[0]) else {
+            panic!("vertical text is not lowered into a box");
+        };
+
+        let style = vertical_box.style.as_ref().unwrap();
+        assert!(matches!(style.flex_wrap, CssValue::Value(FlexWrap::Wrap)));
+
+        // "AB", the space and "cd" are each a sideways cell, as wide as a line is tall.
+        let cells = vertical_box.children.as_deref().unwrap();
+        assert_eq!(cells.len(), 3);
+
+        for cell in cells {
+            let NodeKind::Container(cell) = cell else {
+                panic!("sideways cells are containers");
+            };
+            let style = cell.style.as_ref().unwrap();
+            let (CssValue::Value(Length::Px(width)), CssValue::Value(Length::Px(height))) = (&style.width, &style.height)
+            else {
+                panic!("cells are sized in pixels");
+            };
+            assert!(*width > 0.0 && *height > 0.0, "{width}x{height}");
+        }
+    }
+}
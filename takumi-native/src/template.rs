@@ -0,0 +1,375 @@
+//! Node trees registered once by name and rendered with different variables.
+//!
+//! A template is a tree in JSON whose strings may hold `{{name}}` placeholders, in text,
+//! `src`, `tw`, style values or anywhere else. A string that is a placeholder and nothing
+//! else is replaced by the variable's JSON value itself, so it can stand in for a number
+//! or even a list of children. Placeholders inside longer strings are replaced by the
+//! variable as text.
+//!
+//! The `fragments` a container defines are left as they are: their placeholders are
+//! filled by the variables of each instance when it is instantiated.
+
+use std::ffi::{c_char, CStr};
+
+use serde_json::{Map, Value};
+
+use crate::{node::NodeKind, render, set_last_error, ImageFormat, Viewport};
+
+/// The registered templates, keyed by name.
+static mut TEMPLATES: Vec<(String, Value)> = Vec::new();
+
+/// Returns the template registered under a name.
+///
+/// # Safety
+///
+/// - This function must not be called concurrently with template registration.
+pub(crate) unsafe fn registered(name: &str) -> Option<&'static Value> {
+    let templates = unsafe { &*std::ptr::addr_of!(TEMPLATES) };
+    templates
+        .iter()
+        .find(|(template_name, _)| template_name == name)
+        .map(|(_, template)| template)
+}
+
+/// Replaces the placeholders in every string of a template with their variables, except
+/// in the fragment definitions of its containers.
+pub(crate) fn substitute(value: &Value, variables: &Map<String, Value>) -> Result<Value, String> {
+    Ok(match value {
+        Value::String(text) => substitute_text(text, variables)?,
+        Value::Array(values) => Value::Array(
+            values
+                .iter()
+                .map(|value| substitute(value, variables))
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Object(entries) => Value::Object(
+            entries
+                .iter()
+                .map(|(key, value)| match key.as_str() {
+                    "fragments" => Ok((key.clone(), value.clone())),
+                    _ => Ok((key.clone(), substitute(value, variables)?)),
+                })
+                .collect::<Result<_, String>>()?,
+        ),
+        value => value.clone(),
+    })
+}
+
+fn substitute_text(text: &str, variables: &Map<String, Value>) -> Result<Value, String> {
+    if let Some(name) = text.strip_prefix("{{").and_then(|rest| rest.strip_suffix("}}")) {
+        if !name.contains("}}") {
+            return variable(name, variables).cloned();
+        }
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}").map(|end| start + end) else {
+            break;
+        };
+
+        result.push_str(&rest[..start]);
+        match variable(&rest[start + 2..end], variables)? {
+            Value::Null => {}
+            Value::String(value) => result.push_str(value),
+            value => result.push_str(&value.to_string()),
+        }
+        rest = &rest[end + 2..];
+    }
+    result.push_str(rest);
+
+    Ok(Value::String(result))
+}
+
+fn variable<'v>(name: &str, variables: &'v Map<String, Value>) -> Result<&'v Value, String> {
+    let name = name.trim();
+    variables
+        .get(name)
+        .ok_or_else(|| format!("no value for variable \"{name}\""))
+}
+
+/// Builds the tree of the template named `name` with the variables in `variables_str`.
+unsafe fn instantiate(name: *const c_char, variables_str: *const c_char) -> Result<NodeKind, String> {
+    if name.is_null() {
+        return Err("name is null".to_string());
+    }
+
+    let name = CStr::from_ptr(name).to_str().map_err(|e| e.to_string())?;
+    let variables = match variables_str.is_null() {
+        true => Map::new(),
+        false => {
+            let variables_str = CStr::from_ptr(variables_str).to_str().map_err(|e| e.to_string())?;
+            serde_json::from_str(variables_str).map_err(|e| format!("invalid template variables: {e}"))?
+        }
+    };
+
+    let Some(template) = (unsafe { registered(name) }) else {
+        return Err(format!("no template is registered as \"{name}\""));
+    };
+
+    let tree = substitute(template, &variables).map_err(|e| format!("template \"{name}\": {e}"))?;
+    NodeKind::from_value(&tree).map_err(|e| format!("template \"{name}\": {e}"))
+}
+
+/// Registers a template under a name, replacing the template previously registered with it.
+///
+/// # Safety
+///
+/// - `name` and `template_str` must be valid null-terminated C string pointers.
+/// - This function must not be called concurrently with rendering or other template calls.
+#[no_mangle]
+pub unsafe extern "C" fn template_register(name: *const c_char, template_str: *const c_char) -> bool {
+    if name.is_null() || template_str.is_null() {
+        set_last_error("name or template_str is null");
+        return false;
+    }
+
+    let (name, template_str) = match (CStr::from_ptr(name).to_str(), CStr::from_ptr(template_str).to_str()) {
+        (Ok(name), Ok(template_str)) => (name, template_str),
+        (Err(e), _) | (_, Err(e)) => {
+            set_last_error(e);
+            return false;
+        }
+    };
+
+    let template: Value = match serde_json::from_str(template_str) {
+        Ok(template) => template,
+        Err(e) => {
+            set_last_error(e);
+            return false;
+        }
+    };
+
+    let templates = unsafe { &mut *std::ptr::addr_of_mut!(TEMPLATES) };
+    templates.retain(|(template_name, _)| template_name != name);
+    templates.push((name.to_string(), template));
+
+    true
+}
+
+/// Removes the template registered under a name. Returns false when there is none.
+///
+/// # Safety
+///
+/// - `name` must be a valid null-terminated C string pointer.
+/// - This function must not be called concurrently with rendering or other template calls.
+#[no_mangle]
+pub unsafe extern "C" fn template_unregister(name: *const c_char) -> bool {
+    if name.is_null() {
+        return false;
+    }
+
+    let Ok(name) = CStr::from_ptr(name).to_str() else {
+        return false;
+    };
+
+    let templates = unsafe { &mut *std::ptr::addr_of_mut!(TEMPLATES) };
+    let count = templates.len();
+    templates.retain(|(template_name, _)| template_name != name);
+
+    templates.len() != count
+}
+
+/// Calculates the required buffer size for rendering a template to an image.
+///
+/// `variables_str` is a JSON object with a value for every placeholder of the template,
+/// or null when it has none.
+///
+/// # Safety
+///
+/// - `name` must be a valid null-terminated C string pointer.
+/// - `variables_str` must be null or a valid null-terminated C string pointer.
+#[no_mangle]
+pub unsafe extern "C" fn render_template_calculate_buffer_size_with_format(
+    name: *const c_char,
+    variables_str: *const c_char,
+    viewport: Viewport,
+    format: ImageFormat,
+) -> u64 {
+    let bytes = unsafe { instantiate(name, variables_str) }.and_then(|node| unsafe { render(node, viewport, format) });
+
+    match bytes {
+        Ok(bytes) => bytes.len() as u64,
+        Err(e) => {
+            set_last_error(e);
+            0
+        }
+    }
+}
+
+/// Renders a template to an image and writes it to a buffer.
+///
+/// # Safety
+///
+/// - `name` must be a valid null-terminated C string pointer.
+/// - `variables_str` must be null or a valid null-terminated C string pointer.
+/// - `out_buffer` must be a valid pointer to a buffer of at least `buffer_len` bytes.
+/// - The buffer must remain valid for the duration of this call.
+#[no_mangle]
+pub unsafe extern "C" fn render_template_to_buffer_with_format(
+    name: *const c_char,
+    variables_str: *const c_char,
+    viewport: Viewport,
+    format: ImageFormat,
+    out_buffer: *mut u8,
+    buffer_len: u64,
+) -> bool {
+    if out_buffer.is_null() || buffer_len == 0 {
+        set_last_error("Invalid arguments: null pointer or zero buffer length");
+        return false;
+    }
+
+    let bytes = unsafe { instantiate(name, variables_str) }.and_then(|node| unsafe { render(node, viewport, format) });
+    let bytes = match bytes {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            set_last_error(e);
+            return false;
+        }
+    };
+
+    if bytes.len() > buffer_len as usize {
+        set_last_error("Buffer too small");
+        return false;
+    }
+
+    std::ptr::copy_nonoverlapping(bytes.as_ptr(), out_buffer, bytes.len());
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn variables(value: Value) -> Map<String, Value> {
+        match value {
+            Value::Object(variables) => variables,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn whole_string_placeholder_keeps_the_value() {
+        let variables = variables(json!({ "size": 48, "items": [1, 2] }));
+
+        assert_eq!(substitute_text("{{size}}", &variables).unwrap(), json!(48));
+        assert_eq!(substitute_text("{{ items }}", &variables).unwrap(), json!([1, 2]));
+    }
+
+    #[test]
+    fn placeholders_in_text_are_replaced_as_text() {
+        let variables = variables(json!({ "name": "Ada", "count": 3, "missing": null }));
+
+        assert_eq!(
+            substitute_text("{{name}} has {{ count }} items{{missing}}", &variables).unwrap(),
+            json!("Ada has 3 items")
+        );
+    }
+
+    #[test]
+    fn repeated_placeholders_are_all_replaced() {
+        let variables = variables(json!({ "name": "Ada" }));
+
+        assert_eq!(
+            substitute_text("{{name}}, {{name}} and {{name}}", &variables).unwrap(),
+            json!("Ada, Ada and Ada")
+        );
+        assert_eq!(substitute_text("{{name}}{{name}}", &variables).unwrap(), json!("AdaAda"));
+    }
+
+    #[test]
+    fn missing_variables_fail() {
+        let variables = variables(json!({ "name": "Ada" }));
+
+        assert_eq!(
+            substitute_text("Hello {{ title }}", &variables).unwrap_err(),
+            "no value for variable \"title\""
+        );
+        assert_eq!(substitute_text("{{title}}", &variables).unwrap_err(), "no value for variable \"title\"");
+    }
+
+    #[test]
+    fn values_are_inserted_verbatim() {
+        let variables = variables(json!({ "quote": "\"{{name}}\" \\ }}", "name": "Ada" }));
+
+        assert_eq!(
+            substitute_text("say {{quote}}", &variables).unwrap(),
+            json!("say \"{{name}}\" \\ }}")
+        );
+        assert_eq!(substitute_text("{{quote}}", &variables).unwrap(), json!("\"{{name}}\" \\ }}"));
+    }
+
+    #[test]
+    fn unbalanced_braces_stay_text() {
+        let variables = variables(json!({ "name": "Ada" }));
+
+        assert_eq!(substitute_text("{{name", &variables).unwrap(), json!("{{name"));
+        assert_eq!(substitute_text("name}} {", &variables).unwrap(), json!("name}} {"));
+        assert_eq!(substitute_text("{{name}} {{", &variables).unwrap(), json!("Ada {{"));
+    }
+
+    #[test]
+    fn nested_braces_are_part_of_the_name() {
+        let variables = variables(json!({ "name": "Ada" }));
+
+        assert_eq!(
+            substitute_text("{{{name}}}", &variables).unwrap_err(),
+            "no value for variable \"{name}\""
+        );
+        assert_eq!(
+            substitute_text("a {{{name}}}", &variables).unwrap_err(),
+            "no value for variable \"{name\""
+        );
+    }
+
+    #[test]
+    fn fragment_definitions_keep_their_placeholders() {
+        let variables = variables(json!({ "name": "Ada" }));
+        let template = json!({
+            "type": "container",
+            "fragments": { "row": { "type": "text", "text": "{{title}}" } },
+            "children": [{ "type": "fragment", "id": "row", "variables": { "title": "{{name}}" } }],
+        });
+
+        assert_eq!(
+            substitute(&template, &variables).unwrap(),
+            json!({
+                "type": "container",
+                "fragments": { "row": { "type": "text", "text": "{{title}}" } },
+                "children": [{ "type": "fragment", "id": "row", "variables": { "title": "Ada" } }],
+            })
+        );
+
+        let mut tree = NodeKind::from_value(&substitute(&template, &variables).unwrap()).unwrap();
+        tree.resolve().unwrap();
+        let NodeKind::Container(container) = tree else {
+            panic!("the root is not a container");
+        };
+        let Some(NodeKind::Text(text)) = container.children.as_deref().and_then(<[_]>::first) else {
+            panic!("the instance is not a text node");
+        };
+        assert_eq!(text.text, "Ada");
+    }
+
+    #[test]
+    fn substitutes_every_string_of_a_tree() {
+        let variables = variables(json!({ "title": "Hi", "size": 48, "children": [{ "type": "text", "text": "x" }] }));
+        let template = json!({
+            "type": "container",
+            "style": { "fontSize": "{{size}}", "width": 100 },
+            "children": ["{{children}}", { "type": "text", "text": "{{title}}!" }],
+        });
+
+        assert_eq!(
+            substitute(&template, &variables).unwrap(),
+            json!({
+                "type": "container",
+                "style": { "fontSize": 48, "width": 100 },
+                "children": [[{ "type": "text", "text": "x" }], { "type": "text", "text": "Hi!" }],
+            })
+        );
+    }
+}
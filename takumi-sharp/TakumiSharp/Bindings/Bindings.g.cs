@@ -30,6 +30,18 @@ namespace TakumiSharp.Bindings
         [return: MarshalAs(UnmanagedType.U1)]
         internal static extern bool global_font_context_load_and_store(byte* data, nuint len);
 
+        /// <summary>
+        ///  Resets the global context, dropping all fonts, language fallbacks, stored images and the
+        ///  last error of the calling thread.
+        ///
+        ///  # Safety
+        ///
+        ///  - This function must not be called concurrently with rendering or other global context calls.
+        ///  - Pointers previously returned by [`get_last_error`] on the calling thread are invalidated.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "global_context_reset", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern void global_context_reset();
+
         /// <summary>
         ///  Calculates the required buffer size for rendering a node to an image.
         ///
@@ -41,11 +53,12 @@ namespace TakumiSharp.Bindings
         internal static extern ulong render_calculate_buffer_size_with_format(byte* node_str, Viewport viewport, ImageFormat format);
 
         /// <summary>
-        ///  Returns the last error that occurred in the global context.
+        ///  Returns the last error that occurred on the calling thread.
         ///
         ///  # Safety
         ///
-        ///  - The returned pointer must not be used after the global context is dropped.
+        ///  - The returned pointer must only be used on the calling thread, and not after its next
+        ///    failing call or [`global_context_reset`].
         /// </summary>
         [DllImport(__DllName, EntryPoint = "get_last_error", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
         internal static extern byte* get_last_error();
@@ -63,6 +76,451 @@ namespace TakumiSharp.Bindings
         [return: MarshalAs(UnmanagedType.U1)]
         internal static extern bool render_to_buffer_with_format(byte* node_str, Viewport viewport, ImageFormat format, byte* out_buffer, ulong buffer_len);
 
+        /// <summary>
+        ///  Loads font data into the global font context and returns its face ID.
+        ///
+        ///  Every face contained in the data (e.g. all fonts of a collection) shares the
+        ///  returned ID, which can later be passed to [`global_font_context_unload_face`].
+        ///  Returns 0 on failure.
+        ///
+        ///  # Safety
+        ///
+        ///  - `data` must be a valid pointer to a byte array of at least `len` bytes.
+        ///  - The data must remain valid for the duration of this call.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "global_font_context_load_and_store_face", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern ulong global_font_context_load_and_store_face(byte* data, nuint len);
+
+        /// <summary>
+        ///  Unloads every face registered under the given face ID.
+        ///
+        ///  # Safety
+        ///
+        ///  This function must not be called concurrently with rendering or other font context calls.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "global_font_context_unload_face", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        [return: MarshalAs(UnmanagedType.U1)]
+        internal static extern bool global_font_context_unload_face(ulong face_id);
+
+        /// <summary>
+        ///  Unloads every face of the given font family that was loaded through
+        ///  [`global_font_context_load_and_store_face`]. Returns false when there is none, e.g. for
+        ///  the built-in fallback font.
+        ///
+        ///  # Safety
+        ///
+        ///  - `family` must be a valid null-terminated C string pointer.
+        ///  - This function must not be called concurrently with rendering or other font context calls.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "global_font_context_unload_family", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        [return: MarshalAs(UnmanagedType.U1)]
+        internal static extern bool global_font_context_unload_family(byte* family);
+
+        /// <summary>
+        ///  Unloads every font from the global font context.
+        ///
+        ///  The built-in fallback font, if enabled, is registered again afterwards.
+        ///
+        ///  # Safety
+        ///
+        ///  This function must not be called concurrently with rendering or other font context calls.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "global_font_context_clear", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern void global_font_context_clear();
+
+        /// <summary>
+        ///  Sets the font families text in a language falls back to when the font families in
+        ///  its style lack a glyph, e.g. `"Noto Sans JP"` for `ja`.
+        ///
+        ///  `families` is a comma-separated list like the `fontFamily` style property. Passing
+        ///  null or an empty string removes the fallback for the language.
+        ///
+        ///  # Safety
+        ///
+        ///  - `lang` must be a valid null-terminated C string pointer.
+        ///  - `families` must be null or a valid null-terminated C string pointer.
+        ///  - This function must not be called concurrently with rendering or other font context calls.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "global_font_context_set_language_fallback", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        [return: MarshalAs(UnmanagedType.U1)]
+        internal static extern bool global_font_context_set_language_fallback(byte* lang, byte* families);
+
+        /// <summary>
+        ///  Writes every font loaded into the global font context to a cache file.
+        ///
+        ///  # Safety
+        ///
+        ///  - `path` must be a valid null-terminated C string pointer.
+        ///  - This function must not be called concurrently with other font context calls.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "global_font_context_save_cache", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        [return: MarshalAs(UnmanagedType.U1)]
+        internal static extern bool global_font_context_save_cache(byte* path);
+
+        /// <summary>
+        ///  Restores fonts from a cache file written by [`global_font_context_save_cache`].
+        ///
+        ///  The file is memory-mapped, so it must not be modified while fonts from it are loaded.
+        ///  Returns false if the file is missing, invalid, or was written by a different version
+        ///  of takumi, in which case the fonts should be loaded again and the cache rebuilt.
+        ///
+        ///  Writes the face ID of every restored font to `out_face_ids`, in the order the fonts
+        ///  were loaded before saving, and their number to `out_count`. If the file holds more
+        ///  than `len` fonts, nothing is restored, false is returned and `out_count` is set to the
+        ///  number of fonts in the file, so the call can be repeated with a buffer of that size.
+        ///
+        ///  # Safety
+        ///
+        ///  - `path` must be a valid null-terminated C string pointer.
+        ///  - `out_face_ids` must be null or a valid pointer to at least `len` `u64`s.
+        ///  - `out_count` must be a valid pointer to a `u64`.
+        ///  - This function must not be called concurrently with rendering or other font context calls.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "global_font_context_restore_cache", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        [return: MarshalAs(UnmanagedType.U1)]
+        internal static extern bool global_font_context_restore_cache(byte* path, ulong* out_face_ids, ulong len, ulong* out_count);
+
+        /// <summary>
+        ///  Measures text with the fonts loaded into the global context, without rasterizing it.
+        ///
+        ///  Writes the overall size to `out_metrics` and the metrics of up to `lines_len` lines to
+        ///  `out_lines`. `line_count` always holds the total number of lines, so a second call with
+        ///  a larger buffer can retrieve every line. The text is measured as-is, without collapsing
+        ///  whitespace, and the widths of the lines and of the text box exclude the whitespace lines
+        ///  end with, like the width of a text node sized to its content.
+        ///
+        ///  Each thread keeps its own copy of the global font context to measure with, copied again
+        ///  only after fonts are loaded or unloaded. Text can therefore be measured concurrently
+        ///  with rendering and with other measurements, which only read the global font context.
+        ///
+        ///  # Safety
+        ///
+        ///  - `text` must be a valid null-terminated C string pointer.
+        ///  - `style.font_family` must be null or a valid null-terminated C string pointer.
+        ///  - `out_lines` must be null or a valid pointer to at least `lines_len` [`LineMetrics`].
+        ///  - `out_metrics` must be a valid pointer to a [`TextMetrics`].
+        ///  - This function must not be called concurrently with loading or unloading fonts.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "measure_text", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        [return: MarshalAs(UnmanagedType.U1)]
+        internal static extern bool measure_text(byte* text, TextMeasureStyle style, float max_width, LineMetrics* out_lines, ulong lines_len, TextMetrics* out_metrics);
+
+        /// <summary>
+        ///  Picks the font size of every text node with `fit` set in a parsed node tree, as
+        ///  rendering it with the same viewport does, without rendering it.
+        ///
+        ///  Sizes are in document order of the fitted text nodes. Writes up to `len` sizes to
+        ///  `out_sizes` and the total number of fitted text nodes to `out_count`, so a call with
+        ///  a buffer that is too small can be repeated with one of the right size.
+        ///
+        ///  # Safety
+        ///
+        ///  - `handle` must be a handle returned by [`crate::handle::takumi_node_parse`] that was not released yet.
+        ///  - `out_sizes` must be null or a valid pointer to at least `len` floats.
+        ///  - `out_count` must be a valid pointer to a `u64`.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "takumi_node_fit_font_sizes", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        [return: MarshalAs(UnmanagedType.U1)]
+        internal static extern bool takumi_node_fit_font_sizes(NodeHandle* handle, Viewport viewport, float* out_sizes, ulong len, ulong* out_count);
+
+        /// <summary>
+        ///  Sets the function paint nodes are drawn with, or removes it when `callback` is null.
+        ///
+        ///  The callback is called on the rendering thread for every paint node with a non-empty
+        ///  content box, each time its tree is rendered, which includes calculating the buffer
+        ///  size. Rendering a tree with paint nodes fails while no callback is set.
+        ///
+        ///  # Safety
+        ///
+        ///  - `callback` must be null or a function that stays valid until it is replaced.
+        ///  - This function must not be called concurrently with rendering.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "set_paint_callback", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern void set_paint_callback(delegate* unmanaged[Cdecl]<byte*, PaintArea, byte*, nuint, void> callback);
+
+        /// <summary>
+        ///  Registers a template under a name, replacing the template previously registered with it.
+        ///
+        ///  # Safety
+        ///
+        ///  - `name` and `template_str` must be valid null-terminated C string pointers.
+        ///  - This function must not be called concurrently with rendering or other template calls.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "template_register", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        [return: MarshalAs(UnmanagedType.U1)]
+        internal static extern bool template_register(byte* name, byte* template_str);
+
+        /// <summary>
+        ///  Removes the template registered under a name. Returns false when there is none.
+        ///
+        ///  # Safety
+        ///
+        ///  - `name` must be a valid null-terminated C string pointer.
+        ///  - This function must not be called concurrently with rendering or other template calls.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "template_unregister", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        [return: MarshalAs(UnmanagedType.U1)]
+        internal static extern bool template_unregister(byte* name);
+
+        /// <summary>
+        ///  Calculates the required buffer size for rendering a template to an image.
+        ///
+        ///  `variables_str` is a JSON object with a value for every placeholder of the template,
+        ///  or null when it has none.
+        ///
+        ///  # Safety
+        ///
+        ///  - `name` must be a valid null-terminated C string pointer.
+        ///  - `variables_str` must be null or a valid null-terminated C string pointer.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "render_template_calculate_buffer_size_with_format", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern ulong render_template_calculate_buffer_size_with_format(byte* name, byte* variables_str, Viewport viewport, ImageFormat format);
+
+        /// <summary>
+        ///  Renders a template to an image and writes it to a buffer.
+        ///
+        ///  # Safety
+        ///
+        ///  - `name` must be a valid null-terminated C string pointer.
+        ///  - `variables_str` must be null or a valid null-terminated C string pointer.
+        ///  - `out_buffer` must be a valid pointer to a buffer of at least `buffer_len` bytes.
+        ///  - The buffer must remain valid for the duration of this call.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "render_template_to_buffer_with_format", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        [return: MarshalAs(UnmanagedType.U1)]
+        internal static extern bool render_template_to_buffer_with_format(byte* name, byte* variables_str, Viewport viewport, ImageFormat format, byte* out_buffer, ulong buffer_len);
+
+        /// <summary>
+        ///  Parses a node tree once, returning a handle to render it with or null on failure.
+        ///
+        ///  The tree is parsed like the one passed to [`crate::render_to_buffer_with_format`],
+        ///  with its Tailwind classes resolved and its composite nodes expanded. The handle must
+        ///  be released with [`takumi_node_free`].
+        ///
+        ///  # Safety
+        ///
+        ///  - `node_str` must be a valid null-terminated C string pointer.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "takumi_node_parse", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern NodeHandle* takumi_node_parse(byte* node_str);
+
+        /// <summary>
+        ///  Releases a handle returned by [`takumi_node_parse`]. Does nothing when `handle` is null.
+        ///
+        ///  # Safety
+        ///
+        ///  - `handle` must be null or a handle returned by [`takumi_node_parse`] that was not released yet.
+        ///  - The handle must not be rendered concurrently or used after this call.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "takumi_node_free", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern void takumi_node_free(NodeHandle* handle);
+
+        /// <summary>
+        ///  Calculates the required buffer size for rendering a parsed node tree to an image.
+        ///
+        ///  # Safety
+        ///
+        ///  - `handle` must be a handle returned by [`takumi_node_parse`] that was not released yet.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "takumi_node_calculate_buffer_size_with_format", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern ulong takumi_node_calculate_buffer_size_with_format(NodeHandle* handle, Viewport viewport, ImageFormat format);
+
+        /// <summary>
+        ///  Renders a parsed node tree to an image and writes it to a buffer.
+        ///
+        ///  # Safety
+        ///
+        ///  - `handle` must be a handle returned by [`takumi_node_parse`] that was not released yet.
+        ///  - `out_buffer` must be a valid pointer to a buffer of at least `buffer_len` bytes.
+        ///  - The buffer must remain valid for the duration of this call.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "takumi_node_render_to_buffer_with_format", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        [return: MarshalAs(UnmanagedType.U1)]
+        internal static extern bool takumi_node_render_to_buffer_with_format(NodeHandle* handle, Viewport viewport, ImageFormat format, byte* out_buffer, ulong buffer_len);
+
+        /// <summary>
+        ///  Creates a builder for an empty container node.
+        ///
+        ///  The builder must be appended to a container, built with [`takumi_builder_build`] or
+        ///  released with [`takumi_builder_free`].
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "takumi_builder_new_container", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern NodeBuilder* takumi_builder_new_container();
+
+        /// <summary>
+        ///  Creates a builder for a text node, or returns null on failure.
+        ///
+        ///  # Safety
+        ///
+        ///  - `text` must be a valid null-terminated C string pointer.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "takumi_builder_new_text", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern NodeBuilder* takumi_builder_new_text(byte* text);
+
+        /// <summary>
+        ///  Creates a builder for an image node, or returns null on failure.
+        ///
+        ///  `width` and `height` are in pixels, or negative to use the size of the image.
+        ///
+        ///  # Safety
+        ///
+        ///  - `src` must be a valid null-terminated C string pointer.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "takumi_builder_new_image", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern NodeBuilder* takumi_builder_new_image(byte* src, float width, float height);
+
+        /// <summary>
+        ///  Sets a style property of a node to a CSS value, like `"fontSize"` to `"2rem"`.
+        ///
+        ///  The property is named like in a node's JSON style. Fails when takumi does not know the
+        ///  property or cannot parse the value, or for a property resolved natively, like
+        ///  `"writingMode"`, on an image.
+        ///
+        ///  # Safety
+        ///
+        ///  - `builder` must be a builder that was not built, appended or released yet.
+        ///  - `property` and `value` must be valid null-terminated C string pointers.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "takumi_builder_set_style", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        [return: MarshalAs(UnmanagedType.U1)]
+        internal static extern bool takumi_builder_set_style(NodeBuilder* builder, byte* property, byte* value);
+
+        /// <summary>
+        ///  Sets a style property of a node to a number, like `"flexGrow"` to `1`. Lengths given
+        ///  as a number are in pixels.
+        ///
+        ///  # Safety
+        ///
+        ///  - `builder` must be a builder that was not built, appended or released yet.
+        ///  - `property` must be a valid null-terminated C string pointer.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "takumi_builder_set_style_number", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        [return: MarshalAs(UnmanagedType.U1)]
+        internal static extern bool takumi_builder_set_style_number(NodeBuilder* builder, byte* property, float value);
+
+        /// <summary>
+        ///  Sets the Tailwind classes of a node, replacing the ones set before. Fails for a class
+        ///  takumi does not know.
+        ///
+        ///  # Safety
+        ///
+        ///  - `builder` must be a builder that was not built, appended or released yet.
+        ///  - `tw` must be a valid null-terminated C string pointer.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "takumi_builder_set_tw", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        [return: MarshalAs(UnmanagedType.U1)]
+        internal static extern bool takumi_builder_set_tw(NodeBuilder* builder, byte* tw);
+
+        /// <summary>
+        ///  Appends a node as the last child of a container.
+        ///
+        ///  On success the parent owns the child, and the child's builder must not be used again.
+        ///  On failure, when the parent is not a container, the child stays with the caller.
+        ///
+        ///  # Safety
+        ///
+        ///  - `parent` and `child` must be distinct builders that were not built, appended or released yet.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "takumi_builder_append_child", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        [return: MarshalAs(UnmanagedType.U1)]
+        internal static extern bool takumi_builder_append_child(NodeBuilder* parent, NodeBuilder* child);
+
+        /// <summary>
+        ///  Builds a node and its children into a handle rendered like a parsed tree, or returns
+        ///  null on failure.
+        ///
+        ///  The builder is consumed either way. The handle must be released with
+        ///  [`crate::handle::takumi_node_free`].
+        ///
+        ///  # Safety
+        ///
+        ///  - `builder` must be a builder that was not built, appended or released yet.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "takumi_builder_build", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern NodeHandle* takumi_builder_build(NodeBuilder* builder);
+
+        /// <summary>
+        ///  Releases a builder that was not built or appended, along with its children. Does
+        ///  nothing when `builder` is null.
+        ///
+        ///  # Safety
+        ///
+        ///  - `builder` must be null or a builder that was not built, appended or released yet.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "takumi_builder_free", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern void takumi_builder_free(NodeBuilder* builder);
+
+        /// <summary>
+        ///  Calculates the required buffer size for rendering a node tree read from bytes to an image.
+        ///
+        ///  This is also how to render UTF-8 JSON given by its length, with [`NodeFormat::Json`].
+        ///  `node_format` is the value of a [`NodeFormat`].
+        ///
+        ///  # Safety
+        ///
+        ///  - `data` must be a valid pointer to a byte array of at least `len` bytes.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "render_bytes_calculate_buffer_size_with_format", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern ulong render_bytes_calculate_buffer_size_with_format(byte* data, nuint len, byte node_format, Viewport viewport, ImageFormat format);
+
+        /// <summary>
+        ///  Renders a node tree read from bytes to an image and writes it to a buffer.
+        ///
+        ///  # Safety
+        ///
+        ///  - `data` must be a valid pointer to a byte array of at least `len` bytes.
+        ///  - `out_buffer` must be a valid pointer to a buffer of at least `buffer_len` bytes.
+        ///  - The buffers must remain valid for the duration of this call.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "render_bytes_to_buffer_with_format", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        [return: MarshalAs(UnmanagedType.U1)]
+        internal static extern bool render_bytes_to_buffer_with_format(byte* data, nuint len, byte node_format, Viewport viewport, ImageFormat format, byte* out_buffer, ulong buffer_len);
+
+        /// <summary>
+        ///  Parses a node tree read from bytes once, returning a handle to render it with or null
+        ///  on failure. The handle must be released with [`crate::handle::takumi_node_free`].
+        ///
+        ///  # Safety
+        ///
+        ///  - `data` must be a valid pointer to a byte array of at least `len` bytes.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "takumi_node_parse_bytes", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern NodeHandle* takumi_node_parse_bytes(byte* data, nuint len, byte node_format);
+
+        /// <summary>
+        ///  Calculates the required buffer size for rendering a node tree in UTF-16 JSON to an image.
+        ///
+        ///  # Safety
+        ///
+        ///  - `data` must be a valid pointer to an array of at least `len` UTF-16 code units.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "render_utf16_calculate_buffer_size_with_format", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern ulong render_utf16_calculate_buffer_size_with_format(ushort* data, nuint len, Viewport viewport, ImageFormat format);
+
+        /// <summary>
+        ///  Renders a node tree in UTF-16 JSON to an image and writes it to a buffer.
+        ///
+        ///  # Safety
+        ///
+        ///  - `data` must be a valid pointer to an array of at least `len` UTF-16 code units.
+        ///  - `out_buffer` must be a valid pointer to a buffer of at least `buffer_len` bytes.
+        ///  - The buffers must remain valid for the duration of this call.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "render_utf16_to_buffer_with_format", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        [return: MarshalAs(UnmanagedType.U1)]
+        internal static extern bool render_utf16_to_buffer_with_format(ushort* data, nuint len, Viewport viewport, ImageFormat format, byte* out_buffer, ulong buffer_len);
+
+        /// <summary>
+        ///  Parses a node tree in UTF-16 JSON once, returning a handle to render it with or null on
+        ///  failure. The handle must be released with [`crate::handle::takumi_node_free`].
+        ///
+        ///  # Safety
+        ///
+        ///  - `data` must be a valid pointer to an array of at least `len` UTF-16 code units.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "takumi_node_parse_utf16", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern NodeHandle* takumi_node_parse_utf16(ushort* data, nuint len);
+
 
     }
 
@@ -90,6 +548,140 @@ namespace TakumiSharp.Bindings
         public float device_pixel_ratio;
     }
 
+    /// <summary>
+    ///  The style used to measure text.
+    /// </summary>
+    [StructLayout(LayoutKind.Sequential)]
+    internal unsafe partial struct TextMeasureStyle
+    {
+        /// <summary>
+        ///  A null-terminated CSS font family list, or null for sans-serif.
+        /// </summary>
+        public byte* font_family;
+        /// <summary>
+        ///  The font size in pixels.
+        /// </summary>
+        public float font_size;
+        /// <summary>
+        ///  The font weight, from 1 to 1000.
+        /// </summary>
+        public float font_weight;
+        /// <summary>
+        ///  Extra spacing between letters in pixels.
+        /// </summary>
+        public float letter_spacing;
+        /// <summary>
+        ///  The line height as a multiple of the font size, or 0 or less for the default.
+        /// </summary>
+        public float line_height;
+    }
+
+    /// <summary>
+    ///  The overall size of measured text.
+    /// </summary>
+    [StructLayout(LayoutKind.Sequential)]
+    internal unsafe partial struct TextMetrics
+    {
+        /// <summary>
+        ///  The width of the text box in pixels (the widest line width), rounded up. It exceeds
+        ///  the maximum width when a word that cannot be broken does not fit it.
+        /// </summary>
+        public float width;
+        /// <summary>
+        ///  The total height of all lines in pixels, rounded up.
+        /// </summary>
+        public float height;
+        /// <summary>
+        ///  The number of lines the text wraps to.
+        /// </summary>
+        public uint line_count;
+    }
+
+    /// <summary>
+    ///  The metrics of a single line of measured text.
+    /// </summary>
+    [StructLayout(LayoutKind.Sequential)]
+    internal unsafe partial struct LineMetrics
+    {
+        /// <summary>
+        ///  The advance of the line in pixels, excluding trailing whitespace.
+        /// </summary>
+        public float width;
+        /// <summary>
+        ///  The height of the line in pixels.
+        /// </summary>
+        public float height;
+        /// <summary>
+        ///  The offset of the baseline from the top of the text in pixels.
+        /// </summary>
+        public float baseline;
+        /// <summary>
+        ///  The typographic ascent of the line.
+        /// </summary>
+        public float ascent;
+        /// <summary>
+        ///  The typographic descent of the line.
+        /// </summary>
+        public float descent;
+        /// <summary>
+        ///  The UTF-16 index of the first character of the line.
+        /// </summary>
+        public uint start;
+        /// <summary>
+        ///  The UTF-16 index after the last character of the line.
+        /// </summary>
+        public uint end;
+    }
+
+    /// <summary>
+    ///  Where a paint node is drawn in the rendered image.
+    /// </summary>
+    [StructLayout(LayoutKind.Sequential)]
+    internal unsafe partial struct PaintArea
+    {
+        /// <summary>
+        ///  The distance of the content box from the left edge of the image in pixels.
+        /// </summary>
+        public float x;
+        /// <summary>
+        ///  The distance of the content box from the top edge of the image in pixels.
+        /// </summary>
+        public float y;
+        /// <summary>
+        ///  The width of the content box and the pixel buffer in pixels.
+        /// </summary>
+        public uint width;
+        /// <summary>
+        ///  The height of the content box and the pixel buffer in pixels.
+        /// </summary>
+        public uint height;
+        /// <summary>
+        ///  The device pixel ratio the image is rendered at.
+        /// </summary>
+        public float device_pixel_ratio;
+    }
+
+    /// <summary>
+    ///  A parsed and checked node tree. It is never changed once parsed, so a handle can be
+    ///  rendered from several threads at once, as long as fonts and other global state are not
+    ///  changed meanwhile. Each thread reads the errors of its own renders.
+    ///
+    ///  The tree is lowered into takumi's nodes for the viewport it was last rendered with,
+    ///  and lowered again only once the viewport or the fonts change.
+    /// </summary>
+    [StructLayout(LayoutKind.Sequential)]
+    internal unsafe partial struct NodeHandle
+    {
+    }
+
+    /// <summary>
+    ///  A node being built, along with its style and children.
+    /// </summary>
+    [StructLayout(LayoutKind.Sequential)]
+    internal unsafe partial struct NodeBuilder
+    {
+    }
+
 
     internal enum ImageFormat : byte
     {
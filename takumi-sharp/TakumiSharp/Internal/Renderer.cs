@@ -1,3 +1,6 @@
+using System.Collections.Concurrent;
+using System.Runtime.CompilerServices;
+using System.Runtime.InteropServices;
 using System.Text;
 using TakumiSharp.Bindings;
 
@@ -5,13 +8,19 @@ namespace TakumiSharp.Internal;
 
 internal class Renderer
 {
+  private static readonly ConcurrentDictionary<string, Models.Painter> Painters = new();
+
+  [ThreadStatic]
+  private static Exception? paintException;
+
   /// <summary>
   /// Loads a font from a file path.
   /// </summary>
   /// <param name="fontPath">Path to the font file</param>
+  /// <returns>The face ID of the loaded font</returns>
   /// <exception cref="FileNotFoundException">Thrown when the font file is not found</exception>
   /// <exception cref="InvalidOperationException">Thrown when the font fails to load</exception>
-  public static void LoadFont(string fontPath)
+  public static ulong LoadFont(string fontPath)
   {
     if (!File.Exists(fontPath))
     {
@@ -19,28 +28,305 @@ internal class Renderer
     }
 
     byte[] fontData = File.ReadAllBytes(fontPath);
-    LoadFont(fontData);
+    return LoadFont(fontData);
   }
 
   /// <summary>
   /// Loads a font from byte data.
   /// </summary>
   /// <param name="fontData">The font file bytes</param>
+  /// <returns>The face ID of the loaded font, usable with <see cref="UnloadFont(ulong)"/></returns>
   /// <exception cref="InvalidOperationException">Thrown when the font fails to load</exception>
-  public static unsafe void LoadFont(ReadOnlySpan<byte> fontData)
+  public static unsafe ulong LoadFont(ReadOnlySpan<byte> fontData)
   {
-    bool success;
+    ulong faceId;
     fixed (byte* dataPtr = fontData)
     {
-      success = NativeBindings.global_font_context_load_and_store(dataPtr, (nuint)fontData.Length);
+      faceId = NativeBindings.global_font_context_load_and_store_face(dataPtr, (nuint)fontData.Length);
     }
 
-    if (!success)
+    if (faceId == 0)
     {
-      var errorPtr = (nint)NativeBindings.get_last_error();
-      var errorMsg = errorPtr != 0 ? System.Runtime.InteropServices.Marshal.PtrToStringUTF8(errorPtr) : "Unknown error";
+      var errorMsg = GetLastError();
       throw new InvalidOperationException($"Failed to load font data: {errorMsg}");
     }
+
+    return faceId;
+  }
+
+  /// <summary>
+  /// Unloads every face registered under a face ID returned by <see cref="LoadFont(ReadOnlySpan{byte})"/>.
+  /// </summary>
+  /// <param name="faceId">The face ID of the font to unload</param>
+  /// <exception cref="InvalidOperationException">Thrown when no font was loaded with the face ID</exception>
+  public static void UnloadFont(ulong faceId)
+  {
+    if (!NativeBindings.global_font_context_unload_face(faceId))
+    {
+      var errorMsg = GetLastError();
+      throw new InvalidOperationException($"Failed to unload font: {errorMsg}");
+    }
+  }
+
+  /// <summary>
+  /// Unloads every face of a font family loaded with <see cref="LoadFont(ReadOnlySpan{byte})"/>.
+  /// </summary>
+  /// <param name="family">The font family name</param>
+  /// <exception cref="InvalidOperationException">Thrown when no loaded font belongs to the family, e.g. for the built-in fallback font</exception>
+  public static unsafe void UnloadFontFamily(string family)
+  {
+    byte[] familyBytes = Encoding.UTF8.GetBytes(family + '\0');
+
+    bool success;
+    fixed (byte* familyPtr = familyBytes)
+    {
+      success = NativeBindings.global_font_context_unload_family(familyPtr);
+    }
+
+    if (!success)
+    {
+      var errorMsg = GetLastError();
+      throw new InvalidOperationException($"Failed to unload font family: {errorMsg}");
+    }
+  }
+
+  /// <summary>
+  /// Sets the font families text in a language falls back to when its own font families lack a glyph.
+  /// </summary>
+  /// <param name="lang">The BCP 47 language tag, e.g. "ja" or "zh-Hant"</param>
+  /// <param name="fontFamilies">A comma-separated list of font families, or null to remove the fallback</param>
+  /// <exception cref="InvalidOperationException">Thrown when the fallback fails to be set</exception>
+  public static unsafe void SetLanguageFallback(string lang, string? fontFamilies)
+  {
+    byte[] langBytes = Encoding.UTF8.GetBytes(lang + '\0');
+    byte[]? familiesBytes = fontFamilies is null ? null : Encoding.UTF8.GetBytes(fontFamilies + '\0');
+
+    bool success;
+    fixed (byte* langPtr = langBytes)
+    fixed (byte* familiesPtr = familiesBytes)
+    {
+      success = NativeBindings.global_font_context_set_language_fallback(langPtr, familiesPtr);
+    }
+
+    if (!success)
+    {
+      var errorMsg = GetLastError();
+      throw new InvalidOperationException($"Failed to set language fallback: {errorMsg}");
+    }
+  }
+
+  /// <summary>
+  /// Writes every loaded font to a cache file, to be restored with <see cref="TryRestoreFontCache(string)"/>.
+  /// </summary>
+  /// <param name="cachePath">Path where the cache file will be written</param>
+  /// <exception cref="InvalidOperationException">Thrown when the cache fails to be written</exception>
+  public static unsafe void SaveFontCache(string cachePath)
+  {
+    byte[] pathBytes = Encoding.UTF8.GetBytes(cachePath + '\0');
+
+    bool success;
+    fixed (byte* pathPtr = pathBytes)
+    {
+      success = NativeBindings.global_font_context_save_cache(pathPtr);
+    }
+
+    if (!success)
+    {
+      var errorMsg = GetLastError();
+      throw new InvalidOperationException($"Failed to save font cache: {errorMsg}");
+    }
+  }
+
+  /// <summary>
+  /// Restores fonts from a cache file written by <see cref="SaveFontCache(string)"/>.
+  /// The file is memory-mapped and must not be modified while the fonts are loaded.
+  /// </summary>
+  /// <param name="cachePath">Path to the cache file</param>
+  /// <returns>False when the cache is missing, invalid or written by another takumi version, and should be rebuilt</returns>
+  public static bool TryRestoreFontCache(string cachePath) => TryRestoreFontCache(cachePath, out _);
+
+  /// <summary>
+  /// Restores fonts from a cache file written by <see cref="SaveFontCache(string)"/>.
+  /// The file is memory-mapped and must not be modified while the fonts are loaded.
+  /// </summary>
+  /// <param name="cachePath">Path to the cache file</param>
+  /// <param name="faceIds">The face IDs of the restored fonts, in the order they were loaded before saving, usable with <see cref="UnloadFont(ulong)"/></param>
+  /// <returns>False when the cache is missing, invalid or written by another takumi version, and should be rebuilt</returns>
+  public static unsafe bool TryRestoreFontCache(string cachePath, out ulong[] faceIds)
+  {
+    faceIds = [];
+    if (!File.Exists(cachePath))
+    {
+      return false;
+    }
+
+    byte[] pathBytes = Encoding.UTF8.GetBytes(cachePath + '\0');
+    ulong[] ids = new ulong[16];
+
+    while (true)
+    {
+      ulong count;
+      bool success;
+      fixed (byte* pathPtr = pathBytes)
+      fixed (ulong* idsPtr = ids)
+      {
+        success = NativeBindings.global_font_context_restore_cache(pathPtr, idsPtr, (ulong)ids.Length, &count);
+      }
+
+      if (success)
+      {
+        faceIds = ids[..(int)count];
+        return true;
+      }
+
+      // Nothing was restored because the cache holds more fonts than fit in the buffer
+      if (count <= (ulong)ids.Length)
+      {
+        return false;
+      }
+
+      ids = new ulong[count];
+    }
+  }
+
+  /// <summary>
+  /// Unloads every font from the font context.
+  /// </summary>
+  public static void ClearFonts() => NativeBindings.global_font_context_clear();
+
+  /// <summary>
+  /// Resets the renderer, dropping all fonts, stored images and the last error.
+  /// </summary>
+  public static void Reset() => NativeBindings.global_context_reset();
+
+  /// <summary>
+  /// Registers the painter drawing every paint node with the given name, replacing the previous one.
+  /// </summary>
+  /// <param name="name">The name of the paint nodes</param>
+  /// <param name="painter">The function drawing a paint node into its pixels</param>
+  public static unsafe void RegisterPainter(string name, Models.Painter painter)
+  {
+    Painters[name] = painter;
+    NativeBindings.set_paint_callback(&Paint);
+  }
+
+  /// <summary>
+  /// Removes the painter registered with a name.
+  /// </summary>
+  /// <param name="name">The name of the paint nodes</param>
+  /// <returns>False when no painter was registered with the name</returns>
+  public static bool UnregisterPainter(string name) => Painters.TryRemove(name, out _);
+
+  [UnmanagedCallersOnly(CallConvs = new[] { typeof(CallConvCdecl) })]
+  private static unsafe void Paint(byte* name, PaintArea area, byte* pixels, nuint len)
+  {
+    // Exceptions must not unwind into native code, so they are thrown once rendering returns.
+    try
+    {
+      string painterName = Marshal.PtrToStringUTF8((nint)name) ?? string.Empty;
+      if (!Painters.TryGetValue(painterName, out var painter))
+      {
+        throw new InvalidOperationException($"No painter is registered for paint node \"{painterName}\"");
+      }
+
+      var paintArea = new Models.PaintArea(area.x, area.y, (int)area.width, (int)area.height, area.device_pixel_ratio);
+      painter(paintArea, new Span<byte>(pixels, checked((int)len)));
+    }
+    catch (Exception e)
+    {
+      paintException ??= e;
+    }
+  }
+
+  /// <summary>
+  /// Throws the first exception a painter threw on this thread since the last check.
+  /// </summary>
+  private static void ThrowIfPaintFailed()
+  {
+    if (paintException is { } exception)
+    {
+      paintException = null;
+      throw new InvalidOperationException($"Failed to paint node: {exception.Message}", exception);
+    }
+  }
+
+  /// <summary>
+  /// Measures text with the loaded fonts, without rendering it.
+  /// It is safe to call concurrently with rendering and with other measurements, as each thread
+  /// measures with its own copy of the font context, but not while fonts are loaded or unloaded.
+  /// </summary>
+  /// <param name="text">The text to measure</param>
+  /// <param name="fontFamily">CSS font family list, or null for sans-serif</param>
+  /// <param name="fontSize">Font size in pixels</param>
+  /// <param name="fontWeight">Font weight, from 1 to 1000</param>
+  /// <param name="letterSpacing">Extra spacing between letters in pixels</param>
+  /// <param name="lineHeight">Line height as a multiple of the font size, or null for the default</param>
+  /// <param name="maxWidth">Width in pixels to wrap the text at, or null to not wrap</param>
+  /// <returns>The size of the text and the metrics of every line, wider than <paramref name="maxWidth"/> when a word does not fit it</returns>
+  /// <exception cref="InvalidOperationException">Thrown when measuring fails</exception>
+  internal static unsafe Models.TextMeasurement MeasureText(
+      string text,
+      string? fontFamily,
+      float fontSize,
+      float fontWeight,
+      float letterSpacing,
+      float? lineHeight,
+      float? maxWidth)
+  {
+    byte[] textBytes = Encoding.UTF8.GetBytes(text + '\0');
+    byte[]? familyBytes = fontFamily is null ? null : Encoding.UTF8.GetBytes(fontFamily + '\0');
+
+    var lines = new LineMetrics[8];
+    TextMetrics metrics = default;
+
+    while (true)
+    {
+      bool success;
+      fixed (byte* textPtr = textBytes)
+      fixed (byte* familyPtr = familyBytes)
+      fixed (LineMetrics* linesPtr = lines)
+      {
+        var style = new TextMeasureStyle
+        {
+          font_family = familyPtr,
+          font_size = fontSize,
+          font_weight = fontWeight,
+          letter_spacing = letterSpacing,
+          line_height = lineHeight ?? 0,
+        };
+
+        success = NativeBindings.measure_text(textPtr, style, maxWidth ?? -1, linesPtr, (ulong)lines.Length, &metrics);
+      }
+
+      if (!success)
+      {
+        var errorMsg = GetLastError();
+        throw new InvalidOperationException($"Failed to measure text: {errorMsg}");
+      }
+
+      if (metrics.line_count <= lines.Length)
+      {
+        break;
+      }
+
+      lines = new LineMetrics[metrics.line_count];
+    }
+
+    var lineMeasurements = new Models.TextLineMeasurement[metrics.line_count];
+    for (int i = 0; i < lineMeasurements.Length; i++)
+    {
+      var line = lines[i];
+      lineMeasurements[i] = new Models.TextLineMeasurement(
+        line.width,
+        line.height,
+        line.baseline,
+        line.ascent,
+        line.descent,
+        (int)line.start,
+        (int)line.end);
+    }
+
+    return new Models.TextMeasurement(metrics.width, metrics.height, lineMeasurements);
   }
 
   /// <summary>
@@ -70,33 +356,359 @@ internal class Renderer
       device_pixel_ratio = devicePixelRatio,
     };
 
-    byte[] nodeBytes = Encoding.UTF8.GetBytes(nodeJson + '\0');
+    // The string is passed as UTF-16 as-is, rather than copied to null-terminated UTF-8.
+    ulong size;
+    fixed (char* nodePtr = nodeJson)
+    {
+      size = NativeBindings.render_utf16_calculate_buffer_size_with_format((ushort*)nodePtr, (nuint)nodeJson.Length, viewport, format);
+    }
+
+    ThrowIfPaintFailed();
+
+    if (size == 0)
+    {
+      var errorMsg = GetLastError();
+      throw new InvalidOperationException($"Failed to calculate buffer size for rendering: {errorMsg}");
+    }
+
+    byte[] buffer = new byte[size];
+    bool success;
+    fixed (char* nodePtr = nodeJson)
+    fixed (byte* bufferPtr = buffer)
+    {
+      success = NativeBindings.render_utf16_to_buffer_with_format((ushort*)nodePtr, (nuint)nodeJson.Length, viewport, format, bufferPtr, size);
+    }
+
+    ThrowIfPaintFailed();
+
+    if (!success)
+    {
+      var errorMsg = GetLastError();
+      throw new InvalidOperationException($"Failed to render node: {errorMsg}");
+    }
+
+    return buffer;
+  }
+
+  /// <summary>
+  /// Parses a node tree once, to be rendered with <see cref="RenderParsed"/>.
+  /// </summary>
+  /// <param name="nodeJson">JSON string representing the node to parse</param>
+  /// <returns>The parsed tree, which releases its native handle when disposed</returns>
+  /// <exception cref="InvalidOperationException">Thrown when the node is invalid</exception>
+  internal static unsafe Models.ParsedNode Parse(string nodeJson)
+  {
+    NodeHandle* handle;
+    fixed (char* nodePtr = nodeJson)
+    {
+      handle = NativeBindings.takumi_node_parse_utf16((ushort*)nodePtr, (nuint)nodeJson.Length);
+    }
+
+    if (handle == null)
+    {
+      var errorMsg = GetLastError();
+      throw new InvalidOperationException($"Failed to parse node: {errorMsg}");
+    }
+
+    return new Models.ParsedNode((nint)handle);
+  }
+
+  /// <summary>
+  /// Parses a node tree read from bytes once, to be rendered with <see cref="RenderParsed"/>.
+  /// </summary>
+  /// <param name="data">The encoded node tree, optionally gzip or zstd compressed</param>
+  /// <param name="nodeFormat">How the node tree is encoded, as the value of a <see cref="Models.NodeFormat"/></param>
+  /// <returns>The parsed tree, which releases its native handle when disposed</returns>
+  /// <exception cref="InvalidOperationException">Thrown when the node is invalid</exception>
+  internal static unsafe Models.ParsedNode ParseBytes(ReadOnlySpan<byte> data, byte nodeFormat)
+  {
+    NodeHandle* handle;
+    fixed (byte* dataPtr = data)
+    {
+      handle = NativeBindings.takumi_node_parse_bytes(dataPtr, (nuint)data.Length, nodeFormat);
+    }
+
+    if (handle == null)
+    {
+      var errorMsg = GetLastError();
+      throw new InvalidOperationException($"Failed to parse node: {errorMsg}");
+    }
+
+    return new Models.ParsedNode((nint)handle);
+  }
+
+  /// <summary>
+  /// Renders a node tree parsed with <see cref="Parse"/> to a byte array in the specified image format.
+  /// </summary>
+  /// <param name="handle">The native handle of the parsed tree</param>
+  /// <param name="width">Viewport width in pixels, or null for auto</param>
+  /// <param name="height">Viewport height in pixels, or null for auto</param>
+  /// <param name="fontSize">Font size in pixels (default: 16)</param>
+  /// <param name="devicePixelRatio">Device pixel ratio (default: 1)</param>
+  /// <param name="format">Output image format (default: PNG)</param>
+  /// <returns>The encoded image data</returns>
+  /// <exception cref="InvalidOperationException">Thrown when rendering fails</exception>
+  internal static unsafe byte[] RenderParsed(
+      nint handle,
+      int? width = null,
+      int? height = null,
+      float fontSize = 16f,
+      float devicePixelRatio = 1f,
+      ImageFormat format = ImageFormat.WebP)
+  {
+    var viewport = new Viewport
+    {
+      width = width ?? -1,
+      height = height ?? -1,
+      font_size = fontSize,
+      device_pixel_ratio = devicePixelRatio,
+    };
+
+    var nodeHandle = (NodeHandle*)handle;
+    ulong size = NativeBindings.takumi_node_calculate_buffer_size_with_format(nodeHandle, viewport, format);
+
+    ThrowIfPaintFailed();
+
+    if (size == 0)
+    {
+      var errorMsg = GetLastError();
+      throw new InvalidOperationException($"Failed to calculate buffer size for rendering: {errorMsg}");
+    }
+
+    byte[] buffer = new byte[size];
+    bool success;
+    fixed (byte* bufferPtr = buffer)
+    {
+      success = NativeBindings.takumi_node_render_to_buffer_with_format(nodeHandle, viewport, format, bufferPtr, size);
+    }
+
+    ThrowIfPaintFailed();
+
+    if (!success)
+    {
+      var errorMsg = GetLastError();
+      throw new InvalidOperationException($"Failed to render node: {errorMsg}");
+    }
+
+    return buffer;
+  }
+
+  /// <summary>
+  /// Picks the font sizes of the text nodes with <c>Fit</c> set in a node tree parsed with <see cref="Parse"/>,
+  /// as rendering it with the same viewport does.
+  /// </summary>
+  /// <param name="handle">The native handle of the parsed tree</param>
+  /// <param name="width">Viewport width in pixels, or null for auto</param>
+  /// <param name="height">Viewport height in pixels, or null for auto</param>
+  /// <param name="fontSize">Font size in pixels (default: 16)</param>
+  /// <param name="devicePixelRatio">Device pixel ratio (default: 1)</param>
+  /// <returns>The font sizes in pixels, in document order of the fitted text nodes</returns>
+  /// <exception cref="InvalidOperationException">Thrown when fitting fails</exception>
+  internal static unsafe float[] FitFontSizes(
+      nint handle,
+      int? width = null,
+      int? height = null,
+      float fontSize = 16f,
+      float devicePixelRatio = 1f)
+  {
+    var viewport = new Viewport
+    {
+      width = width ?? -1,
+      height = height ?? -1,
+      font_size = fontSize,
+      device_pixel_ratio = devicePixelRatio,
+    };
+
+    var nodeHandle = (NodeHandle*)handle;
+    float[] fontSizes = new float[8];
+
+    while (true)
+    {
+      ulong count;
+      bool success;
+      fixed (float* fontSizesPtr = fontSizes)
+      {
+        success = NativeBindings.takumi_node_fit_font_sizes(nodeHandle, viewport, fontSizesPtr, (ulong)fontSizes.Length, &count);
+      }
+
+      if (!success)
+      {
+        var errorMsg = GetLastError();
+        throw new InvalidOperationException($"Failed to fit text: {errorMsg}");
+      }
+
+      if (count <= (ulong)fontSizes.Length)
+      {
+        return fontSizes[..(int)count];
+      }
+
+      fontSizes = new float[count];
+    }
+  }
+
+  /// <summary>
+  /// Registers a template under a name, replacing the template previously registered with it.
+  /// </summary>
+  /// <param name="name">The name the template is rendered by</param>
+  /// <param name="templateJson">JSON string representing the node tree, with <c>{{variable}}</c> placeholders in its strings</param>
+  /// <exception cref="InvalidOperationException">Thrown when the template is not valid JSON</exception>
+  public static unsafe void RegisterTemplate(string name, string templateJson)
+  {
+    byte[] nameBytes = Encoding.UTF8.GetBytes(name + '\0');
+    byte[] templateBytes = Encoding.UTF8.GetBytes(templateJson + '\0');
+
+    bool success;
+    fixed (byte* namePtr = nameBytes)
+    fixed (byte* templatePtr = templateBytes)
+    {
+      success = NativeBindings.template_register(namePtr, templatePtr);
+    }
+
+    if (!success)
+    {
+      var errorMsg = GetLastError();
+      throw new InvalidOperationException($"Failed to register template: {errorMsg}");
+    }
+  }
+
+  /// <summary>
+  /// Removes the template registered under a name.
+  /// </summary>
+  /// <param name="name">The name of the template</param>
+  /// <returns>False when no template was registered with the name</returns>
+  public static unsafe bool UnregisterTemplate(string name)
+  {
+    byte[] nameBytes = Encoding.UTF8.GetBytes(name + '\0');
+
+    fixed (byte* namePtr = nameBytes)
+    {
+      return NativeBindings.template_unregister(namePtr);
+    }
+  }
+
+  /// <summary>
+  /// Renders a registered template to a byte array in the specified image format.
+  /// </summary>
+  /// <param name="name">The name of the template</param>
+  /// <param name="variablesJson">JSON object with a value for every placeholder of the template</param>
+  /// <param name="width">Viewport width in pixels, or null for auto</param>
+  /// <param name="height">Viewport height in pixels, or null for auto</param>
+  /// <param name="fontSize">Font size in pixels (default: 16)</param>
+  /// <param name="devicePixelRatio">Device pixel ratio (default: 1)</param>
+  /// <param name="format">Output image format (default: PNG)</param>
+  /// <returns>The encoded image data</returns>
+  /// <exception cref="InvalidOperationException">Thrown when rendering fails</exception>
+  internal static unsafe byte[] RenderTemplate(
+      string name,
+      string variablesJson,
+      int? width = null,
+      int? height = null,
+      float fontSize = 16f,
+      float devicePixelRatio = 1f,
+      ImageFormat format = ImageFormat.WebP)
+  {
+    var viewport = new Viewport
+    {
+      width = width ?? -1,
+      height = height ?? -1,
+      font_size = fontSize,
+      device_pixel_ratio = devicePixelRatio,
+    };
+
+    byte[] nameBytes = Encoding.UTF8.GetBytes(name + '\0');
+    byte[] variablesBytes = Encoding.UTF8.GetBytes(variablesJson + '\0');
+
+    ulong size;
+    fixed (byte* namePtr = nameBytes)
+    fixed (byte* variablesPtr = variablesBytes)
+    {
+      size = NativeBindings.render_template_calculate_buffer_size_with_format(namePtr, variablesPtr, viewport, format);
+    }
+
+    ThrowIfPaintFailed();
+
+    if (size == 0)
+    {
+      var errorMsg = GetLastError();
+      throw new InvalidOperationException($"Failed to calculate buffer size for rendering: {errorMsg}");
+    }
+
+    byte[] buffer = new byte[size];
+    bool success;
+    fixed (byte* namePtr = nameBytes)
+    fixed (byte* variablesPtr = variablesBytes)
+    fixed (byte* bufferPtr = buffer)
+    {
+      success = NativeBindings.render_template_to_buffer_with_format(namePtr, variablesPtr, viewport, format, bufferPtr, size);
+    }
+
+    ThrowIfPaintFailed();
+
+    if (!success)
+    {
+      var errorMsg = GetLastError();
+      throw new InvalidOperationException($"Failed to render template: {errorMsg}");
+    }
+
+    return buffer;
+  }
+
+  /// <summary>
+  /// Renders a node tree read from bytes to a byte array in the specified image format.
+  /// </summary>
+  /// <param name="data">The encoded node tree, optionally gzip or zstd compressed</param>
+  /// <param name="nodeFormat">How the node tree is encoded, as the value of a <see cref="Models.NodeFormat"/></param>
+  /// <param name="width">Viewport width in pixels, or null for auto</param>
+  /// <param name="height">Viewport height in pixels, or null for auto</param>
+  /// <param name="fontSize">Font size in pixels (default: 16)</param>
+  /// <param name="devicePixelRatio">Device pixel ratio (default: 1)</param>
+  /// <param name="format">Output image format (default: PNG)</param>
+  /// <returns>The encoded image data</returns>
+  /// <exception cref="InvalidOperationException">Thrown when rendering fails</exception>
+  internal static unsafe byte[] RenderBytes(
+      ReadOnlySpan<byte> data,
+      byte nodeFormat,
+      int? width = null,
+      int? height = null,
+      float fontSize = 16f,
+      float devicePixelRatio = 1f,
+      ImageFormat format = ImageFormat.WebP)
+  {
+    var viewport = new Viewport
+    {
+      width = width ?? -1,
+      height = height ?? -1,
+      font_size = fontSize,
+      device_pixel_ratio = devicePixelRatio,
+    };
 
     ulong size;
-    fixed (byte* nodePtr = nodeBytes)
+    fixed (byte* dataPtr = data)
     {
-      size = NativeBindings.render_calculate_buffer_size_with_format(nodePtr, viewport, format);
+      size = NativeBindings.render_bytes_calculate_buffer_size_with_format(dataPtr, (nuint)data.Length, nodeFormat, viewport, format);
     }
 
+    ThrowIfPaintFailed();
+
     if (size == 0)
     {
-      var errorPtr = (nint)NativeBindings.get_last_error();
-      var errorMsg = errorPtr != 0 ? System.Runtime.InteropServices.Marshal.PtrToStringUTF8(errorPtr) : "Unknown error";
+      var errorMsg = GetLastError();
       throw new InvalidOperationException($"Failed to calculate buffer size for rendering: {errorMsg}");
     }
 
     byte[] buffer = new byte[size];
     bool success;
-    fixed (byte* nodePtr = nodeBytes)
+    fixed (byte* dataPtr = data)
     fixed (byte* bufferPtr = buffer)
     {
-      success = NativeBindings.render_to_buffer_with_format(nodePtr, viewport, format, bufferPtr, size);
+      success = NativeBindings.render_bytes_to_buffer_with_format(dataPtr, (nuint)data.Length, nodeFormat, viewport, format, bufferPtr, size);
     }
 
+    ThrowIfPaintFailed();
+
     if (!success)
     {
-      var errorPtr = (nint)NativeBindings.get_last_error();
-      var errorMsg = errorPtr != 0 ? System.Runtime.InteropServices.Marshal.PtrToStringUTF8(errorPtr) : "Unknown error";
+      var errorMsg = GetLastError();
       throw new InvalidOperationException($"Failed to render node: {errorMsg}");
     }
 
@@ -152,4 +764,10 @@ internal class Renderer
     byte[] data = Render(nodeJson, width, height, fontSize, devicePixelRatio, format);
     await File.WriteAllBytesAsync(outputPath, data, cancellationToken);
   }
+
+  internal static string GetLastError()
+  {
+    var errorPtr = (nint)NativeBindings.get_last_error();
+    return errorPtr != 0 ? System.Runtime.InteropServices.Marshal.PtrToStringUTF8(errorPtr) ?? "Unknown error" : "Unknown error";
+  }
 }
\ No newline at end of file
@@ -10,12 +10,147 @@ using System.Text.Json.Serialization;
 
 namespace TakumiSharp.Models;
 
+[JsonPolymorphic(TypeDiscriminatorPropertyName = "type")]
+[JsonDerivedType(typeof(LineGeometry), "line")]
+[JsonDerivedType(typeof(PolylineGeometry), "polyline")]
+[JsonDerivedType(typeof(RectGeometry), "rect")]
+[JsonDerivedType(typeof(CircleGeometry), "circle")]
+[JsonDerivedType(typeof(ArcGeometry), "arc")]
+[JsonDerivedType(typeof(PathGeometry), "path")]
+public abstract class GeometryKind { }
+
+[JsonPolymorphic(TypeDiscriminatorPropertyName = "type")]
+[JsonDerivedType(typeof(LinearGradient), "linear")]
+[JsonDerivedType(typeof(RadialGradient), "radial")]
+public abstract class GradientKind { }
+
 [JsonPolymorphic(TypeDiscriminatorPropertyName = "type")]
 [JsonDerivedType(typeof(ContainerNode), "container")]
 [JsonDerivedType(typeof(ImageNode), "image")]
 [JsonDerivedType(typeof(TextNode), "text")]
+[JsonDerivedType(typeof(SvgNode), "svg")]
+[JsonDerivedType(typeof(ShapeNode), "shape")]
+[JsonDerivedType(typeof(QrCodeNode), "qrcode")]
+[JsonDerivedType(typeof(BarcodeNode), "barcode")]
+[JsonDerivedType(typeof(PaintNode), "paint")]
+[JsonDerivedType(typeof(CodeBlockNode), "codeblock")]
+[JsonDerivedType(typeof(MarkdownNode), "markdown")]
+[JsonDerivedType(typeof(HtmlNode), "html")]
+[JsonDerivedType(typeof(FragmentNode), "fragment")]
 public abstract class NodeKind { }
 
+public class ArcGeometry : GeometryKind
+{
+    [JsonPropertyName("cx")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public float Cx { get; set; }
+
+    [JsonPropertyName("cy")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public float Cy { get; set; }
+
+    [JsonPropertyName("r")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public float R { get; set; }
+
+    [JsonPropertyName("startAngle")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public float StartAngle { get; set; }
+
+    [JsonPropertyName("endAngle")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public float EndAngle { get; set; }
+
+}
+
+public class BarcodeNode : NodeKind
+{
+    [JsonPropertyName("preset")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public Style? Preset { get; set; }
+
+    [JsonPropertyName("style")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public Style? Style { get; set; }
+
+    [JsonPropertyName("data")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public string Data { get; set; } = string.Empty;
+
+    [JsonPropertyName("format")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public string Format { get; set; } = string.Empty;
+
+    [JsonPropertyName("quietZone")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public uint? QuietZone { get; set; }
+
+    [JsonPropertyName("tw")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public TailwindValues? Tw { get; set; }
+
+}
+
+public class CircleGeometry : GeometryKind
+{
+    [JsonPropertyName("cx")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public float Cx { get; set; }
+
+    [JsonPropertyName("cy")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public float Cy { get; set; }
+
+    [JsonPropertyName("r")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public float R { get; set; }
+
+}
+
+public class CodeBlockNode : NodeKind
+{
+    [JsonPropertyName("preset")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public Style? Preset { get; set; }
+
+    [JsonPropertyName("style")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public Style? Style { get; set; }
+
+    [JsonPropertyName("code")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public string Code { get; set; } = string.Empty;
+
+    [JsonPropertyName("language")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public string? Language { get; set; }
+
+    [JsonPropertyName("theme")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public string? Theme { get; set; }
+
+    [JsonPropertyName("lineNumbers")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public bool LineNumbers { get; set; }
+
+    [JsonPropertyName("firstLineNumber")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public uint? FirstLineNumber { get; set; }
+
+    [JsonPropertyName("tabWidth")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public uint? TabWidth { get; set; }
+
+    [JsonPropertyName("longLines")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public string? LongLines { get; set; }
+
+    [JsonPropertyName("tw")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public TailwindValues? Tw { get; set; }
+
+}
+
 public class ContainerNode : NodeKind
 {
     [JsonPropertyName("preset")]
@@ -34,6 +169,66 @@ public class ContainerNode : NodeKind
     [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
     public TailwindValues? Tw { get; set; }
 
+    [JsonPropertyName("lang")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public string? Lang { get; set; }
+
+    [JsonPropertyName("fragments")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public Dictionary<string, NodeKind>? Fragments { get; set; }
+
+}
+
+public class FragmentNode : NodeKind
+{
+    [JsonPropertyName("id")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public string Id { get; set; } = string.Empty;
+
+    [JsonPropertyName("variables")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public Dictionary<string, object>? Variables { get; set; }
+
+    [JsonPropertyName("style")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public Style? Style { get; set; }
+
+    [JsonPropertyName("tw")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public string? Tw { get; set; }
+
+}
+
+public class GradientStop
+{
+    [JsonPropertyName("offset")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public float Offset { get; set; }
+
+    [JsonPropertyName("color")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public string Color { get; set; } = string.Empty;
+
+}
+
+public class HtmlNode : NodeKind
+{
+    [JsonPropertyName("preset")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public Style? Preset { get; set; }
+
+    [JsonPropertyName("style")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public Style? Style { get; set; }
+
+    [JsonPropertyName("html")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public string Html { get; set; } = string.Empty;
+
+    [JsonPropertyName("tw")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public TailwindValues? Tw { get; set; }
+
 }
 
 public class ImageNode : NodeKind
@@ -64,6 +259,370 @@ public class ImageNode : NodeKind
 
 }
 
+public class LineGeometry : GeometryKind
+{
+    [JsonPropertyName("x1")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public float X1 { get; set; }
+
+    [JsonPropertyName("y1")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public float Y1 { get; set; }
+
+    [JsonPropertyName("x2")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public float X2 { get; set; }
+
+    [JsonPropertyName("y2")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public float Y2 { get; set; }
+
+}
+
+public class LinearGradient : GradientKind
+{
+    [JsonPropertyName("id")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public string Id { get; set; } = string.Empty;
+
+    [JsonPropertyName("x1")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public float X1 { get; set; }
+
+    [JsonPropertyName("y1")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public float Y1 { get; set; }
+
+    [JsonPropertyName("x2")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public float X2 { get; set; }
+
+    [JsonPropertyName("y2")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public float Y2 { get; set; }
+
+    [JsonPropertyName("stops")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public List<GradientStop> Stops { get; set; }
+
+}
+
+public class MarkdownNode : NodeKind
+{
+    [JsonPropertyName("preset")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public Style? Preset { get; set; }
+
+    [JsonPropertyName("style")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public Style? Style { get; set; }
+
+    [JsonPropertyName("markdown")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public string Markdown { get; set; } = string.Empty;
+
+    [JsonPropertyName("styles")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public MarkdownStyles? Styles { get; set; }
+
+    [JsonPropertyName("tw")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public TailwindValues? Tw { get; set; }
+
+}
+
+public class MarkdownStyles
+{
+    [JsonPropertyName("heading1")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public Style? Heading1 { get; set; }
+
+    [JsonPropertyName("heading2")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public Style? Heading2 { get; set; }
+
+    [JsonPropertyName("heading3")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public Style? Heading3 { get; set; }
+
+    [JsonPropertyName("heading4")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public Style? Heading4 { get; set; }
+
+    [JsonPropertyName("heading5")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public Style? Heading5 { get; set; }
+
+    [JsonPropertyName("heading6")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public Style? Heading6 { get; set; }
+
+    [JsonPropertyName("paragraph")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public Style? Paragraph { get; set; }
+
+    [JsonPropertyName("list")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public Style? List { get; set; }
+
+    [JsonPropertyName("listItem")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public Style? ListItem { get; set; }
+
+    [JsonPropertyName("listMarker")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public Style? ListMarker { get; set; }
+
+    [JsonPropertyName("codeBlock")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public Style? CodeBlock { get; set; }
+
+    [JsonPropertyName("emphasis")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public Style? Emphasis { get; set; }
+
+    [JsonPropertyName("strong")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public Style? Strong { get; set; }
+
+    [JsonPropertyName("code")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public Style? Code { get; set; }
+
+    [JsonPropertyName("link")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public Style? Link { get; set; }
+
+}
+
+public class PaintNode : NodeKind
+{
+    [JsonPropertyName("preset")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public Style? Preset { get; set; }
+
+    [JsonPropertyName("style")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public Style? Style { get; set; }
+
+    [JsonPropertyName("name")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public string Name { get; set; } = string.Empty;
+
+    [JsonPropertyName("tw")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public TailwindValues? Tw { get; set; }
+
+}
+
+public class PathGeometry : GeometryKind
+{
+    [JsonPropertyName("d")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public string D { get; set; } = string.Empty;
+
+}
+
+public class Point
+{
+    [JsonPropertyName("x")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public float X { get; set; }
+
+    [JsonPropertyName("y")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public float Y { get; set; }
+
+}
+
+public class PolylineGeometry : GeometryKind
+{
+    [JsonPropertyName("points")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public List<Point> Points { get; set; }
+
+    [JsonPropertyName("closed")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public bool Closed { get; set; }
+
+}
+
+public class QrCodeNode : NodeKind
+{
+    [JsonPropertyName("preset")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public Style? Preset { get; set; }
+
+    [JsonPropertyName("style")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public Style? Style { get; set; }
+
+    [JsonPropertyName("data")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public string Data { get; set; } = string.Empty;
+
+    [JsonPropertyName("errorCorrection")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public string? ErrorCorrection { get; set; }
+
+    [JsonPropertyName("quietZone")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public uint? QuietZone { get; set; }
+
+    [JsonPropertyName("tw")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public TailwindValues? Tw { get; set; }
+
+}
+
+public class RadialGradient : GradientKind
+{
+    [JsonPropertyName("id")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public string Id { get; set; } = string.Empty;
+
+    [JsonPropertyName("cx")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public float Cx { get; set; }
+
+    [JsonPropertyName("cy")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public float Cy { get; set; }
+
+    [JsonPropertyName("r")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public float R { get; set; }
+
+    [JsonPropertyName("stops")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public List<GradientStop> Stops { get; set; }
+
+}
+
+public class RectGeometry : GeometryKind
+{
+    [JsonPropertyName("x")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public float X { get; set; }
+
+    [JsonPropertyName("y")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public float Y { get; set; }
+
+    [JsonPropertyName("width")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public float Width { get; set; }
+
+    [JsonPropertyName("height")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public float Height { get; set; }
+
+    [JsonPropertyName("radius")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public float? Radius { get; set; }
+
+}
+
+public class Shape
+{
+    [JsonPropertyName("geometry")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public GeometryKind Geometry { get; set; }
+
+    [JsonPropertyName("fill")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public string? Fill { get; set; }
+
+    [JsonPropertyName("stroke")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public string? Stroke { get; set; }
+
+    [JsonPropertyName("strokeWidth")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public float? StrokeWidth { get; set; }
+
+    [JsonPropertyName("strokeDasharray")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public List<float>? StrokeDasharray { get; set; }
+
+    [JsonPropertyName("strokeDashoffset")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public float? StrokeDashoffset { get; set; }
+
+    [JsonPropertyName("strokeLinecap")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public string? StrokeLinecap { get; set; }
+
+    [JsonPropertyName("strokeLinejoin")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public string? StrokeLinejoin { get; set; }
+
+    [JsonPropertyName("opacity")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public float? Opacity { get; set; }
+
+}
+
+public class ShapeNode : NodeKind
+{
+    [JsonPropertyName("preset")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public Style? Preset { get; set; }
+
+    [JsonPropertyName("style")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public Style? Style { get; set; }
+
+    [JsonPropertyName("shapes")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public List<Shape> Shapes { get; set; }
+
+    [JsonPropertyName("gradients")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public List<GradientKind>? Gradients { get; set; }
+
+    [JsonPropertyName("tw")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public TailwindValues? Tw { get; set; }
+
+}
+
+public class SvgNode : NodeKind
+{
+    [JsonPropertyName("preset")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public Style? Preset { get; set; }
+
+    [JsonPropertyName("style")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public Style? Style { get; set; }
+
+    [JsonPropertyName("svg")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public string Svg { get; set; } = string.Empty;
+
+    [JsonPropertyName("tw")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public TailwindValues? Tw { get; set; }
+
+}
+
+public class TextFit
+{
+    [JsonPropertyName("minFontSize")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public float MinFontSize { get; set; }
+
+    [JsonPropertyName("maxFontSize")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public float MaxFontSize { get; set; }
+
+    [JsonPropertyName("maxLines")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public uint? MaxLines { get; set; }
+
+}
+
 public class TextNode : NodeKind
 {
     [JsonPropertyName("preset")]
@@ -78,6 +637,38 @@ public class TextNode : NodeKind
     [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
     public string Text { get; set; } = string.Empty;
 
+    [JsonPropertyName("spans")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public List<TextSpan>? Spans { get; set; }
+
+    [JsonPropertyName("tw")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public TailwindValues? Tw { get; set; }
+
+    [JsonPropertyName("fit")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public TextFit? Fit { get; set; }
+
+    [JsonPropertyName("lang")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public string? Lang { get; set; }
+
+}
+
+public class TextSpan
+{
+    [JsonPropertyName("style")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public Style? Style { get; set; }
+
+    [JsonPropertyName("text")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public string Text { get; set; } = string.Empty;
+
+    [JsonPropertyName("image")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public ImageNode? Image { get; set; }
+
     [JsonPropertyName("tw")]
     [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
     public TailwindValues? Tw { get; set; }
@@ -541,6 +1132,10 @@ public class Style
     [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
     public object? WebkitTextStrokeColor { get; set; }
 
+    [JsonPropertyName("webkitTextFillColor")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public object? WebkitTextFillColor { get; set; }
+
     [JsonPropertyName("textShadow")]
     [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
     public object? TextShadow { get; set; }
@@ -605,6 +1200,22 @@ public class Style
     [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
     public object? TextWrap { get; set; }
 
+    [JsonPropertyName("hyphens")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public object? Hyphens { get; set; }
+
+    [JsonPropertyName("direction")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public object? Direction { get; set; }
+
+    [JsonPropertyName("writingMode")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public object? WritingMode { get; set; }
+
+    [JsonPropertyName("textOrientation")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public object? TextOrientation { get; set; }
+
 }
 
 /// <summary>
@@ -0,0 +1,208 @@
+using System.Text;
+using TakumiSharp.Bindings;
+
+namespace TakumiSharp.Models;
+
+/// <summary>
+/// Builds a node tree natively call by call, without serializing it to JSON. Every style
+/// property and Tailwind class is checked when it is set.
+/// </summary>
+/// <example>
+/// <code>
+/// using var root = NodeBuilder.Container().Tw("flex flex-col p-8");
+/// root.Append(NodeBuilder.Text("Hello").Style("fontSize", 48));
+/// using var node = root.Build();
+/// </code>
+/// </example>
+public sealed class NodeBuilder : IDisposable
+{
+  private nint handle;
+
+  private NodeBuilder(nint handle) => this.handle = handle;
+
+  ~NodeBuilder() => Free();
+
+  /// <summary>
+  /// Creates an empty container node.
+  /// </summary>
+  public static unsafe NodeBuilder Container() => new((nint)NativeBindings.takumi_builder_new_container());
+
+  /// <summary>
+  /// Creates a text node.
+  /// </summary>
+  /// <param name="text">The text content to be rendered</param>
+  public static unsafe NodeBuilder Text(string text)
+  {
+    byte[] textBytes = Encoding.UTF8.GetBytes(text + '\0');
+
+    fixed (byte* textPtr = textBytes)
+    {
+      return FromNative(NativeBindings.takumi_builder_new_text(textPtr));
+    }
+  }
+
+  /// <summary>
+  /// Creates an image node.
+  /// </summary>
+  /// <param name="src">The source URL or path to the image</param>
+  /// <param name="width">The width of the image in pixels, or null for the size of the image</param>
+  /// <param name="height">The height of the image in pixels, or null for the size of the image</param>
+  public static unsafe NodeBuilder Image(string src, float? width = null, float? height = null)
+  {
+    byte[] srcBytes = Encoding.UTF8.GetBytes(src + '\0');
+
+    fixed (byte* srcPtr = srcBytes)
+    {
+      return FromNative(NativeBindings.takumi_builder_new_image(srcPtr, width ?? -1, height ?? -1));
+    }
+  }
+
+  /// <summary>
+  /// Sets a style property to a CSS value, like <c>Style("fontSize", "2rem")</c>.
+  /// </summary>
+  /// <param name="property">The property, named like in <see cref="Models.Style"/> JSON, e.g. "fontSize"</param>
+  /// <param name="value">The CSS value</param>
+  /// <returns>This builder</returns>
+  /// <exception cref="InvalidOperationException">Thrown when the property is unknown, the value invalid, or the property is natively resolved, like "writingMode", on an image</exception>
+  public unsafe NodeBuilder Style(string property, string value)
+  {
+    byte[] propertyBytes = Encoding.UTF8.GetBytes(property + '\0');
+    byte[] valueBytes = Encoding.UTF8.GetBytes(value + '\0');
+
+    bool success;
+    fixed (byte* propertyPtr = propertyBytes)
+    fixed (byte* valuePtr = valueBytes)
+    {
+      success = NativeBindings.takumi_builder_set_style(Native, propertyPtr, valuePtr);
+    }
+
+    ThrowIfFailed(success, "Failed to set style");
+    return this;
+  }
+
+  /// <summary>
+  /// Sets a style property to a number, like <c>Style("flexGrow", 1)</c>. Lengths are in pixels.
+  /// </summary>
+  /// <param name="property">The property, named like in <see cref="Models.Style"/> JSON, e.g. "fontSize"</param>
+  /// <param name="value">The number</param>
+  /// <returns>This builder</returns>
+  /// <exception cref="InvalidOperationException">Thrown when the property is unknown, the value invalid, or the property is natively resolved, like "writingMode", on an image</exception>
+  public unsafe NodeBuilder Style(string property, float value)
+  {
+    byte[] propertyBytes = Encoding.UTF8.GetBytes(property + '\0');
+
+    bool success;
+    fixed (byte* propertyPtr = propertyBytes)
+    {
+      success = NativeBindings.takumi_builder_set_style_number(Native, propertyPtr, value);
+    }
+
+    ThrowIfFailed(success, "Failed to set style");
+    return this;
+  }
+
+  /// <summary>
+  /// Sets the Tailwind classes, replacing the ones set before.
+  /// </summary>
+  /// <param name="classes">The Tailwind classes, separated by spaces</param>
+  /// <returns>This builder</returns>
+  /// <exception cref="InvalidOperationException">Thrown when a class is unknown</exception>
+  public unsafe NodeBuilder Tw(string classes)
+  {
+    byte[] classesBytes = Encoding.UTF8.GetBytes(classes + '\0');
+
+    bool success;
+    fixed (byte* classesPtr = classesBytes)
+    {
+      success = NativeBindings.takumi_builder_set_tw(Native, classesPtr);
+    }
+
+    ThrowIfFailed(success, "Failed to set Tailwind classes");
+    return this;
+  }
+
+  /// <summary>
+  /// Appends a node as the last child of this container. The child belongs to this
+  /// container afterwards and must not be used again.
+  /// </summary>
+  /// <param name="child">The child node</param>
+  /// <returns>This builder</returns>
+  /// <exception cref="InvalidOperationException">Thrown when this node is not a container</exception>
+  public unsafe NodeBuilder Append(NodeBuilder child)
+  {
+    bool success = NativeBindings.takumi_builder_append_child(Native, child.Native);
+    ThrowIfFailed(success, "Failed to append child");
+
+    child.handle = 0;
+    GC.SuppressFinalize(child);
+    return this;
+  }
+
+  /// <summary>
+  /// Builds the tree into a <see cref="ParsedNode"/> to render. The builder and its children
+  /// must not be used again.
+  /// </summary>
+  /// <returns>The built tree, which must be disposed</returns>
+  /// <exception cref="InvalidOperationException">Thrown when the tree is invalid</exception>
+  public unsafe ParsedNode Build()
+  {
+    var builder = Native;
+    handle = 0;
+    GC.SuppressFinalize(this);
+
+    var node = NativeBindings.takumi_builder_build(builder);
+    if (node == null)
+    {
+      var errorMsg = Internal.Renderer.GetLastError();
+      throw new InvalidOperationException($"Failed to build node: {errorMsg}");
+    }
+
+    return new ParsedNode((nint)node);
+  }
+
+  /// <summary>
+  /// Releases the builder and its children, unless it was appended or built.
+  /// </summary>
+  public void Dispose()
+  {
+    Free();
+    GC.SuppressFinalize(this);
+  }
+
+  private unsafe Bindings.NodeBuilder* Native
+  {
+    get
+    {
+      ObjectDisposedException.ThrowIf(handle == 0, this);
+      return (Bindings.NodeBuilder*)handle;
+    }
+  }
+
+  private static unsafe NodeBuilder FromNative(Bindings.NodeBuilder* builder)
+  {
+    if (builder == null)
+    {
+      var errorMsg = Internal.Renderer.GetLastError();
+      throw new InvalidOperationException($"Failed to create node: {errorMsg}");
+    }
+
+    return new NodeBuilder((nint)builder);
+  }
+
+  private void ThrowIfFailed(bool success, string message)
+  {
+    // The handle must not be released by the finalizer during the native call.
+    GC.KeepAlive(this);
+
+    if (!success)
+    {
+      var errorMsg = Internal.Renderer.GetLastError();
+      throw new InvalidOperationException($"{message}: {errorMsg}");
+    }
+  }
+
+  private unsafe void Free()
+  {
+    NativeBindings.takumi_builder_free((Bindings.NodeBuilder*)Interlocked.Exchange(ref handle, 0));
+  }
+}
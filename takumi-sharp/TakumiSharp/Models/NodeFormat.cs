@@ -0,0 +1,20 @@
+namespace TakumiSharp.Models;
+
+/// <summary>
+/// How the bytes of a node tree passed to <see cref="Takumi.RenderBytes"/> are encoded.
+/// Gzip and zstd compressed bytes are decompressed first, whatever the format.
+/// MessagePack and CBOR trees may only hold what JSON can: maps need string keys, and binary
+/// values are rejected, so images are passed as strings like URLs or data URIs.
+/// </summary>
+public enum NodeFormat : byte
+{
+  Json = 0,
+  MessagePack = 1,
+  Cbor = 2
+}
+
+internal static class NodeFormatExtensions
+{
+  // The native library checks the value, so an undefined format fails instead of being guessed.
+  internal static byte ToInternalNodeFormat(this NodeFormat format) => (byte)format;
+}
@@ -0,0 +1,18 @@
+namespace TakumiSharp.Models;
+
+/// <summary>
+/// Draws a <see cref="PaintNode"/> registered with <see cref="Takumi.RegisterPainter"/>.
+/// </summary>
+/// <param name="area">Where the node's content box is in the rendered image</param>
+/// <param name="pixels">The RGBA pixels of the content box with straight alpha, row by row, starting out transparent</param>
+public delegate void Painter(PaintArea area, Span<byte> pixels);
+
+/// <summary>
+/// Where a paint node is drawn in the rendered image, in device pixels.
+/// </summary>
+/// <param name="X">The distance of the content box from the left edge of the image</param>
+/// <param name="Y">The distance of the content box from the top edge of the image</param>
+/// <param name="Width">The width of the content box and the pixel buffer</param>
+/// <param name="Height">The height of the content box and the pixel buffer</param>
+/// <param name="DevicePixelRatio">The device pixel ratio the image is rendered at</param>
+public readonly record struct PaintArea(float X, float Y, int Width, int Height, float DevicePixelRatio);
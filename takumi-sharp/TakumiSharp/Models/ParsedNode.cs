@@ -0,0 +1,96 @@
+using TakumiSharp.Bindings;
+
+namespace TakumiSharp.Models;
+
+/// <summary>
+/// A node tree parsed once with <see cref="Takumi.Parse(NodeKind)"/>, to be rendered many times
+/// without parsing it again. It is immutable, so it can be rendered from several threads at once
+/// while no fonts, images or other global state are being changed.
+/// </summary>
+public sealed class ParsedNode : IDisposable
+{
+  private nint handle;
+
+  internal ParsedNode(nint handle) => this.handle = handle;
+
+  ~ParsedNode() => Free();
+
+  /// <summary>
+  /// Renders the parsed tree to a byte array in the specified image format.
+  /// </summary>
+  /// <param name="width">Viewport width in pixels, or null for auto</param>
+  /// <param name="height">Viewport height in pixels, or null for auto</param>
+  /// <param name="fontSize">Font size in pixels (default: 16)</param>
+  /// <param name="devicePixelRatio">Device pixel ratio (default: 1)</param>
+  /// <param name="format">Output image format (default: WebP)</param>
+  /// <returns>The encoded image data</returns>
+  /// <exception cref="ObjectDisposedException">Thrown when the parsed tree was disposed</exception>
+  /// <exception cref="InvalidOperationException">Thrown when rendering fails</exception>
+  public byte[] Render(
+    int? width = null,
+    int? height = null,
+    float fontSize = 16f,
+    float devicePixelRatio = 1f,
+    ImageFormat format = ImageFormat.WebP)
+  {
+    ObjectDisposedException.ThrowIf(handle == 0, this);
+
+    byte[] data = Internal.Renderer.RenderParsed(
+      handle,
+      width: width,
+      height: height,
+      fontSize: fontSize,
+      devicePixelRatio: devicePixelRatio,
+      format: format.ToInternalImageFormat()
+    );
+
+    // The handle must not be released by the finalizer while it is being rendered.
+    GC.KeepAlive(this);
+    return data;
+  }
+
+  /// <summary>
+  /// Picks the font sizes of the text nodes with <c>Fit</c> set, as rendering the parsed tree with the
+  /// same viewport does, without rendering it.
+  /// </summary>
+  /// <param name="width">Viewport width in pixels, or null for auto</param>
+  /// <param name="height">Viewport height in pixels, or null for auto</param>
+  /// <param name="fontSize">Font size in pixels (default: 16)</param>
+  /// <param name="devicePixelRatio">Device pixel ratio (default: 1)</param>
+  /// <returns>The font sizes in pixels, in document order of the fitted text nodes</returns>
+  /// <exception cref="ObjectDisposedException">Thrown when the parsed tree was disposed</exception>
+  /// <exception cref="InvalidOperationException">Thrown when fitting fails</exception>
+  public float[] FitFontSizes(
+    int? width = null,
+    int? height = null,
+    float fontSize = 16f,
+    float devicePixelRatio = 1f)
+  {
+    ObjectDisposedException.ThrowIf(handle == 0, this);
+
+    float[] fontSizes = Internal.Renderer.FitFontSizes(
+      handle,
+      width: width,
+      height: height,
+      fontSize: fontSize,
+      devicePixelRatio: devicePixelRatio
+    );
+
+    GC.KeepAlive(this);
+    return fontSizes;
+  }
+
+  /// <summary>
+  /// Releases the parsed tree. It must not be rendered concurrently with this call.
+  /// </summary>
+  public void Dispose()
+  {
+    Free();
+    GC.SuppressFinalize(this);
+  }
+
+  private unsafe void Free()
+  {
+    NativeBindings.takumi_node_free((NodeHandle*)Interlocked.Exchange(ref handle, 0));
+  }
+}
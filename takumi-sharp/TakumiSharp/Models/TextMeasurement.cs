@@ -0,0 +1,21 @@
+namespace TakumiSharp.Models;
+
+/// <summary>
+/// The size of text measured with <see cref="Takumi.MeasureText"/>.
+/// </summary>
+/// <param name="Width">The width of the text box in pixels, the widest of the line widths, rounded up; wider than the maximum width when a word does not fit it</param>
+/// <param name="Height">The total height of all lines in pixels, rounded up</param>
+/// <param name="Lines">The metrics of every line the text wraps to</param>
+public record TextMeasurement(float Width, float Height, IReadOnlyList<TextLineMeasurement> Lines);
+
+/// <summary>
+/// The metrics of a single line of measured text.
+/// </summary>
+/// <param name="Width">The advance of the line in pixels, excluding trailing whitespace</param>
+/// <param name="Height">The height of the line in pixels</param>
+/// <param name="Baseline">The offset of the baseline from the top of the text in pixels</param>
+/// <param name="Ascent">The typographic ascent of the line</param>
+/// <param name="Descent">The typographic descent of the line</param>
+/// <param name="Start">The index of the first character of the line</param>
+/// <param name="End">The index after the last character of the line</param>
+public record TextLineMeasurement(float Width, float Height, float Baseline, float Ascent, float Descent, int Start, int End);
\ No newline at end of file
@@ -5,8 +5,31 @@ namespace TakumiSharp;
 
 public static class Takumi
 {
-  public static void LoadFont(string fontPath) => Internal.Renderer.LoadFont(fontPath);
-  public static void LoadFont(ReadOnlySpan<byte> fontData) => Internal.Renderer.LoadFont(fontData);
+  public static ulong LoadFont(string fontPath) => Internal.Renderer.LoadFont(fontPath);
+  public static ulong LoadFont(ReadOnlySpan<byte> fontData) => Internal.Renderer.LoadFont(fontData);
+  public static void UnloadFont(ulong faceId) => Internal.Renderer.UnloadFont(faceId);
+  public static void UnloadFontFamily(string family) => Internal.Renderer.UnloadFontFamily(family);
+  public static void SetLanguageFallback(string lang, string? fontFamilies) => Internal.Renderer.SetLanguageFallback(lang, fontFamilies);
+  public static void ClearFonts() => Internal.Renderer.ClearFonts();
+  public static void SaveFontCache(string cachePath) => Internal.Renderer.SaveFontCache(cachePath);
+  public static bool TryRestoreFontCache(string cachePath) => Internal.Renderer.TryRestoreFontCache(cachePath);
+  public static bool TryRestoreFontCache(string cachePath, out ulong[] faceIds) => Internal.Renderer.TryRestoreFontCache(cachePath, out faceIds);
+  public static void Reset() => Internal.Renderer.Reset();
+  public static void RegisterPainter(string name, Painter painter) => Internal.Renderer.RegisterPainter(name, painter);
+  public static bool UnregisterPainter(string name) => Internal.Renderer.UnregisterPainter(name);
+  public static void RegisterTemplate(string name, NodeKind template) => Internal.Renderer.RegisterTemplate(name, JsonSerializer.Serialize(template));
+  public static void RegisterTemplateJson(string name, string templateJson) => Internal.Renderer.RegisterTemplate(name, templateJson);
+  public static bool UnregisterTemplate(string name) => Internal.Renderer.UnregisterTemplate(name);
+
+  public static TextMeasurement MeasureText(
+    string text,
+    string? fontFamily = null,
+    float fontSize = 16f,
+    float fontWeight = 400f,
+    float letterSpacing = 0f,
+    float? lineHeight = null,
+    float? maxWidth = null) =>
+    Internal.Renderer.MeasureText(text, fontFamily, fontSize, fontWeight, letterSpacing, lineHeight, maxWidth);
 
   public static byte[] Render(
     NodeKind node,
@@ -16,9 +39,10 @@ public static class Takumi
     float devicePixelRatio = 1f,
     ImageFormat format = ImageFormat.WebP)
   {
-    string nodeJson = JsonSerializer.Serialize(node);
-    return Internal.Renderer.Render(
+    byte[] nodeJson = JsonSerializer.SerializeToUtf8Bytes(node);
+    return Internal.Renderer.RenderBytes(
       nodeJson,
+      NodeFormat.Json.ToInternalNodeFormat(),
       width: width,
       height: height,
       fontSize: fontSize,
@@ -27,4 +51,88 @@ public static class Takumi
     );
   }
 
+  /// <summary>
+  /// Parses a node tree once, so that it can be rendered at several sizes or in several
+  /// formats without parsing it again. The parsed tree must be disposed.
+  /// </summary>
+  public static ParsedNode Parse(NodeKind node) =>
+    Internal.Renderer.ParseBytes(
+      JsonSerializer.SerializeToUtf8Bytes(node),
+      NodeFormat.Json.ToInternalNodeFormat()
+    );
+
+  /// <summary>
+  /// Parses a tree already serialized as JSON once, like <see cref="RenderJson"/> accepts it.
+  /// </summary>
+  public static ParsedNode ParseJson(string json) => Internal.Renderer.Parse(json);
+
+  /// <summary>
+  /// Parses a tree read from bytes once, like <see cref="RenderBytes"/> accepts them.
+  /// </summary>
+  public static ParsedNode ParseBytes(ReadOnlySpan<byte> data, NodeFormat nodeFormat) =>
+    Internal.Renderer.ParseBytes(data, nodeFormat.ToInternalNodeFormat());
+
+  /// <summary>
+  /// Renders a tree already serialized as JSON: nodes, or a React element tree like the
+  /// ones <c>@vercel/og</c> templates serialize to.
+  /// </summary>
+  public static byte[] RenderJson(
+    string json,
+    int? width = null,
+    int? height = null,
+    float fontSize = 16f,
+    float devicePixelRatio = 1f,
+    ImageFormat format = ImageFormat.WebP) =>
+    Internal.Renderer.Render(
+      json,
+      width: width,
+      height: height,
+      fontSize: fontSize,
+      devicePixelRatio: devicePixelRatio,
+      format: format.ToInternalImageFormat()
+    );
+
+  /// <summary>
+  /// Renders a tree encoded as JSON, MessagePack or CBOR, with the same fields as the JSON
+  /// of the nodes. The bytes can be gzip or zstd compressed, which is detected from them.
+  /// </summary>
+  public static byte[] RenderBytes(
+    ReadOnlySpan<byte> data,
+    NodeFormat nodeFormat,
+    int? width = null,
+    int? height = null,
+    float fontSize = 16f,
+    float devicePixelRatio = 1f,
+    ImageFormat format = ImageFormat.WebP) =>
+    Internal.Renderer.RenderBytes(
+      data,
+      nodeFormat.ToInternalNodeFormat(),
+      width: width,
+      height: height,
+      fontSize: fontSize,
+      devicePixelRatio: devicePixelRatio,
+      format: format.ToInternalImageFormat()
+    );
+
+  /// <summary>
+  /// Renders a template registered with <see cref="RegisterTemplate(string, NodeKind)"/>. The variables
+  /// are serialized to a JSON object, so they can be a dictionary or an anonymous object.
+  /// </summary>
+  public static byte[] RenderTemplate(
+    string name,
+    object variables,
+    int? width = null,
+    int? height = null,
+    float fontSize = 16f,
+    float devicePixelRatio = 1f,
+    ImageFormat format = ImageFormat.WebP) =>
+    Internal.Renderer.RenderTemplate(
+      name,
+      JsonSerializer.Serialize(variables),
+      width: width,
+      height: height,
+      fontSize: fontSize,
+      devicePixelRatio: devicePixelRatio,
+      format: format.ToInternalImageFormat()
+    );
 }